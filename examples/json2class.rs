@@ -10,7 +10,7 @@ fn main() {
 
     let outfile = Path::new("./data/Example.out.class");
     let mut writer: ByteWriter = ByteWriter::new();
-    writer.write::<Class>(class);
+    writer.try_write(class).unwrap();
     let class_bytes: Vec<u8> = writer.into();
     fs::write(outfile, class_bytes).unwrap();
 }
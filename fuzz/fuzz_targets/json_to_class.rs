@@ -0,0 +1,15 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rusty_javap::bytecode::writer::ByteWriter;
+use rusty_javap::model::class::Class;
+
+// Any class that deserializes must also be written, or rejected, without panicking
+fuzz_target!(|data: &[u8]| {
+    if let Ok(class) = serde_json::from_slice::<Class>(data) {
+        let mut writer = ByteWriter::new();
+        if writer.try_write(class).is_ok() {
+            let _: Vec<u8> = writer.into();
+        }
+    }
+});
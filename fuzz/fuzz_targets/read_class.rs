@@ -0,0 +1,12 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rusty_javap::bytecode::reader::{ByteReader, Take};
+use rusty_javap::error::ParseError;
+use rusty_javap::model::class::Class;
+
+// Parsing must return an error, never panic, whatever the input
+fuzz_target!(|data: &[u8]| {
+    let mut reader: ByteReader = data.to_vec().into();
+    let _: Result<Class, ParseError> = reader.take();
+});
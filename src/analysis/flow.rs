@@ -0,0 +1,349 @@
+use crate::analysis::interpreter::{Frame, Interpreter};
+use crate::analysis::{AnalysisError, TypeError};
+use crate::model::attrs::code::{Code, Instruction, Label, OpcodeInfo};
+use crate::model::attrs::stack_map_table::VerificationType;
+use crate::model::attrs::Attribute;
+use crate::model::descriptor::MethodDescriptor;
+use crate::model::method::{Method, MethodAccessModifier};
+use crate::w4;
+use std::collections::{BTreeSet, HashMap};
+use std::ops::Range;
+
+/// Labels no instruction, exception range or attribute of a [Code] uses yet
+pub(crate) struct FreshLabels(w4);
+
+impl FreshLabels {
+    pub(crate) fn new(code: &Code) -> Self {
+        let used = code
+            .code
+            .iter()
+            .flat_map(|instruction| match instruction {
+                Instruction::Label { label } => vec![*label],
+                Instruction::Op(op) => op.labels(),
+            })
+            .chain(code.exception_table.iter().flat_map(|entry| entry.labels()))
+            .chain(
+                code.attributes
+                    .iter()
+                    .flat_map(|attribute| attribute.labels()),
+            );
+        FreshLabels(
+            used.map(|label| label.0.saturating_add(1))
+                .max()
+                .unwrap_or(0),
+        )
+    }
+
+    pub(crate) fn next(&mut self) -> Label {
+        let label = Label(self.0);
+        self.0 = self.0.saturating_add(1);
+        label
+    }
+}
+
+/// The method whose code is being analyzed
+pub(crate) struct MethodContext<'a> {
+    pub(crate) name: &'a str,
+    pub(crate) descriptor: &'a MethodDescriptor,
+    pub(crate) is_static: bool,
+}
+
+/// The code of [method] together with what the analyses need to know about the method itself,
+/// `None` if the method has no code
+pub(crate) fn method_code(method: &Method) -> Option<(MethodContext<'_>, &Code)> {
+    let code = method
+        .attributes
+        .iter()
+        .find_map(|attribute| match attribute {
+            Attribute::Code(code) => Some(code),
+            _ => None,
+        })?;
+    Some((
+        context(&method.name, &method.descriptor, &method.access_flags),
+        code,
+    ))
+}
+
+/// Like [method_code], with the code mutable
+pub(crate) fn method_code_mut(method: &mut Method) -> Option<(MethodContext<'_>, &mut Code)> {
+    let code = method
+        .attributes
+        .iter_mut()
+        .find_map(|attribute| match attribute {
+            Attribute::Code(code) => Some(code),
+            _ => None,
+        })?;
+    Some((
+        context(&method.name, &method.descriptor, &method.access_flags),
+        code,
+    ))
+}
+
+fn context<'a>(
+    name: &'a str,
+    descriptor: &'a MethodDescriptor,
+    access_flags: &[MethodAccessModifier],
+) -> MethodContext<'a> {
+    MethodContext {
+        name,
+        descriptor,
+        is_static: access_flags
+            .iter()
+            .any(|flag| matches!(flag, MethodAccessModifier::STATIC)),
+    }
+}
+
+impl MethodContext<'_> {
+    pub(crate) fn error(&self, instruction: Option<usize>, error: TypeError) -> AnalysisError {
+        AnalysisError {
+            method: format!("{}{}", self.name, self.descriptor.encode()),
+            instruction,
+            pc: None,
+            error,
+        }
+    }
+}
+
+/// The types inferred at every instruction of a [Code]
+pub(crate) struct Flow {
+    /// Index in [Code::code] of every op, in order; other positions count ops rather than instructions
+    pub(crate) ops: Vec<usize>,
+    /// The types before each op, `None` if no path reaches it
+    pub(crate) frames: Vec<Option<Frame>>,
+    /// Positions of the ops branches and exception handlers go to
+    pub(crate) targets: BTreeSet<usize>,
+    /// Position of the op each label in the code marks, `ops.len()` for labels at the end
+    pub(crate) labels: HashMap<Label, usize>,
+    /// The label of each `new`, which names the objects it creates;
+    /// the ones [FreshLabels] made up aren't in the code yet
+    pub(crate) new_labels: HashMap<usize, Label>,
+    /// Where each exception handler in [Code::exception_table] starts
+    pub(crate) handlers: Vec<(Range<usize>, usize)>,
+}
+
+impl Flow {
+    /// Whether [label] is placed in the code
+    pub(crate) fn is_placed(&self, label: Label) -> bool {
+        self.labels.contains_key(&label)
+    }
+}
+
+/// The ops of a [Code], where its labels are, and where its exception handlers start
+pub(crate) struct Scan<'a> {
+    /// Index in [Code::code] of every op, in order
+    pub(crate) ops: Vec<usize>,
+    pub(crate) instructions: Vec<&'a OpcodeInfo>,
+    /// Position of the op each label in the code marks, `ops.len()` for labels at the end
+    pub(crate) labels: HashMap<Label, usize>,
+    /// The label of each `new`, which names the objects it creates
+    pub(crate) new_labels: HashMap<usize, Label>,
+    /// The range each entry of [Code::exception_table] covers, and where its handler starts
+    pub(crate) handlers: Vec<(Range<usize>, usize)>,
+    /// The exception each handler starts with on the stack
+    pub(crate) catch_types: Vec<VerificationType>,
+}
+
+impl<'a> Scan<'a> {
+    /// Labels the `new`s that have no label of their own with [fresh_labels]
+    pub(crate) fn new(
+        code: &'a Code,
+        method: &MethodContext,
+        fresh_labels: &mut FreshLabels,
+    ) -> Result<Self, AnalysisError> {
+        let mut ops: Vec<usize> = vec![];
+        let mut instructions: Vec<&OpcodeInfo> = vec![];
+        let mut labels = HashMap::new();
+        let mut new_labels = HashMap::new();
+        let mut previous_label = None;
+        for (index, instruction) in code.code.iter().enumerate() {
+            match instruction {
+                Instruction::Label { label } => {
+                    labels.insert(*label, ops.len());
+                    previous_label = previous_label.or(Some(*label));
+                }
+                Instruction::Op(op) => {
+                    if let OpcodeInfo::new { .. } = op {
+                        let label = previous_label.unwrap_or_else(|| fresh_labels.next());
+                        new_labels.insert(ops.len(), label);
+                    }
+                    previous_label = None;
+                    ops.push(index);
+                    instructions.push(op);
+                }
+            }
+        }
+
+        let mut scan = Scan {
+            ops,
+            instructions,
+            labels,
+            new_labels,
+            handlers: vec![],
+            catch_types: vec![],
+        };
+        for entry in &code.exception_table {
+            let handler = scan
+                .position(entry.handler)
+                .map_err(|e| method.error(None, e))?;
+            if handler == scan.ops.len() {
+                return Err(method.error(None, TypeError::FallsOffEnd));
+            }
+            let start = scan
+                .position(entry.start)
+                .map_err(|e| method.error(None, e))?;
+            let end = scan
+                .position(entry.end)
+                .map_err(|e| method.error(None, e))?;
+            scan.handlers.push((start..end, handler));
+            scan.catch_types.push(VerificationType::Object(
+                entry
+                    .catch_type
+                    .clone()
+                    .unwrap_or_else(|| "java/lang/Throwable".to_string()),
+            ));
+        }
+        Ok(scan)
+    }
+
+    /// Position of the op [label] marks
+    pub(crate) fn position(&self, label: Label) -> Result<usize, TypeError> {
+        self.labels
+            .get(&label)
+            .copied()
+            .ok_or(TypeError::UnknownLabel(label))
+    }
+}
+
+/// Whether execution can continue with the instruction after [op]
+pub(crate) fn falls_through(op: &OpcodeInfo) -> bool {
+    use OpcodeInfo::*;
+    !matches!(
+        op,
+        goto { .. }
+            | goto_w { .. }
+            | tableswitch { .. }
+            | lookupswitch { .. }
+            | ireturn
+            | lreturn
+            | freturn
+            | dreturn
+            | areturn
+            | r#return
+            | athrow
+    )
+}
+
+/// Follows every path through [code] until the types at each instruction no longer change
+pub(crate) fn analyze(
+    code: &Code,
+    method: &MethodContext,
+    interpreter: &Interpreter,
+    fresh_labels: &mut FreshLabels,
+) -> Result<Flow, AnalysisError> {
+    let scan = Scan::new(code, method, fresh_labels)?;
+    let Scan {
+        ops,
+        instructions,
+        new_labels,
+        handlers,
+        catch_types,
+        ..
+    } = &scan;
+
+    let mut frames: Vec<Option<Frame>> = vec![None; ops.len()];
+    let mut targets = BTreeSet::new();
+    let mut worklist = vec![];
+    if !ops.is_empty() {
+        let initial = interpreter.initial_frame(method.name, method.descriptor, method.is_static);
+        flow_into(interpreter, &mut frames, &mut worklist, 0, initial)
+            .map_err(|e| method.error(Some(ops[0]), e))?;
+    }
+    while let Some(i) = worklist.pop() {
+        let error = |e| method.error(Some(ops[i]), e);
+        let op = instructions[i];
+        let Some(before) = frames[i].clone() else {
+            continue;
+        };
+        let mut after = before.clone();
+        interpreter
+            .execute(op, &mut after, new_labels.get(&i).copied())
+            .map_err(error)?;
+
+        // An exception can be thrown before or after the instruction changes the locals
+        for ((range, handler), catch_type) in handlers.iter().zip(catch_types.iter()) {
+            if range.contains(&i) {
+                targets.insert(*handler);
+                for locals in [&before.locals, &after.locals] {
+                    let incoming = Frame {
+                        locals: locals.clone(),
+                        stack: vec![catch_type.clone()],
+                    };
+                    flow_into(interpreter, &mut frames, &mut worklist, *handler, incoming)
+                        .map_err(|e| method.error(Some(ops[*handler]), e))?;
+                }
+            }
+        }
+
+        for label in op.labels() {
+            let target = scan.position(label).map_err(error)?;
+            if target == ops.len() {
+                return Err(error(TypeError::FallsOffEnd));
+            }
+            targets.insert(target);
+            flow_into(
+                interpreter,
+                &mut frames,
+                &mut worklist,
+                target,
+                after.clone(),
+            )
+            .map_err(|e| method.error(Some(ops[target]), e))?;
+        }
+        if falls_through(op) {
+            if i + 1 == ops.len() {
+                return Err(error(TypeError::FallsOffEnd));
+            }
+            flow_into(interpreter, &mut frames, &mut worklist, i + 1, after)
+                .map_err(|e| method.error(Some(ops[i + 1]), e))?;
+        }
+    }
+
+    let Scan {
+        ops,
+        labels,
+        new_labels,
+        handlers,
+        ..
+    } = scan;
+    Ok(Flow {
+        ops,
+        frames,
+        targets,
+        labels,
+        new_labels,
+        handlers,
+    })
+}
+
+/// Merges [incoming] into the types before the op at [to], revisiting it if they changed
+fn flow_into(
+    interpreter: &Interpreter,
+    frames: &mut [Option<Frame>],
+    worklist: &mut Vec<usize>,
+    to: usize,
+    incoming: Frame,
+) -> Result<(), TypeError> {
+    let merged = match &frames[to] {
+        None => incoming,
+        Some(frame) => {
+            let merged = interpreter.merge(frame, &incoming)?;
+            if merged == *frame {
+                return Ok(());
+            }
+            merged
+        }
+    };
+    frames[to] = Some(merged);
+    worklist.push(to);
+    Ok(())
+}
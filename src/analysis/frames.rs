@@ -0,0 +1,273 @@
+//! Computes `StackMapTable`s from scratch, like ASM's `COMPUTE_FRAMES`
+
+use crate::analysis::flow::{analyze, method_code_mut, FreshLabels};
+use crate::analysis::hierarchy::ClassHierarchy;
+use crate::analysis::interpreter::{Frame, Interpreter};
+use crate::analysis::AnalysisError;
+use crate::constant_pool::ConstantPool;
+use crate::model::attrs::code::exception_table::ExceptionTableElement;
+use crate::model::attrs::code::{Instruction, Label, OpcodeInfo};
+use crate::model::attrs::stack_map_table::StackMapFrame::*;
+use crate::model::attrs::stack_map_table::{StackMapFrame, StackMapTable, VerificationType};
+use crate::model::attrs::Attribute;
+use crate::model::class::Class;
+use crate::model::method::Method;
+use crate::w1;
+use std::collections::{BTreeMap, HashMap};
+
+/// Recomputes the `StackMapTable` of every method of [class], see [compute_method_frames].
+/// Stops at the first method that can't be analyzed, leaving it and the methods after it as they were.
+///
+/// Where the class' own type meets another one, [hierarchy] has to know the class too,
+/// e.g. by [adding](crate::analysis::hierarchy::Classes::add) it.
+pub fn compute_frames(
+    class: &mut Class,
+    hierarchy: &dyn ClassHierarchy,
+) -> Result<(), AnalysisError> {
+    for method in &mut class.methods {
+        compute_method_frames_in(
+            method,
+            &class.this_class,
+            hierarchy,
+            class.constant_pool.as_ref(),
+        )?;
+    }
+    Ok(())
+}
+
+/// Replaces the `StackMapTable` of [method]'s code with frames inferred from its instructions.
+/// Branch targets and exception handlers get a frame, and so does each `new` whose
+/// uninitialized object is in a frame; labels are added for the ones that don't have one.
+///
+/// Like ASM, unreachable instructions are replaced with `nop`s followed by an `athrow`
+/// (whose frame only holds a `java/lang/Throwable`), and taken out of exception ranges,
+/// since there are no types to give them a frame with. `max_stack` is raised to make room for the exception.
+///
+/// The code has to place every label its branches and exception ranges point at,
+/// and can't use `jsr` or `ret`.
+pub fn compute_method_frames(
+    method: &mut Method,
+    this_class: &str,
+    hierarchy: &dyn ClassHierarchy,
+) -> Result<(), AnalysisError> {
+    compute_method_frames_in(method, this_class, hierarchy, None)
+}
+
+/// [compute_method_frames] for a method of a class with [constant_pool], which errors are located with
+pub(crate) fn compute_method_frames_in(
+    method: &mut Method,
+    this_class: &str,
+    hierarchy: &dyn ClassHierarchy,
+    constant_pool: Option<&ConstantPool>,
+) -> Result<(), AnalysisError> {
+    let Some((context, code)) = method_code_mut(method) else {
+        return Ok(());
+    };
+    let interpreter = Interpreter {
+        hierarchy,
+        this_class,
+        strict: false,
+    };
+    let mut fresh_labels = FreshLabels::new(code);
+    let flow = analyze(code, &context, &interpreter, &mut fresh_labels)
+        .map_err(|e| e.locate(code, constant_pool))?;
+    let initial = interpreter.initial_frame(context.name, context.descriptor, context.is_static);
+
+    // The first label at each position, and the ones that have to be added
+    let mut labels: HashMap<usize, Label> = HashMap::new();
+    for (&label, &position) in &flow.labels {
+        let first = labels.entry(position).or_insert(label);
+        *first = label.min(*first);
+    }
+    let mut added: BTreeMap<usize, Vec<Label>> = BTreeMap::new();
+    let mut label_at = |position: usize| {
+        *labels.entry(position).or_insert_with(|| {
+            let label = fresh_labels.next();
+            added.entry(position).or_default().push(label);
+            label
+        })
+    };
+
+    let mut frames: BTreeMap<usize, Frame> = flow
+        .targets
+        .iter()
+        .filter_map(|&target| Some((target, flow.frames[target].clone()?)))
+        .collect();
+    let dead: Vec<bool> = flow.frames.iter().map(Option::is_none).collect();
+    let mut replaced: HashMap<usize, OpcodeInfo> = HashMap::new();
+    let mut position = 0;
+    while position < dead.len() {
+        if !dead[position] {
+            position += 1;
+            continue;
+        }
+        let start = position;
+        while position < dead.len() && dead[position] {
+            replaced.insert(flow.ops[position], OpcodeInfo::nop);
+            position += 1;
+        }
+        replaced.insert(flow.ops[position - 1], OpcodeInfo::athrow);
+        let throwable = VerificationType::Object("java/lang/Throwable".to_string());
+        frames.insert(
+            start,
+            Frame {
+                locals: vec![],
+                stack: vec![throwable],
+            },
+        );
+    }
+
+    // The `athrow`s need the stack to hold the exception
+    if !replaced.is_empty() {
+        code.max_stack = code.max_stack.max(1);
+    }
+
+    let mut exception_table = Vec::with_capacity(code.exception_table.len());
+    for (entry, (range, _)) in code.exception_table.drain(..).zip(&flow.handlers) {
+        if !range.clone().any(|position| dead[position]) {
+            exception_table.push(entry);
+            continue;
+        }
+        let mut start = None;
+        for position in range.clone().chain([range.end]) {
+            let live = position < range.end && !dead[position];
+            match start {
+                None if live => start = Some(position),
+                Some(from) if !live => {
+                    exception_table.push(ExceptionTableElement {
+                        start: label_at(from),
+                        end: label_at(position),
+                        handler: entry.handler,
+                        catch_type: entry.catch_type.clone(),
+                    });
+                    start = None;
+                }
+                _ => {}
+            }
+        }
+    }
+    code.exception_table = exception_table;
+
+    let mut stack_map_table: StackMapTable = vec![];
+    let mut previous = compress_locals(&initial.locals);
+    for (position, frame) in frames {
+        let label = label_at(position);
+        let locals = compress_locals(&frame.locals);
+        let stack = compress(&frame.stack);
+        stack_map_table.push(frame_between(&previous, label, locals.clone(), stack));
+        previous = locals;
+    }
+
+    // Objects are named after the `new` that created them
+    let new_positions: HashMap<Label, usize> = flow
+        .new_labels
+        .iter()
+        .filter(|(_, label)| !flow.is_placed(**label))
+        .map(|(position, label)| (*label, *position))
+        .collect();
+    for frame in &stack_map_table {
+        for label in frame.labels() {
+            if let Some(&position) = new_positions.get(&label) {
+                let labels = added.entry(position).or_default();
+                if !labels.contains(&label) {
+                    labels.push(label);
+                }
+            }
+        }
+    }
+
+    let mut instructions = Vec::with_capacity(code.code.len() + added.len());
+    let mut position = 0;
+    for (index, instruction) in code.code.drain(..).enumerate() {
+        if let Instruction::Op(op) = instruction {
+            for label in added.remove(&position).into_iter().flatten() {
+                instructions.push(label.into());
+            }
+            instructions.push(replaced.remove(&index).unwrap_or(op).into());
+            position += 1;
+        } else {
+            instructions.push(instruction);
+        }
+    }
+    for label in added.into_values().flatten() {
+        instructions.push(label.into());
+    }
+    code.code = instructions;
+
+    // Written where the old table was, if there was one
+    let is_stack_map_table =
+        |attribute: &Attribute| matches!(attribute, Attribute::StackMapTable(_));
+    let index = code.attributes.iter().position(is_stack_map_table);
+    code.attributes
+        .retain(|attribute| !is_stack_map_table(attribute));
+    if !stack_map_table.is_empty() {
+        let index = index.unwrap_or(code.attributes.len());
+        code.attributes
+            .insert(index, Attribute::StackMapTable(stack_map_table));
+    }
+    Ok(())
+}
+
+/// One entry for each long or double
+pub(crate) fn compress(types: &[VerificationType]) -> Vec<VerificationType> {
+    let mut compressed = vec![];
+    let mut types = types.iter();
+    while let Some(value) = types.next() {
+        if matches!(value, VerificationType::Long | VerificationType::Double) {
+            types.next();
+        }
+        compressed.push(value.clone());
+    }
+    compressed
+}
+
+/// Locals after the last one that's set are implicitly [VerificationType::Top]
+fn compress_locals(locals: &[VerificationType]) -> Vec<VerificationType> {
+    let mut compressed = compress(locals);
+    while compressed.last() == Some(&VerificationType::Top) {
+        compressed.pop();
+    }
+    compressed
+}
+
+/// The most compact frame that changes the [previous] locals to [locals] and has [stack]
+fn frame_between(
+    previous: &[VerificationType],
+    label: Label,
+    locals: Vec<VerificationType>,
+    mut stack: Vec<VerificationType>,
+) -> StackMapFrame {
+    let same_locals = locals == previous;
+    if same_locals && stack.is_empty() {
+        same_frame { label }
+    } else if same_locals && stack.len() == 1 {
+        same_locals_1_stack_item_frame {
+            label,
+            stack: stack.remove(0),
+        }
+    } else if stack.is_empty()
+        && locals.len() < previous.len()
+        && previous.len() - locals.len() <= 3
+        && previous.starts_with(&locals)
+    {
+        chop_frame {
+            label,
+            chopped: (previous.len() - locals.len()) as w1,
+        }
+    } else if stack.is_empty()
+        && locals.len() > previous.len()
+        && locals.len() - previous.len() <= 3
+        && locals.starts_with(previous)
+    {
+        append_frame {
+            label,
+            locals: locals[previous.len()..].to_vec(),
+        }
+    } else {
+        full_frame {
+            label,
+            locals,
+            stack,
+        }
+    }
+}
@@ -0,0 +1,110 @@
+use crate::model::class::{Class, ClassAccessModifier};
+use std::collections::{HashMap, HashSet};
+
+const OBJECT: &str = "java/lang/Object";
+
+/// What the analyses need to know about classes other than the one being analyzed,
+/// e.g. to find the common superclass of two types where control flow merges
+pub trait ClassHierarchy {
+    /// The superclass of [class], or `None` for `java/lang/Object`
+    fn super_class(&self, class: &str) -> Option<String>;
+
+    fn is_interface(&self, class: &str) -> bool;
+
+    /// The most specific class both [a] and [b] extend.
+    /// Like the JVM's verifier, this treats interfaces as `java/lang/Object`.
+    ///
+    ///```rust
+    /// use rusty_javap::analysis::hierarchy::{ClassHierarchy, Classes};
+    /// let mut classes = Classes::new();
+    /// classes.insert("java/lang/Integer", Some("java/lang/Number"), false);
+    /// classes.insert("java/lang/Long", Some("java/lang/Number"), false);
+    /// assert_eq!(classes.common_super_class("java/lang/Integer", "java/lang/Long"), "java/lang/Number");
+    /// assert_eq!(classes.common_super_class("java/lang/Integer", "java/lang/String"), "java/lang/Object");
+    ///```
+    fn common_super_class(&self, a: &str, b: &str) -> String {
+        if a == b {
+            return a.to_string();
+        }
+        if self.is_interface(a) || self.is_interface(b) {
+            return OBJECT.to_string();
+        }
+        let supers_of_a: HashSet<String> = self.super_classes(a).into_iter().collect();
+        self.super_classes(b)
+            .into_iter()
+            .find(|class| supers_of_a.contains(class))
+            .unwrap_or_else(|| OBJECT.to_string())
+    }
+
+    /// Whether a value of class [from] can be used where [to] is expected.
+    /// Like the JVM's verifier, this accepts any class where an interface is expected.
+    fn is_assignable(&self, from: &str, to: &str) -> bool {
+        to == OBJECT
+            || self.is_interface(to)
+            || self.super_classes(from).iter().any(|class| class == to)
+    }
+
+    /// [class] followed by its superclasses, stopping early if the chain has a cycle
+    fn super_classes(&self, class: &str) -> Vec<String> {
+        let mut classes = vec![class.to_string()];
+        while let Some(super_class) = self.super_class(classes.last().unwrap()) {
+            if classes.contains(&super_class) {
+                break;
+            }
+            classes.push(super_class);
+        }
+        classes
+    }
+}
+
+/// A [ClassHierarchy] of the classes added to it.
+/// Classes it doesn't know about are taken to be classes that directly extend `java/lang/Object`.
+#[derive(Debug, Clone, Default)]
+pub struct Classes {
+    /// The superclass and whether it's an interface, by class name
+    classes: HashMap<String, (Option<String>, bool)>,
+}
+
+impl Classes {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(
+        &mut self,
+        class: impl Into<String>,
+        super_class: Option<impl Into<String>>,
+        is_interface: bool,
+    ) {
+        self.classes
+            .insert(class.into(), (super_class.map(Into::into), is_interface));
+    }
+
+    pub fn add(&mut self, class: &Class) {
+        let is_interface = class
+            .access_flags
+            .iter()
+            .any(|flag| matches!(flag, ClassAccessModifier::INTERFACE));
+        self.insert(
+            class.this_class.clone(),
+            class.super_class.clone(),
+            is_interface,
+        );
+    }
+}
+
+impl ClassHierarchy for Classes {
+    fn super_class(&self, class: &str) -> Option<String> {
+        match self.classes.get(class) {
+            Some((super_class, _)) => super_class.clone(),
+            None if class == OBJECT => None,
+            None => Some(OBJECT.to_string()),
+        }
+    }
+
+    fn is_interface(&self, class: &str) -> bool {
+        self.classes
+            .get(class)
+            .is_some_and(|(_, is_interface)| *is_interface)
+    }
+}
@@ -0,0 +1,765 @@
+use crate::analysis::hierarchy::ClassHierarchy;
+use crate::analysis::TypeError;
+use crate::model::attrs::code::{Label, OpcodeInfo, WideInstruction};
+use crate::model::attrs::stack_map_table::VerificationType::{self, *};
+use crate::model::constant::Loadable;
+use crate::model::descriptor::{BaseType, FieldType, MethodDescriptor};
+
+const OBJECT: &str = "java/lang/Object";
+
+/// The types of the locals and the operand stack before an instruction.
+/// Unlike in a [StackMapFrame](crate::model::attrs::stack_map_table::StackMapFrame),
+/// longs and doubles take two entries, the second of which is [Top].
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Frame {
+    pub(crate) locals: Vec<VerificationType>,
+    pub(crate) stack: Vec<VerificationType>,
+}
+
+/// What an error says an instruction expected instead of [found]
+pub(crate) fn describe(expected: &VerificationType) -> String {
+    match expected {
+        Integer => "int".to_string(),
+        Float => "float".to_string(),
+        Long => "long".to_string(),
+        Double => "double".to_string(),
+        Object(class) => class.clone(),
+        other => format!("{:?}", other),
+    }
+}
+
+fn is_wide(value: &VerificationType) -> bool {
+    matches!(value, Long | Double)
+}
+
+fn is_reference(value: &VerificationType) -> bool {
+    matches!(
+        value,
+        Null | Object(_) | UninitializedThis | Uninitialized(_)
+    )
+}
+
+/// The field descriptor of a class name, which is itself for array classes
+fn descriptor_of(class: &str) -> String {
+    if class.starts_with('[') {
+        class.to_string()
+    } else {
+        format!("L{};", class)
+    }
+}
+
+/// The class name of a reference type's field descriptor, `None` for primitive types
+fn class_of(descriptor: &str) -> Option<&str> {
+    if descriptor.starts_with('[') {
+        Some(descriptor)
+    } else {
+        descriptor.strip_prefix('L')?.strip_suffix(';')
+    }
+}
+
+/// The type of a value of [field_type]; booleans, bytes, chars and shorts are ints
+pub(crate) fn value_type(field_type: &FieldType) -> VerificationType {
+    match field_type {
+        FieldType::Base(BaseType::Float) => Float,
+        FieldType::Base(BaseType::Long) => Long,
+        FieldType::Base(BaseType::Double) => Double,
+        FieldType::Base(_) => Integer,
+        FieldType::Object(class) => Object(class.clone()),
+        array @ FieldType::Array(_) => Object(array.encode()),
+    }
+}
+
+/// The type of a value of a field descriptor
+fn field_type(descriptor: &str) -> Result<VerificationType, TypeError> {
+    FieldType::parse(descriptor)
+        .map(|field_type| value_type(&field_type))
+        .map_err(|_| TypeError::InvalidDescriptor(descriptor.to_string()))
+}
+
+/// The argument types and return type (`None` for `void`) of a method
+pub(crate) fn method_types(
+    descriptor: &MethodDescriptor,
+) -> (Vec<VerificationType>, Option<VerificationType>) {
+    (
+        descriptor.parameters.iter().map(value_type).collect(),
+        descriptor.return_type.as_ref().map(value_type),
+    )
+}
+
+fn loadable_type(constant: &Loadable) -> VerificationType {
+    match constant {
+        Loadable::Integer(_) => Integer,
+        Loadable::Float(_) => Float,
+        Loadable::Long(_) => Long,
+        Loadable::Double(_) => Double,
+        Loadable::String(_) => Object("java/lang/String".to_string()),
+        Loadable::Class(_) => Object("java/lang/Class".to_string()),
+        Loadable::MethodType(_) => Object("java/lang/invoke/MethodType".to_string()),
+        Loadable::MethodHandle(_) => Object("java/lang/invoke/MethodHandle".to_string()),
+        Loadable::Dynamic(dynamic) => value_type(&dynamic.descriptor),
+    }
+}
+
+/// The type of an element of [array], which has to hold references
+fn reference_element(array: String) -> Result<VerificationType, TypeError> {
+    match field_type(&array[1..])? {
+        element if is_reference(&element) => Ok(element),
+        _ => Err(TypeError::WrongStackType {
+            expected: "array of references".to_string(),
+            found: Object(array),
+        }),
+    }
+}
+
+impl Frame {
+    fn push(&mut self, value: VerificationType) {
+        let wide = is_wide(&value);
+        self.stack.push(value);
+        if wide {
+            self.stack.push(Top);
+        }
+    }
+
+    fn pop_slot(&mut self) -> Result<VerificationType, TypeError> {
+        self.stack.pop().ok_or(TypeError::StackUnderflow)
+    }
+
+    /// Pops a value of exactly the primitive type [expected]
+    fn pop(&mut self, expected: VerificationType) -> Result<(), TypeError> {
+        let wrong = |found| TypeError::WrongStackType {
+            expected: describe(&expected),
+            found,
+        };
+        if is_wide(&expected) {
+            match self.pop_slot()? {
+                Top => {}
+                found => return Err(wrong(found)),
+            }
+        }
+        match self.pop_slot()? {
+            found if found == expected => Ok(()),
+            found => Err(wrong(found)),
+        }
+    }
+
+    fn pop_reference(&mut self) -> Result<VerificationType, TypeError> {
+        match self.pop_slot()? {
+            found if is_reference(&found) => Ok(found),
+            found => Err(TypeError::WrongStackType {
+                expected: "reference".to_string(),
+                found,
+            }),
+        }
+    }
+
+    /// Pops a value of field type [expected]
+    fn pop_value(&mut self, expected: &VerificationType) -> Result<(), TypeError> {
+        match expected {
+            Object(_) => self.pop_reference().map(|_| ()),
+            _ => self.pop(expected.clone()),
+        }
+    }
+
+    /// Pops an array, returning its type or `None` if it's `null`
+    fn pop_array(&mut self) -> Result<Option<String>, TypeError> {
+        match self.pop_slot()? {
+            Null => Ok(None),
+            Object(class) if class.starts_with('[') => Ok(Some(class)),
+            found => Err(TypeError::WrongStackType {
+                expected: "array".to_string(),
+                found,
+            }),
+        }
+    }
+
+    /// Fails unless the stack can be cut below its top [depth] slots without splitting a long or double
+    fn check_split(&self, depth: usize) -> Result<(), TypeError> {
+        let length = self.stack.len();
+        if depth > length {
+            return Err(TypeError::StackUnderflow);
+        }
+        let at = length - depth;
+        if at > 0 && at < length && self.stack[at] == Top && is_wide(&self.stack[at - 1]) {
+            return Err(TypeError::SplitsWideValue);
+        }
+        Ok(())
+    }
+
+    fn pop_slots(&mut self, count: usize) -> Result<(), TypeError> {
+        self.check_split(count)?;
+        self.stack.truncate(self.stack.len() - count);
+        Ok(())
+    }
+
+    /// Copies the top [count] slots below the [depth] slots under them
+    fn dup(&mut self, count: usize, depth: usize) -> Result<(), TypeError> {
+        self.check_split(count)?;
+        self.check_split(count + depth)?;
+        let length = self.stack.len();
+        let copied = self.stack[length - count..].to_vec();
+        let at = length - count - depth;
+        self.stack.splice(at..at, copied);
+        Ok(())
+    }
+
+    fn swap(&mut self) -> Result<(), TypeError> {
+        self.check_split(1)?;
+        self.check_split(2)?;
+        let length = self.stack.len();
+        self.stack.swap(length - 1, length - 2);
+        Ok(())
+    }
+
+    pub(crate) fn local(&self, index: usize) -> VerificationType {
+        self.locals.get(index).cloned().unwrap_or(Top)
+    }
+
+    fn check_local(&self, index: usize, expected: VerificationType) -> Result<(), TypeError> {
+        match self.local(index) {
+            found if found == expected => Ok(()),
+            found => Err(TypeError::WrongLocalType {
+                index,
+                expected: describe(&expected),
+                found,
+            }),
+        }
+    }
+
+    fn load(&mut self, index: usize, expected: VerificationType) -> Result<(), TypeError> {
+        self.check_local(index, expected.clone())?;
+        self.push(expected);
+        Ok(())
+    }
+
+    fn load_reference(&mut self, index: usize) -> Result<(), TypeError> {
+        match self.local(index) {
+            found if is_reference(&found) => {
+                self.push(found);
+                Ok(())
+            }
+            found => Err(TypeError::WrongLocalType {
+                index,
+                expected: "reference".to_string(),
+                found,
+            }),
+        }
+    }
+
+    /// Stores [value] in local [index], which invalidates a long or double it overwrites half of
+    fn store(&mut self, index: usize, value: VerificationType) {
+        let end = index + if is_wide(&value) { 2 } else { 1 };
+        if self.locals.len() < end {
+            self.locals.resize(end, Top);
+        }
+        if index > 0 && is_wide(&self.locals[index - 1]) {
+            self.locals[index - 1] = Top;
+        }
+        if end > index + 1 {
+            self.locals[index + 1] = Top;
+        }
+        self.locals[index] = value;
+    }
+
+    fn pop_store(&mut self, index: usize, value: VerificationType) -> Result<(), TypeError> {
+        self.pop(value.clone())?;
+        self.store(index, value);
+        Ok(())
+    }
+
+    fn pop_store_reference(&mut self, index: usize) -> Result<(), TypeError> {
+        let value = self.pop_reference()?;
+        self.store(index, value);
+        Ok(())
+    }
+
+    /// Pops [operands], then pushes [result] if there is one
+    fn compute(
+        &mut self,
+        operands: &[VerificationType],
+        result: Option<VerificationType>,
+    ) -> Result<(), TypeError> {
+        for operand in operands.iter().rev() {
+            self.pop(operand.clone())?;
+        }
+        if let Some(result) = result {
+            self.push(result);
+        }
+        Ok(())
+    }
+
+    /// Replaces every occurrence of an uninitialized object, once its constructor has been called
+    fn initialize(&mut self, uninitialized: &VerificationType, initialized: VerificationType) {
+        for value in self.locals.iter_mut().chain(self.stack.iter_mut()) {
+            if value == uninitialized {
+                *value = initialized.clone();
+            }
+        }
+    }
+}
+
+/// Infers the types after each instruction from the types before it
+pub(crate) struct Interpreter<'a> {
+    pub(crate) hierarchy: &'a dyn ClassHierarchy,
+    /// The class whose methods are being analyzed
+    pub(crate) this_class: &'a str,
+    /// Whether references have to be of the classes the instructions take, as the verifier checks,
+    /// rather than just be references
+    pub(crate) strict: bool,
+}
+
+impl Interpreter<'_> {
+    /// The types of the locals when a method is entered
+    pub(crate) fn initial_frame(
+        &self,
+        name: &str,
+        descriptor: &MethodDescriptor,
+        is_static: bool,
+    ) -> Frame {
+        let mut frame = Frame {
+            locals: vec![],
+            stack: vec![],
+        };
+        if !is_static {
+            let this = if name == "<init>" && self.this_class != OBJECT {
+                UninitializedThis
+            } else {
+                Object(self.this_class.to_string())
+            };
+            frame.locals.push(this);
+        }
+        for argument in method_types(descriptor).0 {
+            let index = frame.locals.len();
+            frame.store(index, argument);
+        }
+        frame
+    }
+
+    /// The most specific type both [a] and [b] can be used as, [Top] if there is none
+    fn merge_type(&self, a: &VerificationType, b: &VerificationType) -> VerificationType {
+        match (a, b) {
+            _ if a == b => a.clone(),
+            (Null, Object(_)) => b.clone(),
+            (Object(_), Null) => a.clone(),
+            (Object(a), Object(b)) => Object(self.common_super_class(a, b)),
+            _ => Top,
+        }
+    }
+
+    /// Like [ClassHierarchy::common_super_class], but also for array classes
+    fn common_super_class(&self, a: &str, b: &str) -> String {
+        if a == b {
+            return a.to_string();
+        }
+        match (a.strip_prefix('['), b.strip_prefix('[')) {
+            (None, None) => self.hierarchy.common_super_class(a, b),
+            (Some(a), Some(b)) => match (class_of(a), class_of(b)) {
+                (Some(a), Some(b)) => format!("[{}", descriptor_of(&self.common_super_class(a, b))),
+                _ => OBJECT.to_string(),
+            },
+            _ => OBJECT.to_string(),
+        }
+    }
+
+    /// Whether a value of type [from] can be used where [to] is expected
+    pub(crate) fn is_assignable(&self, from: &VerificationType, to: &VerificationType) -> bool {
+        match (from, to) {
+            _ if from == to => true,
+            (_, Top) | (Null, Object(_)) => true,
+            (Object(from), Object(to)) => self.is_class_assignable(from, to),
+            _ => false,
+        }
+    }
+
+    /// Like [ClassHierarchy::is_assignable], but also for array classes
+    fn is_class_assignable(&self, from: &str, to: &str) -> bool {
+        if from == to || to == OBJECT {
+            return true;
+        }
+        match (from.strip_prefix('['), to.strip_prefix('[')) {
+            (None, None) => self.hierarchy.is_assignable(from, to),
+            (Some(from), Some(to)) => match (class_of(from), class_of(to)) {
+                (Some(from), Some(to)) => self.is_class_assignable(from, to),
+                _ => from == to,
+            },
+            (Some(_), None) => to == "java/lang/Cloneable" || to == "java/io/Serializable",
+            (None, Some(_)) => false,
+        }
+    }
+
+    /// Pops a value of field type [expected]; strictly, a reference has to be assignable to it
+    fn pop_value(&self, frame: &mut Frame, expected: &VerificationType) -> Result<(), TypeError> {
+        if !self.strict || !is_reference(expected) {
+            return frame.pop_value(expected);
+        }
+        match frame.pop_slot()? {
+            found if self.is_assignable(&found, expected) => Ok(()),
+            found => Err(TypeError::WrongStackType {
+                expected: describe(expected),
+                found,
+            }),
+        }
+    }
+
+    /// Pops an index and an array, which strictly has to be one of [arrays] or `null`
+    fn pop_element(&self, frame: &mut Frame, arrays: &[&str]) -> Result<(), TypeError> {
+        frame.pop(Integer)?;
+        match frame.pop_array()? {
+            Some(array) if self.strict && !arrays.contains(&array.as_str()) => {
+                Err(TypeError::WrongStackType {
+                    expected: arrays.join(" or "),
+                    found: Object(array),
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Pops an index and one of [arrays], then pushes an [element]
+    fn array_load(
+        &self,
+        frame: &mut Frame,
+        element: VerificationType,
+        arrays: &[&str],
+    ) -> Result<(), TypeError> {
+        self.pop_element(frame, arrays)?;
+        frame.push(element);
+        Ok(())
+    }
+
+    /// Pops an [element], an index and one of [arrays]
+    fn array_store(
+        &self,
+        frame: &mut Frame,
+        element: VerificationType,
+        arrays: &[&str],
+    ) -> Result<(), TypeError> {
+        self.pop_value(frame, &element)?;
+        self.pop_element(frame, arrays)
+    }
+
+    /// The types that hold on both paths leading to an instruction
+    pub(crate) fn merge(&self, a: &Frame, b: &Frame) -> Result<Frame, TypeError> {
+        if a.stack.len() != b.stack.len() {
+            return Err(TypeError::StackHeightMismatch {
+                expected: a.stack.len(),
+                found: b.stack.len(),
+            });
+        }
+        let locals = (0..a.locals.len().max(b.locals.len()))
+            .map(|index| self.merge_type(&a.local(index), &b.local(index)))
+            .collect();
+        let stack = a
+            .stack
+            .iter()
+            .zip(&b.stack)
+            .map(|(a, b)| self.merge_type(a, b))
+            .collect();
+        Ok(Frame { locals, stack })
+    }
+
+    /// Pops the arguments of the method [class].[name], and its receiver if it [has_receiver],
+    /// then pushes what it returns
+    fn invoke(
+        &self,
+        frame: &mut Frame,
+        name: &str,
+        descriptor: &MethodDescriptor,
+        has_receiver: bool,
+        class: &str,
+    ) -> Result<(), TypeError> {
+        let (arguments, returned) = method_types(descriptor);
+        for argument in arguments.iter().rev() {
+            self.pop_value(frame, argument)?;
+        }
+        if has_receiver {
+            if name != "<init>" {
+                self.pop_value(frame, &Object(class.to_string()))?;
+            } else {
+                let receiver = frame.pop_reference()?;
+                let initialized = match receiver {
+                    UninitializedThis => Object(self.this_class.to_string()),
+                    Uninitialized(_) => Object(class.to_string()),
+                    found => {
+                        return Err(TypeError::WrongStackType {
+                            expected: "uninitialized object".to_string(),
+                            found,
+                        })
+                    }
+                };
+                frame.initialize(&receiver, initialized);
+            }
+        }
+        if let Some(returned) = returned {
+            frame.push(returned);
+        }
+        Ok(())
+    }
+
+    /// Updates [frame] to the types after [op].
+    /// [label] marks the instruction, which is what `new` names the object it creates after.
+    pub(crate) fn execute(
+        &self,
+        op: &OpcodeInfo,
+        frame: &mut Frame,
+        label: Option<Label>,
+    ) -> Result<(), TypeError> {
+        use OpcodeInfo::*;
+        match op {
+            nop | goto { .. } | goto_w { .. } | r#return => {}
+            jsr { .. } | jsr_w { .. } | ret { .. } => return Err(TypeError::Subroutine),
+
+            aconst_null => frame.push(Null),
+            iconst_m1
+            | iconst_0
+            | iconst_1
+            | iconst_2
+            | iconst_3
+            | iconst_4
+            | iconst_5
+            | bipush { .. }
+            | sipush { .. } => frame.push(Integer),
+            lconst_0 | lconst_1 => frame.push(Long),
+            fconst_0 | fconst_1 | fconst_2 => frame.push(Float),
+            dconst_0 | dconst_1 => frame.push(Double),
+            ldc { constant } | ldc_w { constant } | ldc2_w { constant } => {
+                frame.push(loadable_type(constant))
+            }
+
+            iload { index } => frame.load(*index as usize, Integer)?,
+            lload { index } => frame.load(*index as usize, Long)?,
+            fload { index } => frame.load(*index as usize, Float)?,
+            dload { index } => frame.load(*index as usize, Double)?,
+            aload { index } => frame.load_reference(*index as usize)?,
+            iload_0 => frame.load(0, Integer)?,
+            iload_1 => frame.load(1, Integer)?,
+            iload_2 => frame.load(2, Integer)?,
+            iload_3 => frame.load(3, Integer)?,
+            lload_0 => frame.load(0, Long)?,
+            lload_1 => frame.load(1, Long)?,
+            lload_2 => frame.load(2, Long)?,
+            lload_3 => frame.load(3, Long)?,
+            fload_0 => frame.load(0, Float)?,
+            fload_1 => frame.load(1, Float)?,
+            fload_2 => frame.load(2, Float)?,
+            fload_3 => frame.load(3, Float)?,
+            dload_0 => frame.load(0, Double)?,
+            dload_1 => frame.load(1, Double)?,
+            dload_2 => frame.load(2, Double)?,
+            dload_3 => frame.load(3, Double)?,
+            aload_0 => frame.load_reference(0)?,
+            aload_1 => frame.load_reference(1)?,
+            aload_2 => frame.load_reference(2)?,
+            aload_3 => frame.load_reference(3)?,
+
+            istore { index } => frame.pop_store(*index as usize, Integer)?,
+            lstore { index } => frame.pop_store(*index as usize, Long)?,
+            fstore { index } => frame.pop_store(*index as usize, Float)?,
+            dstore { index } => frame.pop_store(*index as usize, Double)?,
+            astore { index } => frame.pop_store_reference(*index as usize)?,
+            istore_0 => frame.pop_store(0, Integer)?,
+            istore_1 => frame.pop_store(1, Integer)?,
+            istore_2 => frame.pop_store(2, Integer)?,
+            istore_3 => frame.pop_store(3, Integer)?,
+            lstore_0 => frame.pop_store(0, Long)?,
+            lstore_1 => frame.pop_store(1, Long)?,
+            lstore_2 => frame.pop_store(2, Long)?,
+            lstore_3 => frame.pop_store(3, Long)?,
+            fstore_0 => frame.pop_store(0, Float)?,
+            fstore_1 => frame.pop_store(1, Float)?,
+            fstore_2 => frame.pop_store(2, Float)?,
+            fstore_3 => frame.pop_store(3, Float)?,
+            dstore_0 => frame.pop_store(0, Double)?,
+            dstore_1 => frame.pop_store(1, Double)?,
+            dstore_2 => frame.pop_store(2, Double)?,
+            dstore_3 => frame.pop_store(3, Double)?,
+            astore_0 => frame.pop_store_reference(0)?,
+            astore_1 => frame.pop_store_reference(1)?,
+            astore_2 => frame.pop_store_reference(2)?,
+            astore_3 => frame.pop_store_reference(3)?,
+            iinc { index, .. } => frame.check_local(*index as usize, Integer)?,
+            wide { instruction } => match instruction {
+                WideInstruction::iload { index } => frame.load(*index as usize, Integer)?,
+                WideInstruction::lload { index } => frame.load(*index as usize, Long)?,
+                WideInstruction::fload { index } => frame.load(*index as usize, Float)?,
+                WideInstruction::dload { index } => frame.load(*index as usize, Double)?,
+                WideInstruction::aload { index } => frame.load_reference(*index as usize)?,
+                WideInstruction::istore { index } => frame.pop_store(*index as usize, Integer)?,
+                WideInstruction::lstore { index } => frame.pop_store(*index as usize, Long)?,
+                WideInstruction::fstore { index } => frame.pop_store(*index as usize, Float)?,
+                WideInstruction::dstore { index } => frame.pop_store(*index as usize, Double)?,
+                WideInstruction::astore { index } => frame.pop_store_reference(*index as usize)?,
+                WideInstruction::iinc { index, .. } => {
+                    frame.check_local(*index as usize, Integer)?
+                }
+                WideInstruction::ret { .. } => return Err(TypeError::Subroutine),
+            },
+
+            iaload => self.array_load(frame, Integer, &["[I"])?,
+            baload => self.array_load(frame, Integer, &["[B", "[Z"])?,
+            caload => self.array_load(frame, Integer, &["[C"])?,
+            saload => self.array_load(frame, Integer, &["[S"])?,
+            laload => self.array_load(frame, Long, &["[J"])?,
+            faload => self.array_load(frame, Float, &["[F"])?,
+            daload => self.array_load(frame, Double, &["[D"])?,
+            aaload => {
+                frame.pop(Integer)?;
+                let element = match frame.pop_array()? {
+                    Some(array) => reference_element(array)?,
+                    None => Null,
+                };
+                frame.push(element);
+            }
+            iastore => self.array_store(frame, Integer, &["[I"])?,
+            bastore => self.array_store(frame, Integer, &["[B", "[Z"])?,
+            castore => self.array_store(frame, Integer, &["[C"])?,
+            sastore => self.array_store(frame, Integer, &["[S"])?,
+            lastore => self.array_store(frame, Long, &["[J"])?,
+            fastore => self.array_store(frame, Float, &["[F"])?,
+            dastore => self.array_store(frame, Double, &["[D"])?,
+            aastore => {
+                self.pop_value(frame, &Object(OBJECT.to_string()))?;
+                frame.pop(Integer)?;
+                let array = frame.pop_array()?;
+                match array {
+                    Some(array) if self.strict => reference_element(array).map(|_| ())?,
+                    _ => {}
+                }
+            }
+
+            pop => frame.pop_slots(1)?,
+            pop2 => frame.pop_slots(2)?,
+            dup => frame.dup(1, 0)?,
+            dup_x1 => frame.dup(1, 1)?,
+            dup_x2 => frame.dup(1, 2)?,
+            dup2 => frame.dup(2, 0)?,
+            dup2_x1 => frame.dup(2, 1)?,
+            dup2_x2 => frame.dup(2, 2)?,
+            swap => frame.swap()?,
+
+            iadd | isub | imul | idiv | irem | iand | ior | ixor | ishl | ishr | iushr => {
+                frame.compute(&[Integer, Integer], Some(Integer))?
+            }
+            ladd | lsub | lmul | ldiv | lrem | land | lor | lxor => {
+                frame.compute(&[Long, Long], Some(Long))?
+            }
+            lshl | lshr | lushr => frame.compute(&[Long, Integer], Some(Long))?,
+            fadd | fsub | fmul | fdiv | frem => frame.compute(&[Float, Float], Some(Float))?,
+            dadd | dsub | dmul | ddiv | drem => frame.compute(&[Double, Double], Some(Double))?,
+            ineg | i2b | i2c | i2s => frame.compute(&[Integer], Some(Integer))?,
+            lneg => frame.compute(&[Long], Some(Long))?,
+            fneg => frame.compute(&[Float], Some(Float))?,
+            dneg => frame.compute(&[Double], Some(Double))?,
+            i2l => frame.compute(&[Integer], Some(Long))?,
+            i2f => frame.compute(&[Integer], Some(Float))?,
+            i2d => frame.compute(&[Integer], Some(Double))?,
+            l2i => frame.compute(&[Long], Some(Integer))?,
+            l2f => frame.compute(&[Long], Some(Float))?,
+            l2d => frame.compute(&[Long], Some(Double))?,
+            f2i => frame.compute(&[Float], Some(Integer))?,
+            f2l => frame.compute(&[Float], Some(Long))?,
+            f2d => frame.compute(&[Float], Some(Double))?,
+            d2i => frame.compute(&[Double], Some(Integer))?,
+            d2l => frame.compute(&[Double], Some(Long))?,
+            d2f => frame.compute(&[Double], Some(Float))?,
+            lcmp => frame.compute(&[Long, Long], Some(Integer))?,
+            fcmpl | fcmpg => frame.compute(&[Float, Float], Some(Integer))?,
+            dcmpl | dcmpg => frame.compute(&[Double, Double], Some(Integer))?,
+
+            ifeq { .. }
+            | ifne { .. }
+            | iflt { .. }
+            | ifge { .. }
+            | ifgt { .. }
+            | ifle { .. }
+            | tableswitch { .. }
+            | lookupswitch { .. }
+            | ireturn => frame.pop(Integer)?,
+            if_icmpeq { .. }
+            | if_icmpne { .. }
+            | if_icmplt { .. }
+            | if_icmpge { .. }
+            | if_icmpgt { .. }
+            | if_icmple { .. } => frame.compute(&[Integer, Integer], None)?,
+            if_acmpeq { .. } | if_acmpne { .. } => {
+                frame.pop_reference()?;
+                frame.pop_reference()?;
+            }
+            ifnull { .. } | ifnonnull { .. } | areturn | monitorenter | monitorexit => {
+                frame.pop_reference()?;
+            }
+            athrow => self.pop_value(frame, &Object("java/lang/Throwable".to_string()))?,
+            lreturn => frame.pop(Long)?,
+            freturn => frame.pop(Float)?,
+            dreturn => frame.pop(Double)?,
+
+            getstatic { field } => frame.push(value_type(&field.descriptor)),
+            putstatic { field } => self.pop_value(frame, &value_type(&field.descriptor))?,
+            getfield { field } => {
+                self.pop_value(frame, &Object(field.class.0.clone()))?;
+                frame.push(value_type(&field.descriptor));
+            }
+            putfield { field } => {
+                self.pop_value(frame, &value_type(&field.descriptor))?;
+                // Constructors can set their own class' fields before calling the super constructor
+                if frame.stack.last() == Some(&UninitializedThis)
+                    && field.class.0 == self.this_class
+                {
+                    frame.pop_slot()?;
+                } else {
+                    self.pop_value(frame, &Object(field.class.0.clone()))?;
+                }
+            }
+            invokevirtual { method } | invokespecial { method } => self.invoke(
+                frame,
+                &method.name,
+                &method.descriptor,
+                true,
+                &method.class.0,
+            )?,
+            invokestatic { method } => self.invoke(
+                frame,
+                &method.name,
+                &method.descriptor,
+                false,
+                &method.class.0,
+            )?,
+            // Like the JVM's verifier, any object can stand in for an interface
+            invokeinterface { method } => {
+                self.invoke(frame, &method.name, &method.descriptor, true, OBJECT)?
+            }
+            invokedynamic { call_site } => {
+                self.invoke(frame, &call_site.name, &call_site.descriptor, false, OBJECT)?
+            }
+
+            // The analyses label every `new` before running it
+            new { .. } => frame.push(label.map_or(Top, Uninitialized)),
+            newarray { atype } => {
+                frame.pop(Integer)?;
+                frame.push(Object(format!("[{}", atype.descriptor())));
+            }
+            anewarray { class } => {
+                frame.pop(Integer)?;
+                frame.push(Object(format!("[{}", descriptor_of(&class.0))));
+            }
+            multianewarray { class, dimensions } => {
+                for _ in 0..*dimensions {
+                    frame.pop(Integer)?;
+                }
+                frame.push(Object(class.0.clone()));
+            }
+            arraylength => {
+                frame.pop_array()?;
+                frame.push(Integer);
+            }
+            checkcast { class } => {
+                self.pop_value(frame, &Object(OBJECT.to_string()))?;
+                frame.push(Object(class.0.clone()));
+            }
+            instanceof { .. } => {
+                self.pop_value(frame, &Object(OBJECT.to_string()))?;
+                frame.push(Integer);
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,114 @@
+//! Computes `max_stack` and `max_locals`, like ASM's `COMPUTE_MAXS`
+
+use crate::analysis::flow::{analyze, method_code_mut, FreshLabels};
+use crate::analysis::hierarchy::Classes;
+use crate::analysis::interpreter::Interpreter;
+use crate::analysis::AnalysisError;
+use crate::constant_pool::ConstantPool;
+use crate::model::attrs::code::{Code, Instruction, OpcodeInfo, Opcodes};
+use crate::model::class::Class;
+use crate::model::method::Method;
+use crate::w2;
+
+/// Recomputes `max_stack` and `max_locals` of every method of [class], see [compute_method_maxs].
+/// Stops at the first method that can't be analyzed, leaving it and the methods after it as they were.
+pub fn compute_maxs(class: &mut Class) -> Result<(), AnalysisError> {
+    for method in &mut class.methods {
+        compute_method_maxs_in(method, &class.this_class, class.constant_pool.as_ref())?;
+    }
+    Ok(())
+}
+
+/// Sets `max_stack` of [method]'s code to the highest the operand stack gets on any path
+/// through the code, including the paths through exception handlers, and `max_locals` to
+/// one past the highest local variable the arguments or any instruction use.
+/// Longs and doubles take two slots in both.
+///
+/// Unreachable instructions don't count towards `max_stack`.
+/// The code has to place every label its branches and exception ranges point at,
+/// and can't use `jsr` or `ret`.
+pub fn compute_method_maxs(method: &mut Method, this_class: &str) -> Result<(), AnalysisError> {
+    compute_method_maxs_in(method, this_class, None)
+}
+
+/// [compute_method_maxs] for a method of a class with [constant_pool], which errors are located with
+pub(crate) fn compute_method_maxs_in(
+    method: &mut Method,
+    this_class: &str,
+    constant_pool: Option<&ConstantPool>,
+) -> Result<(), AnalysisError> {
+    let Some((context, code)) = method_code_mut(method) else {
+        return Ok(());
+    };
+    // How high the stack gets doesn't depend on which classes it holds
+    let hierarchy = Classes::new();
+    let interpreter = Interpreter {
+        hierarchy: &hierarchy,
+        this_class,
+        strict: false,
+    };
+    let flow = analyze(code, &context, &interpreter, &mut FreshLabels::new(code))
+        .map_err(|e| e.locate(code, constant_pool))?;
+    let max_stack = flow
+        .frames
+        .iter()
+        .flatten()
+        .map(|frame| frame.stack.len())
+        .max()
+        .unwrap_or(0);
+    let arguments = interpreter
+        .initial_frame(context.name, context.descriptor, context.is_static)
+        .locals
+        .len();
+    let max_locals = locals_used(code).max(arguments);
+
+    code.max_stack = w2::try_from(max_stack).unwrap_or(w2::MAX);
+    code.max_locals = w2::try_from(max_locals).unwrap_or(w2::MAX);
+    Ok(())
+}
+
+/// One past the highest local variable any instruction in [code] uses
+fn locals_used(code: &Code) -> usize {
+    code.code
+        .iter()
+        .filter_map(|instruction| match instruction {
+            Instruction::Op(op) => local_end(op),
+            Instruction::Label { .. } => None,
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+/// One past the last local variable [op] uses, `None` if it doesn't use any
+fn local_end(op: &OpcodeInfo) -> Option<usize> {
+    use OpcodeInfo::*;
+    let (index, opcode) = match op {
+        iload { index }
+        | lload { index }
+        | fload { index }
+        | dload { index }
+        | aload { index }
+        | istore { index }
+        | lstore { index }
+        | fstore { index }
+        | dstore { index }
+        | astore { index }
+        | iinc { index, .. }
+        | ret { index } => (*index as usize, op.opcode()),
+        wide { instruction } => (instruction.index() as usize, instruction.opcode()),
+        iload_0 | fload_0 | aload_0 | istore_0 | fstore_0 | astore_0 => (0, Opcodes::iload),
+        iload_1 | fload_1 | aload_1 | istore_1 | fstore_1 | astore_1 => (1, Opcodes::iload),
+        iload_2 | fload_2 | aload_2 | istore_2 | fstore_2 | astore_2 => (2, Opcodes::iload),
+        iload_3 | fload_3 | aload_3 | istore_3 | fstore_3 | astore_3 => (3, Opcodes::iload),
+        lload_0 | dload_0 | lstore_0 | dstore_0 => (0, Opcodes::lload),
+        lload_1 | dload_1 | lstore_1 | dstore_1 => (1, Opcodes::lload),
+        lload_2 | dload_2 | lstore_2 | dstore_2 => (2, Opcodes::lload),
+        lload_3 | dload_3 | lstore_3 | dstore_3 => (3, Opcodes::lload),
+        _ => return None,
+    };
+    let wide_value = matches!(
+        opcode,
+        Opcodes::lload | Opcodes::dload | Opcodes::lstore | Opcodes::dstore
+    );
+    Some(index + if wide_value { 2 } else { 1 })
+}
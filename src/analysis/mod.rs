@@ -0,0 +1,164 @@
+//! Analyses of the instructions in [Code](crate::model::attrs::code::Code),
+//! based on inferring the types of the locals and the operand stack at every instruction
+//! (https://docs.oracle.com/javase/specs/jvms/se12/html/jvms-4.html#jvms-4.10.1)
+
+mod flow;
+pub mod frames;
+pub mod hierarchy;
+mod interpreter;
+pub mod maxs;
+pub mod verifier;
+
+use crate::constant_pool::ConstantPool;
+use crate::model::attrs::code::{Code, Label};
+use crate::model::attrs::stack_map_table::VerificationType;
+use std::fmt::{Display, Formatter};
+
+/// Why the instructions of a method couldn't be analyzed
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnalysisError {
+    pub method: String,
+    /// Index of the offending instruction in [Code::code](crate::model::attrs::code::Code::code),
+    /// `None` for errors that aren't about a single instruction, e.g. an unplaced handler label
+    pub instruction: Option<usize>,
+    /// Where the offending instruction is once the code is written (`None` if it can't be).
+    /// It's only exact if the class' [constant pool](crate::model::class::Class::constant_pool)
+    /// holds every constant the code loads, e.g. for an unchanged class read
+    /// [losslessly](crate::bytecode::reader::ByteReader::lossless); otherwise an `ldc`
+    /// before it may be written as an `ldc_w`, one byte longer.
+    pub pc: Option<usize>,
+    pub error: TypeError,
+}
+
+impl AnalysisError {
+    /// Fills in [AnalysisError::pc] from the instructions of [code],
+    /// as written with [constant_pool] or, if there's none, a pool of its own
+    pub(crate) fn locate(mut self, code: &Code, constant_pool: Option<&ConstantPool>) -> Self {
+        let pcs = match constant_pool {
+            Some(constant_pool) => code.pcs(constant_pool),
+            None => code.pcs(&ConstantPool::new()),
+        };
+        self.pc = match (self.instruction, pcs) {
+            (Some(instruction), Ok(pcs)) => pcs.get(instruction).copied(),
+            _ => None,
+        };
+        self
+    }
+}
+
+impl Display for AnalysisError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match (self.pc, self.instruction) {
+            (Some(pc), Some(instruction)) => write!(
+                f,
+                "{}, pc {} (instruction {}): {}",
+                self.method, pc, instruction, self.error
+            ),
+            (None, Some(instruction)) => write!(
+                f,
+                "{}, instruction {}: {}",
+                self.method, instruction, self.error
+            ),
+            _ => write!(f, "{}: {}", self.method, self.error),
+        }
+    }
+}
+
+impl std::error::Error for AnalysisError {}
+
+/// Everything that can be wrong with the types an instruction operates on
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypeError {
+    /// An instruction pops more values than there are on the stack
+    StackUnderflow,
+    /// Paths with different stack heights meet at the same instruction
+    StackHeightMismatch {
+        expected: usize,
+        found: usize,
+    },
+    /// The value on top of the stack isn't what the instruction takes,
+    /// e.g. `expected` is `int` or `reference`
+    WrongStackType {
+        expected: String,
+        found: VerificationType,
+    },
+    /// A local variable doesn't hold what the instruction reads from it
+    WrongLocalType {
+        index: usize,
+        expected: String,
+        found: VerificationType,
+    },
+    /// The instruction only takes part of a long or double on the stack
+    SplitsWideValue,
+    /// The last instruction can continue to the end of the code array
+    FallsOffEnd,
+    /// `jsr` and `ret`, which classes since version 51 can't use, aren't supported
+    Subroutine,
+    /// A branch or exception range points at a label that isn't placed in the code
+    UnknownLabel(Label),
+    /// An array class name isn't a valid field descriptor
+    InvalidDescriptor(String),
+    /// A branch target, an exception handler or an instruction after an unconditional branch
+    /// has no `StackMapTable` frame
+    MissingFrame,
+    /// A `StackMapTable` frame is at or before the previous one, at the end of the code,
+    /// or chops more locals than there are
+    InvalidFrame,
+    /// A return instruction doesn't match the method's return type
+    WrongReturn {
+        expected: String,
+        found: String,
+    },
+    /// The operand stack grows beyond `max_stack`
+    MaxStackExceeded {
+        max_stack: usize,
+    },
+    /// More local variables are used than `max_locals`
+    MaxLocalsExceeded {
+        max_locals: usize,
+    },
+}
+
+impl Display for TypeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        use TypeError::*;
+        match self {
+            StackUnderflow => write!(f, "Stack underflow"),
+            StackHeightMismatch { expected, found } => write!(
+                f,
+                "Stack height {} doesn't match height {} of an earlier path",
+                found, expected
+            ),
+            WrongStackType { expected, found } => {
+                write!(f, "Expected {} on the stack, found {:?}", expected, found)
+            }
+            WrongLocalType {
+                index,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Expected {} in local {}, found {:?}",
+                expected, index, found
+            ),
+            SplitsWideValue => write!(f, "Instruction splits a long or double on the stack"),
+            FallsOffEnd => write!(f, "Execution falls off the end of the code"),
+            Subroutine => write!(f, "Subroutines (jsr/ret) aren't supported"),
+            UnknownLabel(label) => write!(f, "Label {} isn't placed in the code", label.0),
+            InvalidDescriptor(descriptor) => write!(f, "Invalid descriptor `{}`", descriptor),
+            MissingFrame => write!(f, "Missing StackMapTable frame"),
+            InvalidFrame => write!(f, "Invalid StackMapTable frame"),
+            WrongReturn { expected, found } => {
+                write!(f, "Expected a return of {}, found {}", expected, found)
+            }
+            MaxStackExceeded { max_stack } => {
+                write!(f, "The stack grows beyond max_stack {}", max_stack)
+            }
+            MaxLocalsExceeded { max_locals } => {
+                write!(f, "Uses locals beyond max_locals {}", max_locals)
+            }
+        }
+    }
+}
+
+impl std::error::Error for TypeError {}
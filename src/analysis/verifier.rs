@@ -0,0 +1,334 @@
+//! Type checks methods against their descriptors and `StackMapTable`s,
+//! like the JVM's verifier (https://docs.oracle.com/javase/specs/jvms/se12/html/jvms-4.html#jvms-4.10.1)
+
+use crate::analysis::flow::{
+    analyze, falls_through, method_code, FreshLabels, MethodContext, Scan,
+};
+use crate::analysis::frames::compress;
+use crate::analysis::hierarchy::ClassHierarchy;
+use crate::analysis::interpreter::{describe, method_types, Frame, Interpreter};
+use crate::analysis::{AnalysisError, TypeError};
+use crate::constant_pool::ConstantPool;
+use crate::model::attrs::code::{Code, OpcodeInfo};
+use crate::model::attrs::stack_map_table::StackMapFrame::*;
+use crate::model::attrs::stack_map_table::VerificationType;
+use crate::model::attrs::Attribute;
+use crate::model::class::Class;
+use crate::model::method::Method;
+use std::collections::HashMap;
+
+/// Type checks every method of [class], see [verify_method], reporting the first error in each method that fails.
+/// Classes older than version 50 have no `StackMapTable`s, so their methods are checked by
+/// inferring the types at each instruction instead.
+///
+/// [hierarchy] has to know the classes whose values meet, e.g. a class that is returned
+/// where its interface is expected, and the class itself.
+pub fn verify(class: &Class, hierarchy: &dyn ClassHierarchy) -> Result<(), Vec<AnalysisError>> {
+    let constant_pool = class.constant_pool.as_ref();
+    let errors: Vec<AnalysisError> = class
+        .methods
+        .iter()
+        .filter_map(|method| {
+            if class.version.major < 50 {
+                infer_method(method, &class.this_class, hierarchy, constant_pool)
+            } else {
+                verify_method_in(method, &class.this_class, hierarchy, constant_pool)
+            }
+            .err()
+        })
+        .collect();
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// Checks that every instruction of [method]'s code gets the types it takes, going through the
+/// instructions in order. The types come from the method's descriptor at the start of the code,
+/// from the `StackMapTable` frames where there is one, and from the previous instruction otherwise.
+/// Where a frame is, and at every branch target and exception handler, which have to have one,
+/// the types that flow in have to be assignable to the frame's.
+///
+/// Also checks that the values returned match the descriptor,
+/// and that the code stays within its `max_stack` and `max_locals`.
+pub fn verify_method(
+    method: &Method,
+    this_class: &str,
+    hierarchy: &dyn ClassHierarchy,
+) -> Result<(), AnalysisError> {
+    verify_method_in(method, this_class, hierarchy, None)
+}
+
+/// [verify_method] for a method of a class with [constant_pool], which errors are located with
+fn verify_method_in(
+    method: &Method,
+    this_class: &str,
+    hierarchy: &dyn ClassHierarchy,
+    constant_pool: Option<&ConstantPool>,
+) -> Result<(), AnalysisError> {
+    let Some((context, code)) = method_code(method) else {
+        return Ok(());
+    };
+    let interpreter = Interpreter {
+        hierarchy,
+        this_class,
+        strict: true,
+    };
+    type_check(code, &context, &interpreter).map_err(|e| e.locate(code, constant_pool))
+}
+
+/// Checks [method] by following every path through its code, like the JVM does for old classes
+fn infer_method(
+    method: &Method,
+    this_class: &str,
+    hierarchy: &dyn ClassHierarchy,
+    constant_pool: Option<&ConstantPool>,
+) -> Result<(), AnalysisError> {
+    let Some((context, code)) = method_code(method) else {
+        return Ok(());
+    };
+    let interpreter = Interpreter {
+        hierarchy,
+        this_class,
+        strict: true,
+    };
+    analyze(code, &context, &interpreter, &mut FreshLabels::new(code))
+        .map(|_| ())
+        .map_err(|e| e.locate(code, constant_pool))
+}
+
+fn type_check(
+    code: &Code,
+    method: &MethodContext,
+    interpreter: &Interpreter,
+) -> Result<(), AnalysisError> {
+    let scan = Scan::new(code, method, &mut FreshLabels::new(code))?;
+    let initial = interpreter.initial_frame(method.name, method.descriptor, method.is_static);
+    let returned = method_types(method.descriptor).1;
+    let frames = declared_frames(code, &scan, &initial, method)?;
+    let max_stack = code.max_stack as usize;
+    let max_locals = code.max_locals as usize;
+
+    let mut current = Some(initial);
+    for (i, op) in scan.instructions.iter().enumerate() {
+        let error = |e| method.error(Some(scan.ops[i]), e);
+        let before = match (current.take(), frames.get(&i)) {
+            (Some(current), Some(declared)) => {
+                check_assignable(interpreter, &current, declared).map_err(error)?;
+                declared.clone()
+            }
+            (None, Some(declared)) => declared.clone(),
+            (Some(current), None) => current,
+            (None, None) => return Err(error(TypeError::MissingFrame)),
+        };
+        if before.locals.len() > max_locals {
+            return Err(error(TypeError::MaxLocalsExceeded { max_locals }));
+        }
+        check_return(op, &before, returned.as_ref(), method, interpreter).map_err(error)?;
+        let mut after = before.clone();
+        interpreter
+            .execute(op, &mut after, scan.new_labels.get(&i).copied())
+            .map_err(error)?;
+        if after.stack.len() > max_stack {
+            return Err(error(TypeError::MaxStackExceeded { max_stack }));
+        }
+        if after.locals.len() > max_locals {
+            return Err(error(TypeError::MaxLocalsExceeded { max_locals }));
+        }
+
+        // An exception can be thrown before or after the instruction changes the locals
+        for ((range, handler), catch_type) in scan.handlers.iter().zip(&scan.catch_types) {
+            if range.contains(&i) {
+                let handler = frames.get(handler).ok_or(TypeError::MissingFrame);
+                let handler = handler.map_err(error)?;
+                for locals in [&before.locals, &after.locals] {
+                    let incoming = Frame {
+                        locals: locals.clone(),
+                        stack: vec![catch_type.clone()],
+                    };
+                    check_assignable(interpreter, &incoming, handler).map_err(error)?;
+                }
+            }
+        }
+
+        for label in op.labels() {
+            let target = scan.position(label).map_err(error)?;
+            if target == scan.ops.len() {
+                return Err(error(TypeError::FallsOffEnd));
+            }
+            let target = frames.get(&target).ok_or(TypeError::MissingFrame);
+            check_assignable(interpreter, &after, target.map_err(error)?).map_err(error)?;
+        }
+        if falls_through(op) {
+            if i + 1 == scan.ops.len() {
+                return Err(error(TypeError::FallsOffEnd));
+            }
+            current = Some(after);
+        }
+    }
+    Ok(())
+}
+
+/// The frames of the code's `StackMapTable` by the position of the op they're at,
+/// with longs and doubles taking two entries and uninitialized objects named after the label the analyses use
+fn declared_frames(
+    code: &Code,
+    scan: &Scan,
+    initial: &Frame,
+    method: &MethodContext,
+) -> Result<HashMap<usize, Frame>, AnalysisError> {
+    let table = code
+        .attributes
+        .iter()
+        .find_map(|attribute| match attribute {
+            Attribute::StackMapTable(table) => Some(table.as_slice()),
+            _ => None,
+        })
+        .unwrap_or_default();
+
+    let expand = |types: &[VerificationType]| {
+        let mut expanded = vec![];
+        for value in types {
+            let value = match value {
+                VerificationType::Uninitialized(label) => scan
+                    .position(*label)
+                    .ok()
+                    .and_then(|position| scan.new_labels.get(&position))
+                    .map_or(value.clone(), |label| {
+                        VerificationType::Uninitialized(*label)
+                    }),
+                _ => value.clone(),
+            };
+            let wide = matches!(value, VerificationType::Long | VerificationType::Double);
+            expanded.push(value);
+            if wide {
+                expanded.push(VerificationType::Top);
+            }
+        }
+        expanded
+    };
+
+    let mut frames = HashMap::new();
+    let mut locals = compress(&initial.locals);
+    let mut previous = None;
+    for frame in table {
+        let position = scan
+            .position(frame.label())
+            .map_err(|e| method.error(None, e))?;
+        let invalid = || method.error(scan.ops.get(position).copied(), TypeError::InvalidFrame);
+        if position == scan.ops.len() || previous.is_some_and(|previous| position <= previous) {
+            return Err(invalid());
+        }
+        previous = Some(position);
+        let stack = match frame {
+            same_frame { .. } | same_frame_extended { .. } => vec![],
+            same_locals_1_stack_item_frame { stack, .. }
+            | same_locals_1_stack_item_frame_extended { stack, .. } => vec![stack.clone()],
+            chop_frame { chopped, .. } => {
+                let kept = locals.len().checked_sub(*chopped as usize);
+                locals.truncate(kept.ok_or_else(invalid)?);
+                vec![]
+            }
+            append_frame {
+                locals: appended, ..
+            } => {
+                locals.extend(appended.iter().cloned());
+                vec![]
+            }
+            full_frame {
+                locals: all, stack, ..
+            } => {
+                locals = all.clone();
+                stack.clone()
+            }
+        };
+        let frame = Frame {
+            locals: expand(&locals),
+            stack: expand(&stack),
+        };
+        frames.insert(position, frame);
+    }
+    Ok(frames)
+}
+
+/// Fails unless every local and value on the stack of [from] can be used as the one in [to]
+fn check_assignable(interpreter: &Interpreter, from: &Frame, to: &Frame) -> Result<(), TypeError> {
+    if from.stack.len() != to.stack.len() {
+        return Err(TypeError::StackHeightMismatch {
+            expected: to.stack.len(),
+            found: from.stack.len(),
+        });
+    }
+    for index in 0..from.locals.len().max(to.locals.len()) {
+        let (found, expected) = (from.local(index), to.local(index));
+        if !interpreter.is_assignable(&found, &expected) {
+            return Err(TypeError::WrongLocalType {
+                index,
+                expected: describe(&expected),
+                found,
+            });
+        }
+    }
+    for (found, expected) in from.stack.iter().zip(&to.stack) {
+        if !interpreter.is_assignable(found, expected) {
+            return Err(TypeError::WrongStackType {
+                expected: describe(expected),
+                found: found.clone(),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Fails if [op] returns something else than the method does, [returned] being `None` for `void`,
+/// or a constructor returns before calling its super constructor
+fn check_return(
+    op: &OpcodeInfo,
+    before: &Frame,
+    returned: Option<&VerificationType>,
+    method: &MethodContext,
+    interpreter: &Interpreter,
+) -> Result<(), TypeError> {
+    use OpcodeInfo::*;
+    use VerificationType::*;
+    let found = match op {
+        ireturn => "int",
+        lreturn => "long",
+        freturn => "float",
+        dreturn => "double",
+        areturn => "reference",
+        r#return => "void",
+        _ => return Ok(()),
+    };
+    match (op, returned) {
+        (r#return, None) => {
+            let uninitialized = before.locals.iter().position(|l| *l == UninitializedThis);
+            match uninitialized {
+                Some(index) if method.name == "<init>" => Err(TypeError::WrongLocalType {
+                    index,
+                    expected: interpreter.this_class.to_string(),
+                    found: UninitializedThis,
+                }),
+                _ => Ok(()),
+            }
+        }
+        (ireturn, Some(Integer))
+        | (lreturn, Some(Long))
+        | (freturn, Some(Float))
+        | (dreturn, Some(Double)) => Ok(()),
+        (areturn, Some(expected @ Object(_))) => match before.stack.last() {
+            Some(value) if !interpreter.is_assignable(value, expected) => {
+                Err(TypeError::WrongStackType {
+                    expected: describe(expected),
+                    found: value.clone(),
+                })
+            }
+            _ => Ok(()),
+        },
+        _ => Err(TypeError::WrongReturn {
+            expected: returned.map_or("void".to_string(), describe),
+            found: found.to_string(),
+        }),
+    }
+}
@@ -1,5 +1,6 @@
 use crate::bytecode::reader::{ByteReader, Take};
 use crate::bytecode::writer::{ByteWriter, Writeable};
+use crate::error::ParseError;
 use crate::model::class::ClassAccessModifier;
 use crate::model::field::FieldAccessModifier;
 use crate::model::method::MethodAccessModifier;
@@ -9,7 +10,7 @@ use std::iter::FromIterator;
 macro_rules! impl_rw_for_modifiers {
     ($t:ty) => {
         impl Take<Vec<$t>> for ByteReader {
-            fn take(&mut self) -> Result<Vec<$t>, String> {
+            fn take(&mut self) -> Result<Vec<$t>, $crate::error::ParseError> {
                 let flags: w2 = self.take()?;
                 let modifiers = <$t>::variants().into_iter();
                 Ok(Vec::from_iter(
@@ -35,7 +36,7 @@ pub(crate) use impl_rw_for_modifiers;
 impl_rw_for_modifiers!(FieldAccessModifier);
 
 impl Take<Vec<ClassAccessModifier>> for ByteReader {
-    fn take(&mut self) -> Result<Vec<ClassAccessModifier>, String> {
+    fn take(&mut self) -> Result<Vec<ClassAccessModifier>, ParseError> {
         let flags: w2 = self.take()?;
         use ClassAccessModifier::*;
         let modifiers = vec![
@@ -1,57 +1,88 @@
 use crate::bytecode::reader::{ByteReader, Take};
 use crate::bytecode::unresolved::Unresolved;
 use crate::bytecode::writer::{ByteWriter, Writeable};
-use crate::constant_pool::{Constant, ConstantPool, CpInfo, CpTag};
+use crate::constant_pool::{BootstrapMethodRef, Constant, ConstantPool, CpInfo, CpTag};
+use crate::error::{ClassFileError, Location, ParseError};
 use crate::model::attrs;
+use crate::model::attrs::annotations::{
+    parse_annotations, parse_element_value, parse_parameter_annotations, write_annotations,
+    write_element_value, write_parameter_annotations,
+};
 use crate::model::attrs::code;
-use crate::model::attrs::code::exception_table::{parse_exception_table, write_exception_table};
+use crate::model::attrs::code::exception_table::{
+    parse_exception_table, write_exception_table, ExceptionTableElement,
+};
+use crate::model::attrs::code::{Label, Labels};
+use crate::model::attrs::enclosing_method::{parse_enclosing_method, write_enclosing_method};
+use crate::model::attrs::inner_classes::{parse_inner_classes, write_inner_classes};
+use crate::model::attrs::line_number_table::write_line_number_table;
 use crate::model::attrs::local_variable_table::{
     parse_local_variable_table, write_local_variable_table,
 };
+use crate::model::attrs::local_variable_type_table::{
+    parse_local_variable_type_table, write_local_variable_type_table,
+};
 use crate::model::attrs::method_parameters::{MethodParameter, MethodParameterAccessFlags};
+use crate::model::attrs::module::{
+    parse_module, parse_module_packages, write_module, write_module_packages,
+};
+use crate::model::attrs::record::RecordComponent;
+use crate::model::attrs::source_debug_extension::{
+    parse_source_debug_extension, write_source_debug_extension,
+};
+use crate::model::attrs::stack_map_table::{parse_stack_map_table, write_stack_map_table};
+use crate::model::attrs::type_annotations::{parse_type_annotations, write_type_annotations};
 use crate::model::attrs::Attribute;
-use crate::{model, w1, w2, w4};
-use crate::model::attrs::code::OpcodeInfo;
+use crate::{w1, w2, w4, w8};
+use std::collections::BTreeSet;
 
 impl Attribute {
     fn create(
         name: String,
-        info: Vec<w1>,
+        mut bytes: ByteReader,
         constant_pool: &ConstantPool,
-    ) -> Result<Attribute, String> {
-        let mut bytes = ByteReader::from(info);
+    ) -> Result<Attribute, ParseError> {
         use Attribute::*;
         Ok(match name.as_str() {
-            stringify!(ConstantValue) => {
-                ConstantValue(model::attrs::constant_value::ConstantValue::String(
-                    constant_pool.get_constant_as_string(bytes.take()?)?,
-                ))
-            }
-            stringify!(LineNumberTable) => LineNumberTable(
-                bytes
-                    .take()
-                    .map_err(|e| format!("Failed parsing line number table: {}", e))?,
+            stringify!(ConstantValue) => ConstantValue(
+                bytes.take_constant(|index| constant_pool.get_constant_value(index))?,
             ),
+            stringify!(Exceptions) => Exceptions(take_classes(&mut bytes, constant_pool)?),
+            stringify!(LineNumberTable) => LineNumberTable(bytes.take()?),
             stringify!(LocalVariableTable) => {
                 LocalVariableTable(parse_local_variable_table(&mut bytes, constant_pool)?)
             }
+            stringify!(LocalVariableTypeTable) => LocalVariableTypeTable(
+                parse_local_variable_type_table(&mut bytes, constant_pool)?,
+            ),
+            stringify!(StackMapTable) => {
+                StackMapTable(parse_stack_map_table(&mut bytes, constant_pool)?)
+            }
             stringify!(Code) => {
                 let max_stack: w2 = bytes.take()?;
                 let max_locals: w2 = bytes.take()?;
 
+                let code_location = bytes.location();
                 let code_length: w4 = bytes.take()?;
-                let mut code_reader =
-                    ByteReader::from(bytes.take_bytes(code_length as usize)?.to_vec());
-                let mut code: Vec<code::OpcodeInfo> = vec![];
-                while !code_reader.is_empty() {
-                    code.push(OpcodeInfo::decode_opcode_info(&mut code_reader,  constant_pool)?);
-                }
+                let mut code_reader = bytes.take_reader(code_length as usize)?;
+                let ops = code::Code::decode_instructions(&mut code_reader, constant_pool)?;
 
                 let exception_table = parse_exception_table(constant_pool, &mut bytes)?;
 
                 let unresolved_attributes: Vec<UnresolvedAttribute> = bytes.take()?;
                 let attributes = unresolved_attributes.resolve(constant_pool)?;
 
+                let mut labels: BTreeSet<Label> =
+                    ops.iter().flat_map(|(_, op)| op.labels()).collect();
+                labels.extend(
+                    exception_table
+                        .iter()
+                        .flat_map(ExceptionTableElement::labels),
+                );
+                labels.extend(attributes.iter().flat_map(Attribute::labels));
+                let code = code::Code::place_labels(ops, code_length as usize, labels)
+                    .map_err(|e| ParseError::new(e, code_location))?;
+
                 Code(code::Code {
                     max_stack,
                     max_locals,
@@ -65,31 +96,108 @@ impl Attribute {
                 let mut method_parameters: Vec<MethodParameter> =
                     Vec::with_capacity(parameters_count.into());
                 for i in 0..parameters_count {
-                    let name_index: w2 = bytes.take()?;
-                    let name: Option<String> = if name_index == 0 {
-                        Option::None
-                    } else {
-                        Some(constant_pool.get_utf8(name_index).map_err(|e| {
-                            format!(
-                                "Couldn't get name for parameter #{} from index {}:\n\t{}",
-                                i, name_index, e
-                            )
-                        })?)
-                    };
-                    let access_flags: Vec<MethodParameterAccessFlags> = bytes.take()?;
-                    method_parameters.push(MethodParameter { name, access_flags })
+                    bytes.context(format!("parameters[{}]", i), |bytes| {
+                        let name: Option<String> = bytes.take_constant(|name_index| {
+                            if name_index == 0 {
+                                Ok(Option::None)
+                            } else {
+                                constant_pool.get_utf8(name_index).map(Option::Some)
+                            }
+                        })?;
+                        let access_flags: Vec<MethodParameterAccessFlags> = bytes.take()?;
+                        method_parameters.push(MethodParameter { name, access_flags });
+                        Ok(())
+                    })?;
                 }
                 MethodParameters(method_parameters)
             }
+            stringify!(BootstrapMethods) => {
+                // The table was loaded into the pool before anything was resolved,
+                // this just checks that every entry resolves
+                let bootstrap_methods: Vec<BootstrapMethodRef> = bytes.take()?;
+                for i in 0..bootstrap_methods.len() {
+                    bytes.context(format!("bootstrap_methods[{}]", i), |bytes| {
+                        constant_pool
+                            .get_bootstrap_method(i as w2)
+                            .map_err(|e| bytes.error(e))
+                    })?;
+                }
+                BootstrapMethods
+            }
             stringify!(Synthetic) => Synthetic,
             stringify!(Deprecated) => Deprecated,
-            stringify!(Signature) => Signature {
-                signature_index: bytes.take()?,
-            },
-            stringify!(SourceFile) => (|| {
-                Result::<Attribute, String>::Ok(SourceFile(constant_pool.get_utf8(bytes.take()?)?))
-            })()
-            .map_err(|e| format!("Couldn't get source file name:\n\t{}", e))?,
+            stringify!(Signature) => {
+                Signature(bytes.take_constant(|index| constant_pool.get_utf8(index))?)
+            }
+            stringify!(SourceFile) => {
+                SourceFile(bytes.take_constant(|index| constant_pool.get_utf8(index))?)
+            }
+            stringify!(SourceDebugExtension) => {
+                SourceDebugExtension(parse_source_debug_extension(bytes))
+            }
+            stringify!(RuntimeVisibleAnnotations) => {
+                RuntimeVisibleAnnotations(parse_annotations(&mut bytes, constant_pool)?)
+            }
+            stringify!(RuntimeInvisibleAnnotations) => {
+                RuntimeInvisibleAnnotations(parse_annotations(&mut bytes, constant_pool)?)
+            }
+            stringify!(RuntimeVisibleParameterAnnotations) => RuntimeVisibleParameterAnnotations(
+                parse_parameter_annotations(&mut bytes, constant_pool)?,
+            ),
+            stringify!(RuntimeInvisibleParameterAnnotations) => {
+                RuntimeInvisibleParameterAnnotations(parse_parameter_annotations(
+                    &mut bytes,
+                    constant_pool,
+                )?)
+            }
+            stringify!(AnnotationDefault) => {
+                AnnotationDefault(parse_element_value(&mut bytes, constant_pool)?)
+            }
+            stringify!(RuntimeVisibleTypeAnnotations) => {
+                RuntimeVisibleTypeAnnotations(parse_type_annotations(&mut bytes, constant_pool)?)
+            }
+            stringify!(RuntimeInvisibleTypeAnnotations) => {
+                RuntimeInvisibleTypeAnnotations(parse_type_annotations(&mut bytes, constant_pool)?)
+            }
+            stringify!(InnerClasses) => {
+                InnerClasses(parse_inner_classes(&mut bytes, constant_pool)?)
+            }
+            stringify!(EnclosingMethod) => {
+                EnclosingMethod(parse_enclosing_method(&mut bytes, constant_pool)?)
+            }
+            stringify!(Module) => Module(parse_module(&mut bytes, constant_pool)?),
+            stringify!(ModulePackages) => {
+                ModulePackages(parse_module_packages(&mut bytes, constant_pool)?)
+            }
+            stringify!(ModuleMainClass) => {
+                ModuleMainClass(bytes.take_constant(|index| constant_pool.get_class_name(index))?)
+            }
+            stringify!(NestHost) => {
+                NestHost(bytes.take_constant(|index| constant_pool.get_class_name(index))?)
+            }
+            stringify!(NestMembers) => NestMembers(take_classes(&mut bytes, constant_pool)?),
+            stringify!(Record) => {
+                let components_count: w2 = bytes.take()?;
+                let mut components = Vec::with_capacity(components_count.into());
+                for i in 0..components_count {
+                    bytes.context(format!("components[{}]", i), |bytes| {
+                        let name = bytes.take_constant(|index| constant_pool.get_utf8(index))?;
+                        let descriptor =
+                            bytes.take_constant(|index| constant_pool.get_field_type(index))?;
+                        let unresolved_attributes: Vec<UnresolvedAttribute> = bytes.take()?;
+                        components.push(RecordComponent {
+                            name,
+                            descriptor,
+                            attributes: unresolved_attributes.resolve(constant_pool)?,
+                        });
+                        Ok(())
+                    })?;
+                }
+                Record(components)
+            }
+            stringify!(PermittedSubclasses) => {
+                PermittedSubclasses(take_classes(&mut bytes, constant_pool)?)
+            }
 
             &_ => UNIMPLEMENTED_ATTRIBUTE_TODO {
                 name,
@@ -97,50 +205,190 @@ impl Attribute {
             },
         })
     }
+    /// The labels an attribute of a `Code` attribute points at
+    pub(crate) fn labels(&self) -> Vec<Label> {
+        match self {
+            Attribute::LineNumberTable(line_number_table) => line_number_table
+                .iter()
+                .map(|element| element.start)
+                .collect(),
+            Attribute::LocalVariableTable(local_variable_table) => local_variable_table
+                .iter()
+                .flat_map(|element| [element.start, element.end])
+                .collect(),
+            Attribute::LocalVariableTypeTable(local_variable_type_table) => {
+                local_variable_type_table
+                    .iter()
+                    .flat_map(|element| [element.start, element.end])
+                    .collect()
+            }
+            Attribute::StackMapTable(stack_map_table) => stack_map_table
+                .iter()
+                .flat_map(|frame| frame.labels())
+                .collect(),
+            Attribute::RuntimeVisibleTypeAnnotations(annotations)
+            | Attribute::RuntimeInvisibleTypeAnnotations(annotations) => annotations
+                .iter()
+                .flat_map(|annotation| annotation.target.labels())
+                .collect(),
+            _ => vec![],
+        }
+    }
+
     fn name(&self) -> String {
         match self {
             Attribute::ConstantValue(_) => stringify!(ConstantValue).to_string(),
             Attribute::Code(_) => stringify!(Code).to_string(),
+            Attribute::Exceptions(_) => stringify!(Exceptions).to_string(),
             Attribute::SourceFile(_) => stringify!(SourceFile).to_string(),
             Attribute::LocalVariableTable(_) => stringify!(LocalVariableTable).to_string(),
             Attribute::LineNumberTable(_) => stringify!(LineNumberTable).to_string(),
+            Attribute::LocalVariableTypeTable(_) => stringify!(LocalVariableTypeTable).to_string(),
+            Attribute::SourceDebugExtension(_) => stringify!(SourceDebugExtension).to_string(),
+            Attribute::StackMapTable(_) => stringify!(StackMapTable).to_string(),
+            Attribute::BootstrapMethods => stringify!(BootstrapMethods).to_string(),
             Attribute::Synthetic => stringify!(Synthetic).to_string(),
             Attribute::Deprecated => stringify!(Deprecated).to_string(),
-            Attribute::Signature { .. } => stringify!(Signature).to_string(),
+            Attribute::Signature(_) => stringify!(Signature).to_string(),
             Attribute::MethodParameters { .. } => stringify!(MethodParameters).to_string(),
+            Attribute::RuntimeVisibleAnnotations(_) => {
+                stringify!(RuntimeVisibleAnnotations).to_string()
+            }
+            Attribute::RuntimeInvisibleAnnotations(_) => {
+                stringify!(RuntimeInvisibleAnnotations).to_string()
+            }
+            Attribute::RuntimeVisibleParameterAnnotations(_) => {
+                stringify!(RuntimeVisibleParameterAnnotations).to_string()
+            }
+            Attribute::RuntimeInvisibleParameterAnnotations(_) => {
+                stringify!(RuntimeInvisibleParameterAnnotations).to_string()
+            }
+            Attribute::AnnotationDefault(_) => stringify!(AnnotationDefault).to_string(),
+            Attribute::RuntimeVisibleTypeAnnotations(_) => {
+                stringify!(RuntimeVisibleTypeAnnotations).to_string()
+            }
+            Attribute::RuntimeInvisibleTypeAnnotations(_) => {
+                stringify!(RuntimeInvisibleTypeAnnotations).to_string()
+            }
+            Attribute::InnerClasses(_) => stringify!(InnerClasses).to_string(),
+            Attribute::EnclosingMethod(_) => stringify!(EnclosingMethod).to_string(),
+            Attribute::Module(_) => stringify!(Module).to_string(),
+            Attribute::ModulePackages(_) => stringify!(ModulePackages).to_string(),
+            Attribute::ModuleMainClass(_) => stringify!(ModuleMainClass).to_string(),
+            Attribute::NestHost(_) => stringify!(NestHost).to_string(),
+            Attribute::NestMembers(_) => stringify!(NestMembers).to_string(),
+            Attribute::Record(_) => stringify!(Record).to_string(),
+            Attribute::PermittedSubclasses(_) => stringify!(PermittedSubclasses).to_string(),
             Attribute::UNIMPLEMENTED_ATTRIBUTE_TODO { name, .. } => name.clone(),
         }
     }
 }
 
+/// A count followed by that many classes, as in `Exceptions`, `NestMembers` and
+/// `PermittedSubclasses`
+fn take_classes(
+    bytes: &mut ByteReader,
+    constant_pool: &ConstantPool,
+) -> Result<Vec<String>, ParseError> {
+    let number_of_classes: w2 = bytes.take()?;
+    let mut classes = Vec::with_capacity(number_of_classes.into());
+    for i in 0..number_of_classes {
+        bytes.context(format!("classes[{}]", i), |bytes| {
+            classes.push(bytes.take_constant(|index| constant_pool.get_class_name(index))?);
+            Ok(())
+        })?;
+    }
+    Ok(classes)
+}
+
+fn write_classes(
+    classes: Vec<String>,
+    constant_pool: &mut ConstantPool,
+) -> Result<Vec<w1>, ClassFileError> {
+    let mut writer = ByteWriter::new();
+    writer.write(classes.len() as w2);
+    for class in classes {
+        writer.write(constant_pool.push_class(class)?);
+    }
+    Ok(writer.into())
+}
+
 #[derive(Debug)]
 pub struct UnresolvedAttribute {
     name_index: w2,
     _attribute_length: w4,
     info: Vec<w1>,
+    /// Where `info` starts; the last path segment is replaced by the name once it's resolved
+    location: Location,
 }
 
 impl Take<Vec<UnresolvedAttribute>> for ByteReader {
-    fn take(&mut self) -> Result<Vec<UnresolvedAttribute>, String> {
+    fn take(&mut self) -> Result<Vec<UnresolvedAttribute>, ParseError> {
         let attribute_count: w2 = self.take()?;
         let mut attributes = vec![];
-        for _ in 0..attribute_count {
-            let name_index = self.take()?;
-            let attribute_length = self.take()?;
-            let mut info = vec![];
-            for _ in 0..attribute_length {
-                info.push(self.take()?);
-            }
-            attributes.push(UnresolvedAttribute {
-                name_index,
-                _attribute_length: attribute_length,
-                info,
-            })
+        for i in 0..attribute_count {
+            self.context(format!("attributes[{}]", i), |reader| {
+                let name_index = reader.take()?;
+                let attribute_length: w4 = reader.take()?;
+                let location = reader.location();
+                let mut info = vec![];
+                for _ in 0..attribute_length {
+                    info.push(reader.take()?);
+                }
+                attributes.push(UnresolvedAttribute {
+                    name_index,
+                    _attribute_length: attribute_length,
+                    info,
+                    location,
+                });
+                Ok(())
+            })?;
         }
         Ok(attributes)
     }
 }
 
+impl Take<Vec<BootstrapMethodRef>> for ByteReader {
+    fn take(&mut self) -> Result<Vec<BootstrapMethodRef>, ParseError> {
+        let num_bootstrap_methods: w2 = self.take()?;
+        let mut bootstrap_methods = vec![];
+        for _ in 0..num_bootstrap_methods {
+            let method_ref: w2 = self.take()?;
+            let num_bootstrap_arguments: w2 = self.take()?;
+            let mut arguments = vec![];
+            for _ in 0..num_bootstrap_arguments {
+                arguments.push(self.take()?);
+            }
+            bootstrap_methods.push(BootstrapMethodRef {
+                method_ref,
+                arguments,
+            });
+        }
+        Ok(bootstrap_methods)
+    }
+}
+
+impl UnresolvedAttribute {
+    /// Loads the table into [constant_pool] if this is a `BootstrapMethods` attribute.
+    /// Has to happen before anything that may refer to the table is resolved.
+    pub(crate) fn load_bootstrap_methods(
+        &self,
+        constant_pool: &mut ConstantPool,
+    ) -> Result<(), ParseError> {
+        if constant_pool.get_utf8(self.name_index).as_deref() != Ok(stringify!(BootstrapMethods)) {
+            return Ok(());
+        }
+        let mut location = self.location.clone();
+        location.path.pop();
+        location
+            .path
+            .push(format!("attributes[{}]", stringify!(BootstrapMethods)));
+        let bootstrap_methods = ByteReader::at(self.info.clone(), location).take()?;
+        constant_pool.set_bootstrap_methods(bootstrap_methods);
+        Ok(())
+    }
+}
+
 impl Writeable for Vec<UnresolvedAttribute> {
     fn write(self, writer: &mut ByteWriter) {
         writer.write(self.len() as w2);
@@ -148,6 +396,7 @@ impl Writeable for Vec<UnresolvedAttribute> {
             name_index,
             _attribute_length,
             info,
+            ..
         } in self
         {
             writer.write(name_index);
@@ -163,53 +412,64 @@ impl Unresolved for UnresolvedAttribute {
     type Resolved = Attribute;
     type NeededToResolve = ConstantPool;
 
-    fn resolve(self, constant_pool: &Self::NeededToResolve) -> Result<Self::Resolved, String> {
-        Attribute::create(
-            constant_pool.get_utf8(self.name_index)?,
-            self.info,
-            constant_pool,
-        )
-        .map_err(|e| format!("Attribute resolution failure:\n {}", e))
+    fn resolve(self, constant_pool: &Self::NeededToResolve) -> Result<Self::Resolved, ParseError> {
+        let mut location = self.location;
+        let name = constant_pool
+            .get_utf8(self.name_index)
+            .map_err(|e| ParseError::new(e, location.clone()))?;
+        location.path.pop();
+        location.path.push(format!("attributes[{}]", name));
+        Attribute::create(name, ByteReader::at(self.info, location), constant_pool)
     }
 
-    fn unresolve(resolved: Self::Resolved, constant_pool: &mut Self::NeededToResolve) -> Self {
-        let name_index = constant_pool.push(Constant(
-            CpTag::Utf8,
-            CpInfo::Utf8 {
-                string: resolved.name(),
-            },
-        ));
+    fn unresolve(
+        resolved: Self::Resolved,
+        constant_pool: &mut Self::NeededToResolve,
+    ) -> Result<Self, ClassFileError> {
+        UnresolvedAttribute::from_attribute(resolved, constant_pool, &Labels::default())
+    }
+}
+
+impl UnresolvedAttribute {
+    /// Encodes an attribute, resolving any labels it has with [labels]
+    /// (those of the `Code` attribute it belongs to)
+    fn from_attribute(
+        resolved: Attribute,
+        constant_pool: &mut ConstantPool,
+        labels: &Labels,
+    ) -> Result<Self, ClassFileError> {
+        let name_index = constant_pool.push_utf8(resolved.name())?;
 
         let info: Vec<w1> = match resolved {
             Attribute::ConstantValue(it) => {
-                let constant = match it {
-                    attrs::constant_value::ConstantValue::Integer(int) => {
-                        Constant(CpTag::Integer, CpInfo::Integer { int })
-                    }
-                    attrs::constant_value::ConstantValue::Long(long) => {
-                        Constant(CpTag::Long, CpInfo::Long { long })
-                    }
+                let constant_index = match it {
+                    attrs::constant_value::ConstantValue::Integer(int) => constant_pool
+                        .push(Constant(CpTag::Integer, CpInfo::Integer { int: int as w4 }))?,
+                    attrs::constant_value::ConstantValue::Long(long) => constant_pool
+                        .push(Constant(CpTag::Long, CpInfo::Long { long: long as w8 }))?,
                     attrs::constant_value::ConstantValue::Float(float) => {
-                        Constant(CpTag::Float, CpInfo::Float { float })
+                        constant_pool.push(Constant(CpTag::Float, CpInfo::Float { float }))?
                     }
                     attrs::constant_value::ConstantValue::Double(double) => {
-                        Constant(CpTag::Double, CpInfo::Double { double })
+                        constant_pool.push(Constant(CpTag::Double, CpInfo::Double { double }))?
                     }
                     attrs::constant_value::ConstantValue::String(string) => {
-                        let string_index =
-                            constant_pool.push(Constant(CpTag::Utf8, CpInfo::Utf8 { string }));
-                        Constant(CpTag::String, CpInfo::String { string_index })
+                        constant_pool.push_string(string)?
                     }
                 };
-                constant_pool.push(constant).to_be_bytes().to_vec()
+                constant_index.to_be_bytes().to_vec()
             }
             Attribute::LineNumberTable(line_number_table) => {
-                let mut writer = ByteWriter::new();
-                writer.write(line_number_table);
-                writer.into()
+                write_line_number_table(line_number_table, labels)?
+            }
+            Attribute::LocalVariableTable(local_variable_table) => {
+                write_local_variable_table(local_variable_table, constant_pool, labels)?
             }
-            Attribute::LocalVariableTable(line_number_table) => {
-                write_local_variable_table(line_number_table, constant_pool)
+            Attribute::LocalVariableTypeTable(local_variable_type_table) => {
+                write_local_variable_type_table(local_variable_type_table, constant_pool, labels)?
+            }
+            Attribute::StackMapTable(stack_map_table) => {
+                write_stack_map_table(stack_map_table, constant_pool, labels)?
             }
             Attribute::Code(code::Code {
                 max_stack,
@@ -221,21 +481,17 @@ impl Unresolved for UnresolvedAttribute {
                 let mut writer = ByteWriter::new();
                 writer.write(max_stack);
                 writer.write(max_locals);
-                let code_bytes: Vec<w1> = {
-                    let mut code_writer = ByteWriter::new();
-                    for opcode in code {
-                        opcode.encode_opcode_info(constant_pool, &mut code_writer);
-                    }
-                    code_writer.into()
-                };
+                let (code_bytes, labels) = code::Code::encode_instructions(code, constant_pool)?;
                 let code_length = code_bytes.len() as w4;
                 writer.write(code_length);
                 for byte in code_bytes {
                     writer.write_byte(byte);
                 }
-                write_exception_table(constant_pool, exception_table, &mut writer);
-                let unresolved_attributes: Vec<UnresolvedAttribute> =
-                    Unresolved::unresolve(attributes, constant_pool);
+                write_exception_table(constant_pool, exception_table, &labels, &mut writer)?;
+                let unresolved_attributes: Vec<UnresolvedAttribute> = attributes
+                    .into_iter()
+                    .map(|attribute| Self::from_attribute(attribute, constant_pool, &labels))
+                    .collect::<Result<_, _>>()?;
                 writer.write(unresolved_attributes);
 
                 writer.into()
@@ -244,33 +500,103 @@ impl Unresolved for UnresolvedAttribute {
                 let mut writer = ByteWriter::new();
                 writer.write(method_parameters.len() as w1);
                 for MethodParameter { name, access_flags } in method_parameters {
-                    let name_index: w2 = name.map_or(0, |name| {
-                        constant_pool.push(Constant(CpTag::Utf8, CpInfo::Utf8 { string: name }))
-                    });
+                    let name_index: w2 =
+                        name.map_or(Ok(0), |name| constant_pool.push_utf8(name))?;
                     writer.write(name_index);
                     writer.write(access_flags);
                 }
 
                 writer.into()
             }
+            Attribute::RuntimeVisibleAnnotations(annotations)
+            | Attribute::RuntimeInvisibleAnnotations(annotations) => {
+                write_annotations(annotations, constant_pool)?
+            }
+            Attribute::RuntimeVisibleParameterAnnotations(parameter_annotations)
+            | Attribute::RuntimeInvisibleParameterAnnotations(parameter_annotations) => {
+                write_parameter_annotations(parameter_annotations, constant_pool)?
+            }
+            Attribute::AnnotationDefault(value) => {
+                let mut writer = ByteWriter::new();
+                write_element_value(value, constant_pool, &mut writer)?;
+                writer.into()
+            }
+            Attribute::RuntimeVisibleTypeAnnotations(annotations)
+            | Attribute::RuntimeInvisibleTypeAnnotations(annotations) => {
+                write_type_annotations(annotations, constant_pool, labels)?
+            }
+            Attribute::InnerClasses(inner_classes) => {
+                write_inner_classes(inner_classes, constant_pool)?
+            }
+            Attribute::EnclosingMethod(enclosing_method) => {
+                write_enclosing_method(enclosing_method, constant_pool)?
+            }
+            Attribute::Module(module) => write_module(module, constant_pool)?,
+            Attribute::ModulePackages(packages) => write_module_packages(packages, constant_pool)?,
+            Attribute::ModuleMainClass(main_class) => {
+                constant_pool.push_class(main_class)?.to_be_bytes().to_vec()
+            }
+            Attribute::NestHost(host_class) => {
+                constant_pool.push_class(host_class)?.to_be_bytes().to_vec()
+            }
+            Attribute::Exceptions(classes)
+            | Attribute::NestMembers(classes)
+            | Attribute::PermittedSubclasses(classes) => write_classes(classes, constant_pool)?,
+            Attribute::Record(components) => {
+                let mut writer = ByteWriter::new();
+                writer.write(components.len() as w2);
+                for RecordComponent {
+                    name,
+                    descriptor,
+                    attributes,
+                } in components
+                {
+                    writer.write(constant_pool.push_utf8(name)?);
+                    writer.write(constant_pool.push_utf8(descriptor.encode())?);
+                    let unresolved_attributes: Vec<UnresolvedAttribute> =
+                        Unresolved::unresolve(attributes, constant_pool)?;
+                    writer.write(unresolved_attributes);
+                }
+
+                writer.into()
+            }
+            Attribute::SourceDebugExtension(source_debug_extension) => {
+                write_source_debug_extension(source_debug_extension)
+            }
             Attribute::SourceFile(source_file_name) => constant_pool
-                .push(Constant(
-                    CpTag::Utf8,
-                    CpInfo::Utf8 {
-                        string: source_file_name,
-                    },
-                ))
+                .push_utf8(source_file_name)?
                 .to_be_bytes()
                 .to_vec(),
+            Attribute::BootstrapMethods => {
+                let mut writer = ByteWriter::new();
+                let bootstrap_methods = constant_pool.bootstrap_methods();
+                writer.write(bootstrap_methods.len() as w2);
+                for BootstrapMethodRef {
+                    method_ref,
+                    arguments,
+                } in bootstrap_methods
+                {
+                    writer.write(*method_ref);
+                    writer.write(arguments.len() as w2);
+                    for argument in arguments {
+                        writer.write(*argument);
+                    }
+                }
+
+                writer.into()
+            }
             Attribute::Synthetic => vec![],
             Attribute::Deprecated => vec![],
-            Attribute::Signature { signature_index } => signature_index.to_be_bytes().to_vec(),
+            Attribute::Signature(signature) => {
+                constant_pool.push_utf8(signature)?.to_be_bytes().to_vec()
+            }
             Attribute::UNIMPLEMENTED_ATTRIBUTE_TODO { info, .. } => info.clone(),
         };
-        Self {
+        Ok(Self {
             name_index,
             _attribute_length: info.len() as w4,
             info,
-        }
+            location: Location::default(),
+        })
     }
 }
@@ -1,50 +1,53 @@
+use crate::analysis::frames::compute_method_frames_in;
+use crate::analysis::maxs::compute_method_maxs_in;
 use crate::bytecode::attributes::UnresolvedAttribute;
 use crate::bytecode::fields::UnresolvedField;
 use crate::bytecode::interfaces::UnresolvedInterfaces;
 use crate::bytecode::methods::UnresolvedMethod;
 use crate::bytecode::reader::{ByteReader, Take};
 use crate::bytecode::unresolved::Unresolved;
-use crate::bytecode::writer::{ByteWriter, Writeable};
-use crate::constant_pool::{Constant, ConstantPool, CpInfo, CpTag};
+use crate::bytecode::writer::{ByteWriter, TryWriteable};
+use crate::constant_pool::ConstantPool;
+use crate::error::{ClassFileError, ParseError};
+use crate::model::attrs::Attribute;
 use crate::{w2, Class};
 
 impl Take<Class> for ByteReader {
-    fn take(&mut self) -> Result<Class, String> {
-        let version = self
-            .take()
-            .map_err(|e| format!("Error parsing version:\n\t{}", e))?;
-
-        let constant_pool: ConstantPool = self
-            .take()
-            .map_err(|e| format!("Error parsing constant pool:\n\t{}", e))?;
-
-        let access_flags = self
-            .take()
-            .map_err(|e| format!("Error parsing access flags:\n\t{}", e))?;
-
-        let this_class_index: w2 = self.take()?;
-        let this_class = constant_pool.get_class_name(this_class_index)?;
-
-        let super_class_index: w2 = self.take()?;
-        let super_class = if super_class_index == 0 {
-            Option::None
-        } else {
-            Option::Some(constant_pool.get_class_name(super_class_index)?)
-        };
+    fn take(&mut self) -> Result<Class, ParseError> {
+        let version = self.context("version", |reader| reader.take())?;
 
-        let unresolved_interfaces: UnresolvedInterfaces = self.take()?;
-        let interfaces = unresolved_interfaces.resolve(&constant_pool)?;
+        let mut constant_pool: ConstantPool = self.take()?;
 
-        let unresolved_fields: Vec<UnresolvedField> = self.take()?;
-        let fields = unresolved_fields.resolve(&constant_pool)?;
+        let access_flags = self.context("access_flags", |reader| reader.take())?;
 
-        let unresolved_methods: Vec<UnresolvedMethod> = self.take()?;
-        let methods = unresolved_methods.resolve(&constant_pool)?;
+        let this_class = self.context("this_class", |reader| {
+            reader.take_constant(|index| constant_pool.get_class_name(index))
+        })?;
+
+        let super_class = self.context("super_class", |reader| {
+            reader.take_constant(|index| {
+                if index == 0 {
+                    Ok(Option::None)
+                } else {
+                    constant_pool.get_class_name(index).map(Option::Some)
+                }
+            })
+        })?;
 
+        let unresolved_interfaces: UnresolvedInterfaces = self.take()?;
+        let unresolved_fields: Vec<UnresolvedField> = self.take()?;
+        let unresolved_methods: Vec<UnresolvedMethod> = self.take()?;
         let unresolved_attributes: Vec<UnresolvedAttribute> = self.take()?;
-        let attributes = unresolved_attributes
-            .resolve(&constant_pool)
-            .map_err(|e| format!("Error resolving class attributes:\n\t{}", e))?;
+
+        // Call sites in the methods' code refer to the class' BootstrapMethods attribute
+        for attribute in &unresolved_attributes {
+            attribute.load_bootstrap_methods(&mut constant_pool)?;
+        }
+
+        let interfaces = unresolved_interfaces.resolve(&constant_pool)?;
+        let fields = unresolved_fields.resolve(&constant_pool)?;
+        let methods = unresolved_methods.resolve(&constant_pool)?;
+        let attributes = unresolved_attributes.resolve(&constant_pool)?;
 
         Ok(Class {
             version,
@@ -55,57 +58,70 @@ impl Take<Class> for ByteReader {
             fields,
             methods,
             attributes,
+            constant_pool: if self.is_lossless() {
+                Option::Some(constant_pool)
+            } else {
+                Option::None
+            },
         })
     }
 }
 
-impl Writeable for Class {
-    fn write(self, writer: &mut ByteWriter) {
+impl TryWriteable for Class {
+    fn try_write(self, writer: &mut ByteWriter) -> Result<(), ClassFileError> {
         let version = self.version;
-        let mut constant_pool: ConstantPool = ConstantPool::new();
+        let mut constant_pool: ConstantPool = self.constant_pool.unwrap_or_else(ConstantPool::new);
         let access_flags = self.access_flags;
 
-        let this_class_index: w2 = {
-            let class_name_index = constant_pool.push(Constant(
-                CpTag::Utf8,
-                CpInfo::Utf8 {
-                    string: self.this_class,
-                },
-            ));
-            constant_pool.push(Constant(
-                CpTag::Class,
-                CpInfo::Class {
-                    name_index: class_name_index,
-                },
-            ))
+        // Frames come last, since they may add code that needs a stack slot
+        let analysis_failed = |error| ClassFileError::AnalysisFailed {
+            error: Box::new(error),
         };
+        let mut methods = self.methods;
+        for method in &mut methods {
+            if writer.computes_maxs() {
+                compute_method_maxs_in(method, &self.this_class, Some(&constant_pool))
+                    .map_err(analysis_failed)?;
+            }
+            if let Some(hierarchy) = writer.hierarchy() {
+                compute_method_frames_in(method, &self.this_class, hierarchy, Some(&constant_pool))
+                    .map_err(analysis_failed)?;
+            }
+        }
+
+        let this_class_index: w2 = constant_pool.push_class(self.this_class)?;
 
         let super_class_index: w2 = match self.super_class {
             Option::None => 0,
-            Option::Some(class_name) => {
-                let class_name_index =
-                    constant_pool.push(Constant(CpTag::Utf8, CpInfo::Utf8 { string: class_name }));
-                constant_pool.push(Constant(
-                    CpTag::Class,
-                    CpInfo::Class {
-                        name_index: class_name_index,
-                    },
-                ))
-            }
+            Option::Some(class_name) => constant_pool.push_class(class_name)?,
         };
 
         let unresolved_interfaces: UnresolvedInterfaces =
-            Unresolved::unresolve(self.interfaces, &mut constant_pool);
+            Unresolved::unresolve(self.interfaces, &mut constant_pool)?;
         let unresolved_fields: Vec<UnresolvedField> =
-            Unresolved::unresolve(self.fields, &mut constant_pool);
+            Unresolved::unresolve(self.fields, &mut constant_pool)?;
         let unresolved_methods: Vec<UnresolvedMethod> =
-            Unresolved::unresolve(self.methods, &mut constant_pool);
-
+            Unresolved::unresolve(methods, &mut constant_pool)?;
+
+        // The BootstrapMethods table is rebuilt from the call sites in the methods,
+        // so it's written where it was read if there are any, and appended otherwise
+        let mut attributes = self.attributes;
+        let has_bootstrap_methods = !constant_pool.bootstrap_methods().is_empty();
+        attributes.retain(|attribute| {
+            has_bootstrap_methods || !matches!(attribute, Attribute::BootstrapMethods)
+        });
+        if has_bootstrap_methods
+            && !attributes
+                .iter()
+                .any(|attribute| matches!(attribute, Attribute::BootstrapMethods))
+        {
+            attributes.push(Attribute::BootstrapMethods);
+        }
         let unresolved_attributes: Vec<UnresolvedAttribute> =
-            Unresolved::unresolve(self.attributes, &mut constant_pool);
+            Unresolved::unresolve(attributes, &mut constant_pool)?;
 
         writer.write(version);
-        writer.write(constant_pool);
+        writer.try_write(constant_pool)?;
         writer.write(access_flags);
         writer.write(this_class_index);
         writer.write(super_class_index);
@@ -113,5 +129,6 @@ impl Writeable for Class {
         writer.write(unresolved_fields);
         writer.write(unresolved_methods);
         writer.write(unresolved_attributes);
+        Ok(())
     }
 }
@@ -1,17 +1,20 @@
 use crate::bytecode::reader::{ByteReader, Take};
 use crate::constant_pool::{double_utils, Constant, ConstantPool, CpInfo, CpTag};
+use crate::error::{ClassFileError, ParseError};
+use crate::mutf8;
 use crate::typedefs::{w1, w2};
 use core::result::Result;
 use core::result::Result::Ok;
 
-use super::writer::{ByteWriter, Writeable};
+use super::writer::{ByteWriter, TryWriteable, Writeable};
 
 impl Take<CpTag> for ByteReader {
-    fn take(&mut self) -> Result<CpTag, String> {
+    fn take(&mut self) -> Result<CpTag, ParseError> {
         use CpTag::{
             Class, Double, Dynamic, Fieldref, Float, Integer, InterfaceMethodref, InvokeDynamic,
             Long, MethodHandle, MethodType, Methodref, Module, NameAndType, Package, String, Utf8,
         };
+        let offset = self.offset();
         let i: w1 = self.take()?;
         match i {
             1 => Ok(Utf8),
@@ -31,7 +34,7 @@ impl Take<CpTag> for ByteReader {
             18 => Ok(InvokeDynamic),
             19 => Ok(Module),
             20 => Ok(Package),
-            it => Err(format!("Unexpected Constant type ID `{it}`!", it = it)),
+            tag => Err(self.error_at(offset, ClassFileError::UnknownConstantTag { tag })),
         }
     }
 }
@@ -66,37 +69,40 @@ impl Writeable for CpTag {
 }
 
 impl Take<ConstantPool> for ByteReader {
-    fn take(&mut self) -> Result<ConstantPool, String> {
+    fn take(&mut self) -> Result<ConstantPool, ParseError> {
         let constants_pool_count: w2 = self.take()?;
         let mut pool = ConstantPool::new();
         let mut skip: bool = false;
-        for _offset in 1..(constants_pool_count) {
+        for offset in 1..(constants_pool_count) {
             if skip {
                 skip = false;
                 pool.push_empty();
                 continue;
             }
-            let tag = self.take()?;
+            self.context(format!("constant_pool[{}]", offset), |reader| {
+                let start = reader.offset();
+                let tag = reader.take()?;
+                let info = CpInfo::of(&tag, reader)?;
 
-            // Long and Double "swallow" another index
-            skip = matches!(tag, CpTag::Double | CpTag::Long);
+                // Long and Double "swallow" another index
+                skip = matches!(tag, CpTag::Double | CpTag::Long);
 
-            let info = CpInfo::of(&tag, self)?;
-            let constant = Constant(tag, info);
-            pool.push(constant);
+                pool.append(Constant(tag, info))
+                    .map_err(|e| reader.error_at(start, e))
+            })?;
         }
         Ok(pool)
     }
 }
-impl Writeable for ConstantPool {
-    fn write(self, writer: &mut ByteWriter) {
+impl TryWriteable for ConstantPool {
+    fn try_write(self, writer: &mut ByteWriter) -> Result<(), ClassFileError> {
         let mut inner_writer: ByteWriter = ByteWriter::new();
         for index in 1..self.len() {
             match &self[index as w2] {
                 Option::None => {}
                 Option::Some(Constant(tag, info)) => {
                     inner_writer.write(*tag);
-                    inner_writer.write(info.clone());
+                    inner_writer.try_write(info.clone())?;
                 }
             }
         }
@@ -105,16 +111,22 @@ impl Writeable for ConstantPool {
         for byte in constant_pool_bytes {
             writer.write_byte(byte);
         }
+        Ok(())
     }
 }
 
-impl Writeable for CpInfo {
-    fn write(self, writer: &mut ByteWriter) {
+impl TryWriteable for CpInfo {
+    fn try_write(self, writer: &mut ByteWriter) -> Result<(), ClassFileError> {
         match self {
             CpInfo::Package { name_index } => writer.write(name_index),
             CpInfo::Utf8 { string } => {
-                writer.write(string.len() as w2);
-                for byte in string.bytes() {
+                let bytes = mutf8::encode(&string);
+                let length =
+                    w2::try_from(bytes.len()).map_err(|_| ClassFileError::StringTooLong {
+                        length: bytes.len(),
+                    })?;
+                writer.write(length);
+                for byte in bytes {
                     writer.write(byte);
                 }
             }
@@ -184,26 +196,29 @@ impl Writeable for CpInfo {
             }
             CpInfo::Module { name_index } => writer.write(name_index),
         }
+        Ok(())
     }
 }
 
 impl CpInfo {
-    fn of(tag: &CpTag, bytes: &mut ByteReader) -> Result<CpInfo, String> {
+    fn of(tag: &CpTag, bytes: &mut ByteReader) -> Result<CpInfo, ParseError> {
         use crate::constant_pool::double_utils;
         use crate::constant_pool::CpInfo::*;
-        return Ok(match tag {
+        Ok(match tag {
             CpTag::Package => Package {
                 name_index: bytes.take()?,
             },
             CpTag::Utf8 => {
                 let length: w2 = bytes.take()?;
-                let mut vec: Vec<w1> = vec![];
-                for _ in 0..length {
-                    vec.push(bytes.take()?)
-                }
-                Utf8 {
-                    string: std::string::String::from_utf8_lossy(vec.as_slice()).to_string(),
-                }
+                let offset = bytes.offset();
+                let string =
+                    mutf8::decode(bytes.take_bytes(length as usize)?).map_err(|position| {
+                        bytes.error_at(
+                            offset + position,
+                            ClassFileError::InvalidModifiedUtf8 { position },
+                        )
+                    })?;
+                Utf8 { string }
             }
             CpTag::Integer => Integer { int: bytes.take()? },
             CpTag::Float => Float {
@@ -255,6 +270,6 @@ impl CpInfo {
             CpTag::Module => Module {
                 name_index: bytes.take()?,
             },
-        });
+        })
     }
 }
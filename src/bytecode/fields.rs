@@ -2,7 +2,9 @@ use crate::bytecode::attributes::UnresolvedAttribute;
 use crate::bytecode::reader::{ByteReader, Take};
 use crate::bytecode::unresolved::Unresolved;
 use crate::bytecode::writer::{ByteWriter, Writeable};
-use crate::constant_pool::{Constant, ConstantPool, CpInfo, CpTag};
+use crate::constant_pool::ConstantPool;
+use crate::error::{ClassFileError, Location, ParseError};
+use crate::model::attrs::Attribute;
 use crate::model::field::{Field, FieldAccessModifier};
 use crate::w2;
 
@@ -10,23 +12,24 @@ pub struct UnresolvedField {
     access_flags: Vec<FieldAccessModifier>,
     name_index: w2,
     descriptor_index: w2,
-    // TODO: add a descriptor struct?
     attributes: Vec<UnresolvedAttribute>,
+    location: Location,
 }
 
 impl Take<Vec<UnresolvedField>> for ByteReader {
-    fn take(&mut self) -> Result<Vec<UnresolvedField>, String> {
+    fn take(&mut self) -> Result<Vec<UnresolvedField>, ParseError> {
         let field_count: w2 = self.take()?;
         let mut result = vec![];
-        for _ in 0..field_count {
-            result.push(self.take()?);
+        for i in 0..field_count {
+            result.push(self.context(format!("fields[{}]", i), |reader| reader.take())?);
         }
         Ok(result)
     }
 }
 
 impl Take<UnresolvedField> for ByteReader {
-    fn take(&mut self) -> Result<UnresolvedField, String> {
+    fn take(&mut self) -> Result<UnresolvedField, ParseError> {
+        let location = self.location();
         let access_flags = self.take()?;
         let name_index = self.take()?;
         let descriptor_index = self.take()?;
@@ -37,6 +40,7 @@ impl Take<UnresolvedField> for ByteReader {
             name_index,
             descriptor_index,
             attributes,
+            location,
         })
     }
 }
@@ -57,34 +61,49 @@ impl Unresolved for UnresolvedField {
     type Resolved = Field;
     type NeededToResolve = ConstantPool;
 
-    fn resolve(self, constant_pool: &Self::NeededToResolve) -> Result<Self::Resolved, String> {
+    fn resolve(self, constant_pool: &Self::NeededToResolve) -> Result<Self::Resolved, ParseError> {
+        let locate = |e| ParseError::new(e, self.location.clone());
+        let name = constant_pool.get_utf8(self.name_index).map_err(locate)?;
+        let descriptor = constant_pool
+            .get_field_type(self.descriptor_index)
+            .map_err(locate)?;
+        let attributes: Vec<Attribute> = self.attributes.resolve(constant_pool)?;
+        // The JVM ignores the `ConstantValue` of instance fields
+        let is_static = self
+            .access_flags
+            .iter()
+            .any(|flag| matches!(flag, FieldAccessModifier::STATIC));
+        for attribute in &attributes {
+            match attribute {
+                Attribute::ConstantValue(value) if is_static && !value.fits(&descriptor) => {
+                    return Err(locate(ClassFileError::ConstantValueMismatch {
+                        descriptor: descriptor.encode(),
+                        found: value.tag(),
+                    }));
+                }
+                _ => {}
+            }
+        }
         Ok(Field::new(
             self.access_flags,
-            constant_pool.get_utf8(self.name_index)?,
-            constant_pool.get_utf8(self.descriptor_index)?,
-            self.attributes.resolve(constant_pool)?,
+            name,
+            descriptor,
+            attributes,
         ))
     }
-    fn unresolve(resolved: Self::Resolved, constant_pool: &mut Self::NeededToResolve) -> Self {
-        let name_index = constant_pool.push(Constant(
-            CpTag::Utf8,
-            CpInfo::Utf8 {
-                string: resolved.name,
-            },
-        ));
-
-        let descriptor_index = constant_pool.push(Constant(
-            CpTag::Utf8,
-            CpInfo::Utf8 {
-                string: resolved.descriptor,
-            },
-        ));
+    fn unresolve(
+        resolved: Self::Resolved,
+        constant_pool: &mut Self::NeededToResolve,
+    ) -> Result<Self, ClassFileError> {
+        let name_index = constant_pool.push_utf8(resolved.name)?;
+        let descriptor_index = constant_pool.push_utf8(resolved.descriptor.encode())?;
 
-        Self {
+        Ok(Self {
             access_flags: resolved.access_flags,
             name_index: name_index as w2,
             descriptor_index: descriptor_index as w2,
-            attributes: Unresolved::unresolve(resolved.attributes, constant_pool),
-        }
+            attributes: Unresolved::unresolve(resolved.attributes, constant_pool)?,
+            location: Location::default(),
+        })
     }
 }
@@ -1,51 +1,56 @@
 use crate::bytecode::reader::{ByteReader, Take};
 use crate::bytecode::unresolved::Unresolved;
 use crate::bytecode::writer::{ByteWriter, Writeable};
-use crate::constant_pool::{Constant, ConstantPool, CpInfo, CpTag};
+use crate::constant_pool::ConstantPool;
+use crate::error::{ClassFileError, Location, ParseError};
 use crate::model::interface::Interface;
 use crate::w2;
 
-pub type UnresolvedInterfaces = Vec<w2>;
+pub struct UnresolvedInterface {
+    class_index: w2,
+    location: Location,
+}
 
-impl Unresolved for UnresolvedInterfaces {
-    type Resolved = Vec<Interface>;
-    type NeededToResolve = ConstantPool;
+pub type UnresolvedInterfaces = Vec<UnresolvedInterface>;
 
-    fn resolve(self, constant_pool: &Self::NeededToResolve) -> Result<Self::Resolved, String> {
-        let mut interfaces = vec![];
-
-        for index in self {
-            interfaces.push(Interface::new(constant_pool.get_class_name(index)?));
-        }
+impl Unresolved for UnresolvedInterface {
+    type Resolved = Interface;
+    type NeededToResolve = ConstantPool;
 
-        Ok(interfaces)
+    fn resolve(self, constant_pool: &Self::NeededToResolve) -> Result<Self::Resolved, ParseError> {
+        let name = constant_pool
+            .get_class_name(self.class_index)
+            .map_err(|e| ParseError::new(e, self.location))?;
+        Ok(Interface::new(name))
     }
 
-    fn unresolve(resolved: Self::Resolved, constant_pool: &mut Self::NeededToResolve) -> Self {
-        let mut unresolved = UnresolvedInterfaces::default();
-        for Interface(interface) in resolved {
-            let class_name_index =
-                constant_pool.push(Constant(CpTag::Utf8, CpInfo::Utf8 { string: interface }));
+    fn unresolve(
+        resolved: Self::Resolved,
+        constant_pool: &mut Self::NeededToResolve,
+    ) -> Result<Self, ClassFileError> {
+        let Interface(interface) = resolved;
+        let class_index = constant_pool.push_class(interface)?;
 
-            let class_index = constant_pool.push(Constant(
-                CpTag::Class,
-                CpInfo::Class {
-                    name_index: class_name_index,
-                },
-            ));
-
-            unresolved.push(class_index);
-        }
-        unresolved
+        Ok(Self {
+            class_index,
+            location: Location::default(),
+        })
     }
 }
 
 impl Take<UnresolvedInterfaces> for ByteReader {
-    fn take(&mut self) -> Result<UnresolvedInterfaces, String> {
+    fn take(&mut self) -> Result<UnresolvedInterfaces, ParseError> {
         let interface_count: w2 = self.take()?;
         let mut interfaces = vec![];
-        for _ in 0..interface_count {
-            interfaces.push(self.take()?)
+        for i in 0..interface_count {
+            self.context(format!("interfaces[{}]", i), |reader| {
+                let location = reader.location();
+                interfaces.push(UnresolvedInterface {
+                    class_index: reader.take()?,
+                    location,
+                });
+                Ok(())
+            })?;
         }
         Ok(interfaces)
     }
@@ -55,7 +60,7 @@ impl Writeable for UnresolvedInterfaces {
     fn write(self, writer: &mut ByteWriter) {
         writer.write(self.len() as w2);
         for interface in self {
-            writer.write(interface)
+            writer.write(interface.class_index)
         }
     }
 }
@@ -2,7 +2,8 @@ use crate::bytecode::attributes::UnresolvedAttribute;
 use crate::bytecode::reader::{ByteReader, Take};
 use crate::bytecode::unresolved::Unresolved;
 use crate::bytecode::writer::{ByteWriter, Writeable};
-use crate::constant_pool::{Constant, ConstantPool, CpInfo, CpTag};
+use crate::constant_pool::ConstantPool;
+use crate::error::{ClassFileError, Location, ParseError};
 use crate::model::method::{Method, MethodAccessModifier};
 use crate::w2;
 
@@ -10,12 +11,13 @@ pub struct UnresolvedMethod {
     access_flags: Vec<MethodAccessModifier>,
     name_index: w2,
     descriptor_index: w2,
-    // TODO: add a descriptor struct?
     attributes: Vec<UnresolvedAttribute>,
+    location: Location,
 }
 
 impl Take<UnresolvedMethod> for ByteReader {
-    fn take(&mut self) -> Result<UnresolvedMethod, String> {
+    fn take(&mut self) -> Result<UnresolvedMethod, ParseError> {
+        let location = self.location();
         let access_flags = self.take()?;
         let name_index = self.take()?;
         let descriptor_index = self.take()?;
@@ -26,16 +28,17 @@ impl Take<UnresolvedMethod> for ByteReader {
             name_index,
             descriptor_index,
             attributes,
+            location,
         })
     }
 }
 
 impl Take<Vec<UnresolvedMethod>> for ByteReader {
-    fn take(&mut self) -> Result<Vec<UnresolvedMethod>, String> {
+    fn take(&mut self) -> Result<Vec<UnresolvedMethod>, ParseError> {
         let field_count: w2 = self.take()?;
         let mut result = vec![];
-        for _ in 0..field_count {
-            result.push(self.take()?);
+        for i in 0..field_count {
+            result.push(self.context(format!("methods[{}]", i), |reader| reader.take())?);
         }
         Ok(result)
     }
@@ -57,35 +60,31 @@ impl Unresolved for UnresolvedMethod {
     type Resolved = Method;
     type NeededToResolve = ConstantPool;
 
-    fn resolve(self, constant_pool: &Self::NeededToResolve) -> Result<Self::Resolved, String> {
+    fn resolve(self, constant_pool: &Self::NeededToResolve) -> Result<Self::Resolved, ParseError> {
+        let locate = |e| ParseError::new(e, self.location.clone());
         Ok(Method {
             access_flags: self.access_flags,
-            name: constant_pool.get_utf8(self.name_index)?,
-            descriptor: constant_pool.get_utf8(self.descriptor_index)?,
+            name: constant_pool.get_utf8(self.name_index).map_err(locate)?,
+            descriptor: constant_pool
+                .get_method_descriptor(self.descriptor_index)
+                .map_err(locate)?,
             attributes: self.attributes.resolve(constant_pool)?,
         })
     }
 
-    fn unresolve(resolved: Self::Resolved, constant_pool: &mut Self::NeededToResolve) -> Self {
-        let name_index = constant_pool.push(Constant(
-            CpTag::Utf8,
-            CpInfo::Utf8 {
-                string: resolved.name,
-            },
-        ));
+    fn unresolve(
+        resolved: Self::Resolved,
+        constant_pool: &mut Self::NeededToResolve,
+    ) -> Result<Self, ClassFileError> {
+        let name_index = constant_pool.push_utf8(resolved.name)?;
+        let descriptor_index = constant_pool.push_utf8(resolved.descriptor.encode())?;
 
-        let descriptor_index = constant_pool.push(Constant(
-            CpTag::Utf8,
-            CpInfo::Utf8 {
-                string: resolved.descriptor,
-            },
-        ));
-
-        Self {
+        Ok(Self {
             access_flags: resolved.access_flags,
             name_index: name_index as w2,
             descriptor_index: descriptor_index as w2,
-            attributes: Unresolved::unresolve(resolved.attributes, constant_pool),
-        }
+            attributes: Unresolved::unresolve(resolved.attributes, constant_pool)?,
+            location: Location::default(),
+        })
     }
 }
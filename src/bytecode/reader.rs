@@ -1,8 +1,18 @@
+use crate::error::{ClassFileError, Location, ParseError};
 use crate::typedefs::*;
 
+/// Deepest breadcrumb path a [ByteReader] accepts; well-formed classes stay far below this
+pub(crate) const MAX_NESTING: usize = 32;
+
 pub struct ByteReader {
     buffer: Vec<w1>,
     ptr: usize,
+    /// Offset of `buffer[0]` in the original input
+    base: usize,
+    /// Breadcrumbs leading to the item currently being read
+    path: Vec<String>,
+    /// Whether to keep the original constant pool, see [ByteReader::lossless]
+    lossless: bool,
 }
 
 pub trait ByteSize {
@@ -11,11 +21,11 @@ pub trait ByteSize {
 }
 
 pub trait Take<T> {
-    fn take(&mut self) -> Result<T, String>;
+    fn take(&mut self) -> Result<T, ParseError>;
 }
 
 impl<Un: ByteSize> Take<Un> for ByteReader {
-    fn take(&mut self) -> Result<Un, String> {
+    fn take(&mut self) -> Result<Un, ParseError> {
         let bytes = self.take_bytes(Un::width())?;
         Ok(Un::read(bytes))
     }
@@ -23,11 +33,41 @@ impl<Un: ByteSize> Take<Un> for ByteReader {
 
 impl From<Vec<w1>> for ByteReader {
     fn from(buffer: Vec<w1>) -> Self {
-        Self { buffer, ptr: 0 }
+        Self {
+            buffer,
+            ptr: 0,
+            base: 0,
+            path: vec![],
+            lossless: false,
+        }
     }
 }
 
 impl ByteReader {
+    /// Creates a reader over a slice of the input that starts at [location]
+    pub(crate) fn at(buffer: Vec<w1>, location: Location) -> Self {
+        Self {
+            buffer,
+            ptr: 0,
+            base: location.offset,
+            path: location.path,
+            lossless: false,
+        }
+    }
+
+    /// Keeps the original constant pool in [Class::constant_pool](crate::model::class::Class::constant_pool),
+    /// so that writing the class back reuses its order and indices.
+    /// A class that wasn't modified is then written back byte-for-byte,
+    /// and new constants are appended after the original ones.
+    pub fn lossless(mut self) -> Self {
+        self.lossless = true;
+        self
+    }
+
+    pub fn is_lossless(&self) -> bool {
+        self.lossless
+    }
+
     ///
     ///```rust
     /// use rusty_javap::bytecode::reader::ByteReader;
@@ -37,21 +77,25 @@ impl ByteReader {
     ///```
     ///```rust
     /// use rusty_javap::bytecode::reader::ByteReader;
+    /// use rusty_javap::error::ClassFileError;
     /// let mut reader = ByteReader::from(vec![1;12]);
     /// assert!(!reader.is_empty());
     /// let _ = reader.take_bytes(4);
     /// assert!(!reader.is_empty());
     /// let _ = reader.take_bytes(8);
     /// assert!(reader.is_empty());
-    /// assert_eq!(reader.take_bytes(1), Err("Can't take 1 bytes from buffer containing 0 bytes!".to_string()));
+    /// assert_eq!(reader.take_bytes(1).unwrap_err().error, ClassFileError::UnexpectedEof { requested: 1, available: 0 });
     ///```
     ///```rust
     /// use rusty_javap::bytecode::reader::ByteReader;
+    /// use rusty_javap::error::ClassFileError;
     /// let mut reader = ByteReader::from(vec![1;12]);
     /// assert!(!reader.is_empty());
     /// let _ = reader.take_bytes(4);
     /// assert!(!reader.is_empty());
-    /// assert_eq!(reader.take_bytes(10), Err("Can't take 10 bytes from buffer containing 8 bytes!".to_string()));
+    /// let error = reader.take_bytes(10).unwrap_err();
+    /// assert_eq!(error.error, ClassFileError::UnexpectedEof { requested: 10, available: 8 });
+    /// assert_eq!(error.location.offset, 4);
     ///```
     ///
     pub fn is_empty(&self) -> bool {
@@ -64,32 +108,118 @@ impl ByteReader {
             .to_vec()
     }
 
+    /// Position relative to the start of this reader's buffer
+    pub fn position(&self) -> usize {
+        self.ptr
+    }
+
+    /// Position relative to the start of the original input
+    pub fn offset(&self) -> usize {
+        self.base + self.ptr
+    }
+
+    /// Current offset and breadcrumb path
+    pub fn location(&self) -> Location {
+        Location {
+            offset: self.offset(),
+            path: self.path.clone(),
+        }
+    }
+
+    /// Creates an error located at the current position
+    pub fn error(&self, error: ClassFileError) -> ParseError {
+        self.error_at(self.offset(), error)
+    }
+
+    /// Creates an error located at [offset] with the current breadcrumb path
+    pub fn error_at(&self, offset: usize, error: ClassFileError) -> ParseError {
+        ParseError::new(
+            error,
+            Location {
+                offset,
+                path: self.path.clone(),
+            },
+        )
+    }
+
+    /// Runs [read] with [segment] appended to the breadcrumb path
+    ///
+    ///```rust
+    /// use rusty_javap::bytecode::reader::{ByteReader, Take};
+    /// let mut reader = ByteReader::from(vec![0; 3]);
+    /// let error = reader
+    ///     .context("methods[3]", |reader| reader.context("code[pc=1]", |reader| {
+    ///         let _: u16 = reader.take()?;
+    ///         let short: u16 = reader.take()?;
+    ///         Ok(short)
+    ///     }))
+    ///     .unwrap_err();
+    /// assert_eq!(error.location.path, vec!["methods[3]", "code[pc=1]"]);
+    /// assert_eq!(error.location.offset, 2);
+    /// assert!(reader.location().path.is_empty());
+    ///```
+    ///
+    /// Fails with [ClassFileError::NestingTooDeep] rather than recursing without bound
+    /// on inputs that nest attributes inside each other.
+    pub fn context<T>(
+        &mut self,
+        segment: impl Into<String>,
+        read: impl FnOnce(&mut Self) -> Result<T, ParseError>,
+    ) -> Result<T, ParseError> {
+        if self.path.len() >= MAX_NESTING {
+            return Err(self.error(ClassFileError::NestingTooDeep));
+        }
+        self.path.push(segment.into());
+        let result = read(self);
+        self.path.pop();
+        result
+    }
+
+    /// Reads a constant pool index and passes it to [resolve],
+    /// locating any resolution error at the index
+    pub fn take_constant<T>(
+        &mut self,
+        resolve: impl FnOnce(w2) -> Result<T, ClassFileError>,
+    ) -> Result<T, ParseError> {
+        let offset = self.offset();
+        let index: w2 = self.take()?;
+        resolve(index).map_err(|e| self.error_at(offset, e))
+    }
+
+    /// Splits off the next [size] bytes into a reader that keeps reporting offsets
+    /// relative to the original input
+    pub fn take_reader(&mut self, size: usize) -> Result<ByteReader, ParseError> {
+        let location = self.location();
+        let bytes = self.take_bytes(size)?.to_vec();
+        Ok(ByteReader::at(bytes, location))
+    }
+
     /// Take [size] bytes from the buffer.
     /// Pass [usize::MAX] to get the whole buffer -- this cannot fail.
     ///
     ///```rust
     /// use rusty_javap::bytecode::reader::ByteReader;
+    /// use rusty_javap::error::ClassFileError;
     /// assert_eq!(ByteReader::from(vec![0; 12]).take_bytes(3), Ok(vec![0;3].as_slice()));
     /// assert_eq!(ByteReader::from(vec![]).take_bytes(0), Ok(vec![].as_slice()));
-    /// assert_eq!(ByteReader::from(vec![1]).take_bytes(3), Err("Can't take 3 bytes from buffer containing 1 bytes!".to_string()));
-    /// assert_eq!(ByteReader::from(vec![]).take_bytes(3), Err("Can't take 3 bytes from buffer containing 0 bytes!".to_string()));
+    /// assert_eq!(ByteReader::from(vec![1]).take_bytes(3).unwrap_err().error, ClassFileError::UnexpectedEof { requested: 3, available: 1 });
+    /// assert_eq!(ByteReader::from(vec![]).take_bytes(3).unwrap_err().error, ClassFileError::UnexpectedEof { requested: 3, available: 0 });
     /// assert_eq!(ByteReader::from(vec![]).take_bytes(usize::MAX), Ok(vec![].as_slice()));
     /// assert_eq!(ByteReader::from(vec![0; 12]).take_bytes(usize::MAX), Ok(vec![0;12].as_slice()));
     ///```
     ///
-    pub fn take_bytes(&mut self, size: usize) -> Result<&[w1], String> {
+    pub fn take_bytes(&mut self, size: usize) -> Result<&[w1], ParseError> {
         let max_request = self.buffer.len() - self.ptr;
         let request = if size == usize::MAX {
             max_request
         } else {
             size
         };
-        let _ = size;
         if request > max_request {
-            return Err(format!(
-                "Can't take {} bytes from buffer containing {} bytes!",
-                request, max_request
-            ));
+            return Err(self.error(ClassFileError::UnexpectedEof {
+                requested: request,
+                available: max_request,
+            }));
         }
         let previous_ptr = self.ptr;
         self.ptr += request;
@@ -101,15 +231,17 @@ impl ByteReader {
 macro_rules! impl_read_for {
     ($t:ty, $width:expr) => {
         impl ByteSize for $t {
+            /// Reads a big-endian value from the first [ByteSize::width] bytes.
+            /// Missing bytes are read as zeroes rather than panicking.
             fn read(bytes: &[w1]) -> $t {
-                let mut u: $t = 0;
-                for i in (0..$width).rev() {
-                    u |= ((bytes[i] as $t) << 8 * ($width - 1 - i))
+                let mut u: w8 = 0;
+                for i in 0..$width {
+                    u = (u << 8) | bytes.get(i).copied().unwrap_or(0) as w8;
                 }
-                return u;
+                u as $t
             }
             fn width() -> usize {
-                return $width;
+                $width
             }
         }
     };
@@ -118,3 +250,6 @@ macro_rules! impl_read_for {
 impl_read_for!(w1, 1);
 impl_read_for!(w2, 2);
 impl_read_for!(w4, 4);
+impl_read_for!(i8, 1);
+impl_read_for!(i16, 2);
+impl_read_for!(i32, 4);
@@ -1,16 +1,24 @@
-pub trait Unresolved {
+use crate::error::{ClassFileError, ParseError};
+
+pub trait Unresolved: Sized {
     type Resolved;
     type NeededToResolve;
 
-    fn resolve(self, _: &Self::NeededToResolve) -> Result<Self::Resolved, String>;
-    fn unresolve(resolved: Self::Resolved, _: &mut Self::NeededToResolve) -> Self;
+    fn resolve(self, _: &Self::NeededToResolve) -> Result<Self::Resolved, ParseError>;
+    fn unresolve(
+        resolved: Self::Resolved,
+        _: &mut Self::NeededToResolve,
+    ) -> Result<Self, ClassFileError>;
 }
 
 impl<T: Unresolved> Unresolved for Vec<T> {
     type Resolved = Vec<T::Resolved>;
     type NeededToResolve = T::NeededToResolve;
 
-    fn resolve(self, needed_to_resolve: &Self::NeededToResolve) -> Result<Self::Resolved, String> {
+    fn resolve(
+        self,
+        needed_to_resolve: &Self::NeededToResolve,
+    ) -> Result<Self::Resolved, ParseError> {
         let mut resolved = vec![];
         for unresolved in self {
             resolved.push(unresolved.resolve(needed_to_resolve)?)
@@ -18,7 +26,10 @@ impl<T: Unresolved> Unresolved for Vec<T> {
         Ok(resolved)
     }
 
-    fn unresolve(resolved: Self::Resolved, needed_to_resolve: &mut Self::NeededToResolve) -> Self {
+    fn unresolve(
+        resolved: Self::Resolved,
+        needed_to_resolve: &mut Self::NeededToResolve,
+    ) -> Result<Self, ClassFileError> {
         resolved
             .into_iter()
             .map(|item| Unresolved::unresolve(item, needed_to_resolve))
@@ -1,16 +1,18 @@
 use crate::bytecode::reader::{ByteReader, Take};
 use crate::bytecode::writer::{ByteWriter, Writeable};
+use crate::error::{ClassFileError, ParseError};
 use crate::model::class::Version;
 use crate::typedefs::*;
 
 impl Take<Version> for ByteReader {
-    fn take(&mut self) -> Result<Version, String> {
+    fn take(&mut self) -> Result<Version, ParseError> {
+        let offset = self.offset();
         let magic: w4 = self.take()?;
         let minor: w2 = self.take()?;
         let major: w2 = self.take()?;
 
         if magic != 0xCAFEBABE {
-            return Err(format!("Wrong 'magic' field: `{:#X}`!", magic));
+            return Err(self.error_at(offset, ClassFileError::BadMagic { found: magic }));
         }
 
         let version = Version::new(magic, major, minor);
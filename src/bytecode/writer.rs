@@ -1,14 +1,50 @@
+use crate::analysis::hierarchy::ClassHierarchy;
+use crate::error::ClassFileError;
 use crate::typedefs::*;
 
 pub struct ByteWriter {
     buffer: Vec<w1>,
+    /// Where to look up classes when recomputing frames, see [ByteWriter::compute_frames]
+    hierarchy: Option<Box<dyn ClassHierarchy>>,
+    /// Whether to recompute `max_stack` and `max_locals`, see [ByteWriter::compute_maxs]
+    maxs: bool,
 }
 
 impl ByteWriter {
     #[allow(clippy::new_without_default)]
     pub fn new() -> Self {
-        Self { buffer: vec![] }
+        Self {
+            buffer: vec![],
+            hierarchy: None,
+            maxs: false,
+        }
+    }
+
+    /// Recomputes `max_stack` and `max_locals` of every method when writing a class,
+    /// with [compute_method_maxs](crate::analysis::maxs::compute_method_maxs).
+    /// Writing fails with [ClassFileError::AnalysisFailed] if a method's code can't be analyzed.
+    pub fn compute_maxs(mut self) -> Self {
+        self.maxs = true;
+        self
+    }
+
+    pub(crate) fn computes_maxs(&self) -> bool {
+        self.maxs
+    }
+
+    /// Recomputes the `StackMapTable` of every method when writing a class,
+    /// with [compute_method_frames](crate::analysis::frames::compute_method_frames).
+    /// Writing fails with [ClassFileError::AnalysisFailed] if a method's code can't be analyzed,
+    /// rather than writing it with frames that may no longer match its code.
+    pub fn compute_frames(mut self, hierarchy: impl ClassHierarchy + 'static) -> Self {
+        self.hierarchy = Some(Box::new(hierarchy));
+        self
     }
+
+    pub(crate) fn hierarchy(&self) -> Option<&dyn ClassHierarchy> {
+        self.hierarchy.as_deref()
+    }
+
     pub fn write_byte(&mut self, byte: w1) {
         self.buffer.push(byte)
     }
@@ -16,12 +52,22 @@ impl ByteWriter {
     pub fn write<W: Writeable>(&mut self, data: W) {
         data.write(self)
     }
+
+    /// Writes something that may not fit the class file format,
+    /// e.g. a class with more constants than its pool can hold
+    pub fn try_write<W: TryWriteable>(&mut self, data: W) -> Result<(), ClassFileError> {
+        data.try_write(self)
+    }
 }
 
 pub trait Writeable {
     fn write(self, writer: &mut ByteWriter);
 }
 
+pub trait TryWriteable {
+    fn try_write(self, writer: &mut ByteWriter) -> Result<(), ClassFileError>;
+}
+
 impl From<ByteWriter> for Vec<w1> {
     fn from(val: ByteWriter) -> Self {
         val.buffer
@@ -43,3 +89,6 @@ macro_rules! impl_writeable_for {
 impl_writeable_for!(w1);
 impl_writeable_for!(w2);
 impl_writeable_for!(w4);
+impl_writeable_for!(i8);
+impl_writeable_for!(i16);
+impl_writeable_for!(i32);
@@ -1,9 +1,18 @@
+use crate::bytecode::writer::ByteWriter;
+use crate::error::ClassFileError;
+use crate::model::attrs::constant_value::ConstantValue;
+use crate::model::constant::{
+    BootstrapMethod, CallSite, Dynamic, Loadable, MethodHandle, ReferenceKind,
+};
+use crate::model::descriptor::{resolve_descriptor, FieldType, MethodDescriptor};
+use crate::mutf8::JavaString;
 use crate::{w1, w2, w4, w8};
+use std::collections::HashMap;
 use std::fmt::{Display, Formatter};
 use std::ops::Index;
 
 // TODO: replace discriminators with fields
-#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Clone, Copy)]
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Hash, Clone, Copy)]
 pub enum CpTag {
     /// string (usually referenced by other constants)
     Utf8 = 1,
@@ -44,7 +53,7 @@ pub enum CpTag {
 #[derive(Debug, Clone)]
 pub enum CpInfo {
     /// string (usually referenced by other constants)
-    Utf8 { string: String }, // FIXME: find a different type for bytes
+    Utf8 { string: JavaString },
     /// 4-byte int
     Integer { int: w4 },
     /// 4-byte float
@@ -100,7 +109,7 @@ pub enum CpInfo {
     Package { name_index: w2 },
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Constant(pub CpTag, pub CpInfo);
 
 impl Display for CpTag {
@@ -113,7 +122,7 @@ impl CpInfo {
     fn content_to_string(&self) -> String {
         use crate::constant_pool::CpInfo::*;
         match self {
-            Utf8 { string } => string.to_owned(),
+            Utf8 { string } => string.to_string_lossy(),
             Integer { int: bytes } => format!("{}", bytes),
             Float { float } => format!("{}f", float),
             Long { long } => format!("{}L", long),
@@ -162,16 +171,38 @@ impl Display for Constant {
     }
 }
 
-#[derive(Debug)]
+/// An entry of the `BootstrapMethods` attribute, which refers to the pool by index
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct BootstrapMethodRef {
+    pub(crate) method_ref: w2,
+    pub(crate) arguments: Vec<w2>,
+}
+
+/// Most entries the pool can have, counting the unusable slot 0 and the second slots
+/// of longs and doubles, since `constant_pool_count` is a `w2`
+const MAX_CONSTANT_POOL_COUNT: usize = w2::MAX as usize;
+
+/// Deepest chain of `Dynamic` constants passed as bootstrap arguments to each other
+/// that is resolved; the JVMS forbids cycles, so well-formed classes stay far below this
+const MAX_DYNAMIC_NESTING: usize = 32;
+
+#[derive(Debug, Clone)]
 pub struct ConstantPool {
     pool: Vec<Option<Constant>>,
+    /// Index of the first occurrence of each constant, keyed by its encoded form
+    interned: HashMap<(CpTag, Vec<w1>), w2>,
+    /// The class' `BootstrapMethods` table, which `Dynamic` and `InvokeDynamic` constants
+    /// point into. It's kept here since it's shared and interned just like constants are.
+    bootstrap_methods: Vec<BootstrapMethodRef>,
+    interned_bootstrap_methods: HashMap<BootstrapMethodRef, w2>,
 }
 
 impl Index<w2> for ConstantPool {
     type Output = Option<Constant>;
 
+    /// Out-of-range indices are treated like empty slots
     fn index(&self, index: w2) -> &Self::Output {
-        &self.pool[index as usize]
+        self.pool.get(index as usize).unwrap_or(&Option::None)
     }
 }
 
@@ -179,6 +210,9 @@ impl ConstantPool {
     pub(crate) fn new() -> ConstantPool {
         ConstantPool {
             pool: vec![Option::None],
+            interned: HashMap::new(),
+            bootstrap_methods: vec![],
+            interned_bootstrap_methods: HashMap::new(),
         }
     }
 
@@ -187,35 +221,75 @@ impl ConstantPool {
         self.pool.len() as w2
     }
 
-    pub fn get_class_name(&self, class_index: w2) -> Result<String, String> {
-        let index = match self[class_index]
-            .as_ref()
-            .ok_or(format!("Invalid index: {}", class_index))?
-        {
+    /// Looks up the constant at [index], failing on out-of-range or unusable slots
+    pub fn get(&self, index: w2) -> Result<&Constant, ClassFileError> {
+        self.pool
+            .get(index as usize)
+            .and_then(Option::as_ref)
+            .ok_or(ClassFileError::InvalidConstantIndex { index })
+    }
+
+    pub fn get_class_name(&self, class_index: w2) -> Result<String, ClassFileError> {
+        let index = match self.get(class_index)? {
             Constant(CpTag::Class, CpInfo::Class { name_index }) => *name_index,
             Constant(tag, _) => {
-                return Err(format!(
-                    "Wrong constant type at index {idx}: expected `Class`, found `{found}`",
-                    idx = class_index,
-                    found = tag
-                ))
+                return Err(ClassFileError::WrongConstantType {
+                    index: class_index,
+                    expected: &[CpTag::Class],
+                    found: *tag,
+                })
             }
         };
         self.get_utf8(index)
     }
 
-    pub fn get_name_and_type(&self, name_and_type_index: w2) -> Result<(String, String), String> {
-        let (name_index, descriptor_index) = match self[name_and_type_index]
-            .as_ref()
-            .ok_or(format!("Invalid index: {}", name_and_type_index))?
-        {
-            Constant(CpTag::NameAndType, CpInfo::NameAndType { name_index, descriptor_index }) => (*name_index, *descriptor_index),
+    pub fn get_module_name(&self, module_index: w2) -> Result<String, ClassFileError> {
+        let index = match self.get(module_index)? {
+            Constant(CpTag::Module, CpInfo::Module { name_index }) => *name_index,
             Constant(tag, _) => {
-                return Err(format!(
-                    "Wrong constant type at index {idx}: expected `NameAndType`, found `{found}`",
-                    idx = name_and_type_index,
-                    found = tag
-                ))
+                return Err(ClassFileError::WrongConstantType {
+                    index: module_index,
+                    expected: &[CpTag::Module],
+                    found: *tag,
+                })
+            }
+        };
+        self.get_utf8(index)
+    }
+
+    /// The name of a package, in internal form, e.g. `java/lang`
+    pub fn get_package_name(&self, package_index: w2) -> Result<String, ClassFileError> {
+        let index = match self.get(package_index)? {
+            Constant(CpTag::Package, CpInfo::Package { name_index }) => *name_index,
+            Constant(tag, _) => {
+                return Err(ClassFileError::WrongConstantType {
+                    index: package_index,
+                    expected: &[CpTag::Package],
+                    found: *tag,
+                })
+            }
+        };
+        self.get_utf8(index)
+    }
+
+    pub fn get_name_and_type(
+        &self,
+        name_and_type_index: w2,
+    ) -> Result<(String, String), ClassFileError> {
+        let (name_index, descriptor_index) = match self.get(name_and_type_index)? {
+            Constant(
+                CpTag::NameAndType,
+                CpInfo::NameAndType {
+                    name_index,
+                    descriptor_index,
+                },
+            ) => (*name_index, *descriptor_index),
+            Constant(tag, _) => {
+                return Err(ClassFileError::WrongConstantType {
+                    index: name_and_type_index,
+                    expected: &[CpTag::NameAndType],
+                    found: *tag,
+                })
             }
         };
         let name = self.get_utf8(name_index)?;
@@ -223,48 +297,522 @@ impl ConstantPool {
         Ok((name, descriptor_index))
     }
 
-    pub fn get_utf8(&self, index: w2) -> Result<String, String> {
-        match self[index]
-            .as_ref()
-            .ok_or(format!("Invalid index: {}", index))?
-        {
-            Constant(CpTag::Utf8, CpInfo::Utf8 { string }) => Ok(string.to_owned()),
-            Constant(tag, _) => Err(format!(
-                "Wrong constant type at index {idx}: expected `Utf8`, found `{found}`",
-                idx = index,
-                found = tag
-            )),
+    /// Looks up a `Fieldref`, `Methodref` or `InterfaceMethodref` (as given by [tag])
+    /// and returns its class name, member name and descriptor
+    pub fn get_member_ref(
+        &self,
+        index: w2,
+        tag: CpTag,
+    ) -> Result<(String, String, String), ClassFileError> {
+        let (class_index, name_and_type_index) = match self.get(index)? {
+            Constant(
+                found,
+                CpInfo::Fieldref {
+                    class_index,
+                    name_and_type_index,
+                }
+                | CpInfo::Methodref {
+                    class_index,
+                    name_and_type_index,
+                }
+                | CpInfo::InterfaceMethodref {
+                    class_index,
+                    name_and_type_index,
+                },
+            ) if *found == tag => (*class_index, *name_and_type_index),
+            Constant(found, _) => {
+                return Err(ClassFileError::WrongConstantType {
+                    index,
+                    expected: match tag {
+                        CpTag::Fieldref => &[CpTag::Fieldref],
+                        CpTag::Methodref => &[CpTag::Methodref],
+                        _ => &[CpTag::InterfaceMethodref],
+                    },
+                    found: *found,
+                })
+            }
+        };
+        let class = self.get_class_name(class_index)?;
+        let (name, descriptor) = self.get_name_and_type(name_and_type_index)?;
+        Ok((class, name, descriptor))
+    }
+
+    /// Looks up a `Utf8` constant that may hold unpaired surrogates
+    pub fn get_java_string(&self, index: w2) -> Result<JavaString, ClassFileError> {
+        match self.get(index)? {
+            Constant(CpTag::Utf8, CpInfo::Utf8 { string }) => Ok(string.clone()),
+            Constant(tag, _) => Err(ClassFileError::WrongConstantType {
+                index,
+                expected: &[CpTag::Utf8],
+                found: *tag,
+            }),
         }
     }
 
-    pub fn get_constant_as_string(&self, index: w2) -> Result<String, String> {
-        match self[index]
-            .as_ref()
-            .ok_or(format!("Invalid index: {}", index))?
-        {
+    /// Looks up a `Utf8` constant, failing if it isn't valid Unicode
+    pub fn get_utf8(&self, index: w2) -> Result<String, ClassFileError> {
+        match self.get(index)? {
+            Constant(CpTag::Utf8, CpInfo::Utf8 { string }) => string
+                .as_str()
+                .map(str::to_string)
+                .ok_or(ClassFileError::NotUnicode { index }),
+            Constant(tag, _) => Err(ClassFileError::WrongConstantType {
+                index,
+                expected: &[CpTag::Utf8],
+                found: *tag,
+            }),
+        }
+    }
+
+    /// Looks up a `Utf8` constant holding a field descriptor
+    pub fn get_field_type(&self, index: w2) -> Result<FieldType, ClassFileError> {
+        resolve_descriptor(self.get_utf8(index)?, FieldType::parse)
+    }
+
+    /// Looks up a `Utf8` constant holding a method descriptor
+    pub fn get_method_descriptor(&self, index: w2) -> Result<MethodDescriptor, ClassFileError> {
+        resolve_descriptor(self.get_utf8(index)?, MethodDescriptor::parse)
+    }
+
+    /// Looks up the constant a `ConstantValue` attribute refers to
+    pub fn get_constant_value(&self, index: w2) -> Result<ConstantValue, ClassFileError> {
+        Ok(match self.get(index)? {
+            Constant(CpTag::Integer, CpInfo::Integer { int }) => {
+                ConstantValue::Integer(*int as i32)
+            }
+            Constant(CpTag::Long, CpInfo::Long { long }) => ConstantValue::Long(*long as i64),
+            Constant(CpTag::Float, CpInfo::Float { float }) => ConstantValue::Float(*float),
+            Constant(CpTag::Double, CpInfo::Double { double }) => ConstantValue::Double(*double),
+            Constant(CpTag::String, CpInfo::String { string_index }) => {
+                ConstantValue::String(self.get_java_string(*string_index)?)
+            }
+            Constant(tag, _) => {
+                return Err(ClassFileError::WrongConstantType {
+                    index,
+                    expected: &[
+                        CpTag::Integer,
+                        CpTag::Float,
+                        CpTag::Long,
+                        CpTag::Double,
+                        CpTag::String,
+                    ],
+                    found: *tag,
+                })
+            }
+        })
+    }
+
+    /// Looks up a constant that `ldc`, `ldc_w` or `ldc2_w` can load
+    pub fn get_loadable(&self, index: w2) -> Result<Loadable, ClassFileError> {
+        self.get_loadable_nested(index, 0)
+    }
+
+    /// [Self::get_loadable] for a bootstrap argument [depth] `Dynamic` constants deep
+    fn get_loadable_nested(&self, index: w2, depth: usize) -> Result<Loadable, ClassFileError> {
+        Ok(match self.get(index)? {
+            Constant(CpTag::Integer, CpInfo::Integer { int }) => Loadable::Integer(*int as i32),
+            Constant(CpTag::Float, CpInfo::Float { float }) => Loadable::Float(*float),
+            Constant(CpTag::Long, CpInfo::Long { long }) => Loadable::Long(*long as i64),
+            Constant(CpTag::Double, CpInfo::Double { double }) => Loadable::Double(*double),
             Constant(CpTag::String, CpInfo::String { string_index }) => {
-                Ok(format!("\"{}\"", self.get_utf8(*string_index)?))
+                Loadable::String(self.get_java_string(*string_index)?)
+            }
+            Constant(CpTag::Class, CpInfo::Class { name_index }) => {
+                Loadable::Class(self.get_utf8(*name_index)?)
+            }
+            Constant(CpTag::MethodType, CpInfo::MethodType { descriptor_index }) => {
+                Loadable::MethodType(self.get_method_descriptor(*descriptor_index)?)
+            }
+            Constant(CpTag::MethodHandle, _) => {
+                Loadable::MethodHandle(self.get_method_handle(index)?)
+            }
+            Constant(
+                CpTag::Dynamic,
+                CpInfo::Dynamic {
+                    bootstrap_method_attr_index,
+                    name_and_type_index,
+                },
+            ) => {
+                let (name, descriptor) = self.get_name_and_type(*name_and_type_index)?;
+                Loadable::Dynamic(Dynamic {
+                    name,
+                    descriptor: resolve_descriptor(descriptor, FieldType::parse)?,
+                    bootstrap: self
+                        .get_bootstrap_method_nested(*bootstrap_method_attr_index, depth + 1)?,
+                })
             }
-            Constant(CpTag::Double | CpTag::Integer | CpTag::Float | CpTag::Long, it) => {
-                Ok(it.content_to_string())
+            Constant(tag, _) => {
+                return Err(ClassFileError::WrongConstantType {
+                    index,
+                    expected: &[
+                        CpTag::Integer,
+                        CpTag::Float,
+                        CpTag::Long,
+                        CpTag::Double,
+                        CpTag::String,
+                        CpTag::Class,
+                        CpTag::MethodType,
+                        CpTag::MethodHandle,
+                        CpTag::Dynamic,
+                    ],
+                    found: *tag,
+                })
+            }
+        })
+    }
+
+    /// Looks up an `InvokeDynamic` constant
+    pub fn get_call_site(&self, index: w2) -> Result<CallSite, ClassFileError> {
+        let (bootstrap_method_attr_index, name_and_type_index) = match self.get(index)? {
+            Constant(
+                CpTag::InvokeDynamic,
+                CpInfo::InvokeDynamic {
+                    bootstrap_method_attr_index,
+                    name_and_type_index,
+                },
+            ) => (*bootstrap_method_attr_index, *name_and_type_index),
+            Constant(tag, _) => {
+                return Err(ClassFileError::WrongConstantType {
+                    index,
+                    expected: &[CpTag::InvokeDynamic],
+                    found: *tag,
+                })
             }
-            Constant(tag, _) => Err(format!(
-                "Wrong constant type at index {idx}: expected {expected}, found `{found}`",
-                idx = index,
-                expected = stringify!([Integer, Float, Long, Double, String]),
-                found = tag
-            )),
+        };
+        let (name, descriptor) = self.get_name_and_type(name_and_type_index)?;
+        Ok(CallSite {
+            name,
+            descriptor: resolve_descriptor(descriptor, MethodDescriptor::parse)?,
+            bootstrap: self.get_bootstrap_method(bootstrap_method_attr_index)?,
+        })
+    }
+
+    /// Looks up an entry of the `BootstrapMethods` table
+    pub fn get_bootstrap_method(&self, index: w2) -> Result<BootstrapMethod, ClassFileError> {
+        self.get_bootstrap_method_nested(index, 0)
+    }
+
+    fn get_bootstrap_method_nested(
+        &self,
+        index: w2,
+        depth: usize,
+    ) -> Result<BootstrapMethod, ClassFileError> {
+        if depth > MAX_DYNAMIC_NESTING {
+            return Err(ClassFileError::NestingTooDeep);
+        }
+        let BootstrapMethodRef {
+            method_ref,
+            arguments,
+        } = self
+            .bootstrap_methods
+            .get(index as usize)
+            .ok_or(ClassFileError::InvalidBootstrapMethodIndex { index })?;
+        Ok(BootstrapMethod {
+            handle: self.get_method_handle(*method_ref)?,
+            arguments: arguments
+                .iter()
+                .map(|&argument| self.get_loadable_nested(argument, depth))
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    /// The `BootstrapMethods` table, in the order it's written
+    pub(crate) fn bootstrap_methods(&self) -> &[BootstrapMethodRef] {
+        &self.bootstrap_methods
+    }
+
+    /// Sets the `BootstrapMethods` table read from a class file, so that constants can refer to it
+    pub(crate) fn set_bootstrap_methods(&mut self, bootstrap_methods: Vec<BootstrapMethodRef>) {
+        self.interned_bootstrap_methods.clear();
+        for (index, bootstrap_method) in bootstrap_methods.iter().enumerate() {
+            self.interned_bootstrap_methods
+                .entry(bootstrap_method.clone())
+                .or_insert(index as w2);
         }
+        self.bootstrap_methods = bootstrap_methods;
+    }
+
+    /// Looks up a `MethodHandle`, checking that its reference kind matches the member it refers to
+    pub fn get_method_handle(&self, index: w2) -> Result<MethodHandle, ClassFileError> {
+        let (reference_kind, reference_index) = match self.get(index)? {
+            Constant(
+                CpTag::MethodHandle,
+                CpInfo::MethodHandle {
+                    reference_kind,
+                    reference_index,
+                },
+            ) => (*reference_kind, *reference_index),
+            Constant(tag, _) => {
+                return Err(ClassFileError::WrongConstantType {
+                    index,
+                    expected: &[CpTag::MethodHandle],
+                    found: *tag,
+                })
+            }
+        };
+        let kind = ReferenceKind::try_from(reference_kind)
+            .map_err(|kind| ClassFileError::InvalidReferenceKind { index, kind })?;
+        let tag = match self.get(reference_index)? {
+            Constant(CpTag::InterfaceMethodref, _)
+                if matches!(
+                    kind,
+                    ReferenceKind::REF_invokeStatic | ReferenceKind::REF_invokeSpecial
+                ) =>
+            {
+                CpTag::InterfaceMethodref
+            }
+            _ => kind.tag(false),
+        };
+        let (class, name, descriptor) = self.get_member_ref(reference_index, tag)?;
+        Ok(MethodHandle {
+            kind,
+            class,
+            name,
+            descriptor,
+            interface: tag == CpTag::InterfaceMethodref,
+        })
     }
 
     pub(crate) fn push_empty(&mut self) {
         self.pool.push(Option::None)
     }
 
-    /// Appends a constant to the pool and returns its index
-    pub(crate) fn push(&mut self, constant: Constant) -> w2 {
+    /// Appends a constant to the pool as-is, even if an equal constant is already there.
+    /// Used when reading, where indices have to match the class file.
+    pub(crate) fn append(&mut self, constant: Constant) -> Result<w2, ClassFileError> {
+        let index = self.next_index(&constant)?; // Index of the currently-added constant
+        self.interned.entry(Self::key(&constant)?).or_insert(index);
         self.pool.push(Option::Some(constant));
-        self.len() - 1 // Last (= currently-added) index is len - 1
+        Ok(index)
+    }
+
+    /// Adds a constant to the pool unless a structurally equal one is already there,
+    /// and returns its index
+    pub(crate) fn push(&mut self, constant: Constant) -> Result<w2, ClassFileError> {
+        let key = Self::key(&constant)?;
+        if let Some(&index) = self.interned.get(&key) {
+            return Ok(index);
+        }
+        let wide = matches!(constant.0, CpTag::Long | CpTag::Double);
+        let index = self.next_index(&constant)?;
+        self.interned.insert(key, index);
+        self.pool.push(Option::Some(constant));
+        // Long and Double "swallow" another index
+        if wide {
+            self.push_empty();
+        }
+        Ok(index)
+    }
+
+    /// The index [constant] gets if it's added next, unless the pool has no room left for it
+    fn next_index(&self, constant: &Constant) -> Result<w2, ClassFileError> {
+        let slots = match constant.0 {
+            CpTag::Long | CpTag::Double => 2,
+            _ => 1,
+        };
+        if self.pool.len() + slots > MAX_CONSTANT_POOL_COUNT {
+            return Err(ClassFileError::ConstantPoolFull);
+        }
+        Ok(self.pool.len() as w2)
+    }
+
+    /// Constants are compared by their encoded bytes, so e.g. floats compare by bit pattern.
+    /// Fails for constants that can't be encoded, see [ClassFileError::StringTooLong].
+    fn key(Constant(tag, info): &Constant) -> Result<(CpTag, Vec<w1>), ClassFileError> {
+        let mut writer = ByteWriter::new();
+        writer.try_write(info.clone())?;
+        Ok((*tag, writer.into()))
+    }
+
+    pub(crate) fn push_utf8(
+        &mut self,
+        string: impl Into<JavaString>,
+    ) -> Result<w2, ClassFileError> {
+        self.push(Constant(
+            CpTag::Utf8,
+            CpInfo::Utf8 {
+                string: string.into(),
+            },
+        ))
+    }
+
+    pub(crate) fn push_class(&mut self, class_name: String) -> Result<w2, ClassFileError> {
+        let name_index = self.push_utf8(class_name)?;
+        self.push(Constant(CpTag::Class, CpInfo::Class { name_index }))
+    }
+
+    pub(crate) fn push_module(&mut self, module_name: String) -> Result<w2, ClassFileError> {
+        let name_index = self.push_utf8(module_name)?;
+        self.push(Constant(CpTag::Module, CpInfo::Module { name_index }))
+    }
+
+    pub(crate) fn push_package(&mut self, package_name: String) -> Result<w2, ClassFileError> {
+        let name_index = self.push_utf8(package_name)?;
+        self.push(Constant(CpTag::Package, CpInfo::Package { name_index }))
+    }
+
+    pub(crate) fn push_string(
+        &mut self,
+        string: impl Into<JavaString>,
+    ) -> Result<w2, ClassFileError> {
+        let string_index = self.push_utf8(string)?;
+        self.push(Constant(CpTag::String, CpInfo::String { string_index }))
+    }
+
+    pub(crate) fn push_name_and_type(
+        &mut self,
+        name: String,
+        descriptor: String,
+    ) -> Result<w2, ClassFileError> {
+        let name_index = self.push_utf8(name)?;
+        let descriptor_index = self.push_utf8(descriptor)?;
+        self.push(Constant(
+            CpTag::NameAndType,
+            CpInfo::NameAndType {
+                name_index,
+                descriptor_index,
+            },
+        ))
+    }
+
+    /// Adds a `Fieldref`, `Methodref` or `InterfaceMethodref` (as given by [tag])
+    pub(crate) fn push_member_ref(
+        &mut self,
+        tag: CpTag,
+        class_name: String,
+        name: String,
+        descriptor: String,
+    ) -> Result<w2, ClassFileError> {
+        let class_index = self.push_class(class_name)?;
+        let name_and_type_index = self.push_name_and_type(name, descriptor)?;
+        let info = match tag {
+            CpTag::Fieldref => CpInfo::Fieldref {
+                class_index,
+                name_and_type_index,
+            },
+            CpTag::Methodref => CpInfo::Methodref {
+                class_index,
+                name_and_type_index,
+            },
+            _ => CpInfo::InterfaceMethodref {
+                class_index,
+                name_and_type_index,
+            },
+        };
+        self.push(Constant(tag, info))
+    }
+
+    pub(crate) fn push_loadable(&mut self, loadable: Loadable) -> Result<w2, ClassFileError> {
+        match loadable {
+            Loadable::Integer(int) => {
+                self.push(Constant(CpTag::Integer, CpInfo::Integer { int: int as w4 }))
+            }
+            Loadable::Float(float) => self.push(Constant(CpTag::Float, CpInfo::Float { float })),
+            Loadable::Long(long) => {
+                self.push(Constant(CpTag::Long, CpInfo::Long { long: long as w8 }))
+            }
+            Loadable::Double(double) => {
+                self.push(Constant(CpTag::Double, CpInfo::Double { double }))
+            }
+            Loadable::String(string) => self.push_string(string),
+            Loadable::Class(name) => self.push_class(name),
+            Loadable::MethodType(descriptor) => {
+                let descriptor_index = self.push_utf8(descriptor.encode())?;
+                self.push(Constant(
+                    CpTag::MethodType,
+                    CpInfo::MethodType { descriptor_index },
+                ))
+            }
+            Loadable::MethodHandle(handle) => self.push_method_handle(handle),
+            Loadable::Dynamic(Dynamic {
+                name,
+                descriptor,
+                bootstrap,
+            }) => {
+                let bootstrap_method_attr_index = self.push_bootstrap_method(bootstrap)?;
+                let name_and_type_index = self.push_name_and_type(name, descriptor.encode())?;
+                self.push(Constant(
+                    CpTag::Dynamic,
+                    CpInfo::Dynamic {
+                        bootstrap_method_attr_index,
+                        name_and_type_index,
+                    },
+                ))
+            }
+        }
+    }
+
+    pub(crate) fn push_call_site(&mut self, call_site: CallSite) -> Result<w2, ClassFileError> {
+        let bootstrap_method_attr_index = self.push_bootstrap_method(call_site.bootstrap)?;
+        let name_and_type_index =
+            self.push_name_and_type(call_site.name, call_site.descriptor.encode())?;
+        self.push(Constant(
+            CpTag::InvokeDynamic,
+            CpInfo::InvokeDynamic {
+                bootstrap_method_attr_index,
+                name_and_type_index,
+            },
+        ))
+    }
+
+    /// Adds an entry to the `BootstrapMethods` table unless an equal one is already there,
+    /// and returns its index
+    pub(crate) fn push_bootstrap_method(
+        &mut self,
+        bootstrap_method: BootstrapMethod,
+    ) -> Result<w2, ClassFileError> {
+        let method_ref = self.push_method_handle(bootstrap_method.handle)?;
+        let arguments = bootstrap_method
+            .arguments
+            .into_iter()
+            .map(|argument| self.push_loadable(argument))
+            .collect::<Result<_, _>>()?;
+        let bootstrap_method = BootstrapMethodRef {
+            method_ref,
+            arguments,
+        };
+        if let Some(&index) = self.interned_bootstrap_methods.get(&bootstrap_method) {
+            return Ok(index);
+        }
+        let index = w2::try_from(self.bootstrap_methods.len())
+            .map_err(|_| ClassFileError::ConstantPoolFull)?;
+        self.interned_bootstrap_methods
+            .insert(bootstrap_method.clone(), index);
+        self.bootstrap_methods.push(bootstrap_method);
+        Ok(index)
+    }
+
+    pub(crate) fn push_method_handle(
+        &mut self,
+        handle: MethodHandle,
+    ) -> Result<w2, ClassFileError> {
+        let reference_index = self.push_member_ref(
+            handle.kind.tag(handle.interface),
+            handle.class,
+            handle.name,
+            handle.descriptor,
+        )?;
+        self.push(Constant(
+            CpTag::MethodHandle,
+            CpInfo::MethodHandle {
+                reference_kind: handle.kind as w1,
+                reference_index,
+            },
+        ))
+    }
+}
+
+impl ReferenceKind {
+    /// The kind of member ref a handle of this kind refers to
+    fn tag(self, interface: bool) -> CpTag {
+        match self {
+            _ if self.is_field() => CpTag::Fieldref,
+            ReferenceKind::REF_invokeInterface => CpTag::InterfaceMethodref,
+            ReferenceKind::REF_invokeStatic | ReferenceKind::REF_invokeSpecial if interface => {
+                CpTag::InterfaceMethodref
+            }
+            _ => CpTag::Methodref,
+        }
     }
 }
 
@@ -298,7 +846,7 @@ pub(crate) mod double_utils {
     #[inline]
     pub(crate) fn long2bytes(long: w8) -> (w4, w4) {
         let high_bytes = (long >> 32) as w4;
-        let low_bytes = (long & 0xFFFF) as w4;
+        let low_bytes = (long & 0xFFFF_FFFF) as w4;
         (high_bytes, low_bytes)
     }
 
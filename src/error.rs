@@ -0,0 +1,211 @@
+use crate::analysis::AnalysisError;
+use crate::constant_pool::CpTag;
+use crate::{w1, w2, w4};
+use std::fmt::{Display, Formatter};
+
+/// Everything that can go wrong while reading a class file
+#[derive(Debug, Clone, PartialEq)]
+pub enum ClassFileError {
+    /// The input ended before the item being read was complete
+    UnexpectedEof { requested: usize, available: usize },
+    /// The file doesn't start with `0xCAFEBABE`
+    BadMagic { found: w4 },
+    /// A constant pool entry has a tag not defined by the JVMS
+    UnknownConstantTag { tag: w1 },
+    /// A constant pool index points outside the pool or at an unusable slot
+    InvalidConstantIndex { index: w2 },
+    /// A constant pool index points at a constant of the wrong kind
+    WrongConstantType {
+        index: w2,
+        expected: &'static [CpTag],
+        found: CpTag,
+    },
+    /// A `Utf8` constant isn't valid modified UTF-8; `position` is relative to the string's start
+    InvalidModifiedUtf8 { position: usize },
+    /// A `Utf8` constant holds unpaired surrogates where a [String] is expected (e.g. a name)
+    NotUnicode { index: w2 },
+    /// A `MethodHandle` constant has a reference kind not defined by the JVMS
+    InvalidReferenceKind { index: w2, kind: w1 },
+    /// A `Dynamic` or `InvokeDynamic` constant points outside the `BootstrapMethods` table
+    InvalidBootstrapMethodIndex { index: w2 },
+    /// A byte in the code array isn't a known opcode
+    InvalidOpcode { pc: usize, byte: w1 },
+    /// A branch, exception range or debug table points into the middle of an instruction
+    /// or outside the code array
+    InvalidCodeOffset { pc: i64 },
+    /// A `newarray` whose element type isn't one of the JVMS `T_*` codes
+    InvalidArrayType { atype: w1 },
+    /// A `tableswitch` whose `high` bound is below its `low` bound,
+    /// or, when writing, has so many targets that `high` doesn't fit in an int
+    InvalidSwitchBounds { low: i32, high: i64 },
+    /// A `StackMapTable` frame has a frame type reserved by the JVMS
+    InvalidFrameType { frame_type: w1 },
+    /// A `chop_frame` or `append_frame` being written has a number of locals other than 1 to 3
+    InvalidFrameLocals { count: usize },
+    /// A `StackMapTable` frame being written isn't after the frame before it
+    FramesOutOfOrder { pc: usize },
+    /// A `StackMapTable` frame has a `verification_type_info` with an unknown tag
+    InvalidVerificationType { tag: w1 },
+    /// An annotation's `element_value` has an unknown tag
+    InvalidElementValueTag { tag: w1 },
+    /// A type annotation has a `target_type` the JVMS doesn't define
+    InvalidTargetType { target_type: w1 },
+    /// A type annotation's `type_path` has a step of unknown kind
+    InvalidTypePathKind { type_path_kind: w1 },
+    /// A field or method descriptor is malformed; `position` is relative to its start
+    InvalidDescriptor { descriptor: String, position: usize },
+    /// A static field's `ConstantValue` is of a type the field can't hold
+    ConstantValueMismatch { descriptor: String, found: CpTag },
+    /// A `Utf8` constant being written encodes to more bytes than its `u2` length can count
+    StringTooLong { length: usize },
+    /// A class being written needs more constants (or bootstrap methods) than fit the pool
+    ConstantPoolFull,
+    /// A branch, exception range or attribute of a `Code` being written points at a label
+    /// that isn't in its code
+    UnplacedLabel { label: w4 },
+    /// A `Code` being written places the same label more than once
+    DuplicateLabel { label: w4 },
+    /// An exception range or attribute of a `Code` being written points at a pc
+    /// too large for its `u2` field
+    PcOutOfRange { pc: usize },
+    /// The code of a method being written couldn't be analyzed
+    /// to recompute its `max_stack`, `max_locals` or `StackMapTable`
+    AnalysisFailed { error: Box<AnalysisError> },
+    /// Items (usually `Code` attributes, or `Dynamic` constants as bootstrap arguments)
+    /// are nested deeper than any real class would
+    NestingTooDeep,
+}
+
+impl Display for ClassFileError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        use ClassFileError::*;
+        match self {
+            UnexpectedEof {
+                requested,
+                available,
+            } => write!(
+                f,
+                "Can't take {} bytes from buffer containing {} bytes!",
+                requested, available
+            ),
+            BadMagic { found } => write!(f, "Wrong 'magic' field: `{:#X}`!", found),
+            UnknownConstantTag { tag } => write!(f, "Unexpected Constant type ID `{}`!", tag),
+            InvalidConstantIndex { index } => write!(f, "Invalid index: {}", index),
+            WrongConstantType {
+                index,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Wrong constant type at index {}: expected {:?}, found `{}`",
+                index, expected, found
+            ),
+            InvalidModifiedUtf8 { position } => {
+                write!(f, "Malformed modified UTF-8 at byte {}", position)
+            }
+            NotUnicode { index } => {
+                write!(f, "Utf8 constant at index {} isn't valid Unicode", index)
+            }
+            InvalidReferenceKind { index, kind } => write!(
+                f,
+                "Invalid reference kind {} in MethodHandle at index {}",
+                kind, index
+            ),
+            InvalidBootstrapMethodIndex { index } => {
+                write!(f, "Invalid bootstrap method index: {}", index)
+            }
+            InvalidOpcode { pc, byte } => write!(f, "Invalid opcode {} at pc {}", byte, pc),
+            InvalidCodeOffset { pc } => {
+                write!(f, "Code offset {} isn't the start of an instruction", pc)
+            }
+            InvalidArrayType { atype } => write!(f, "Invalid newarray type {}", atype),
+            InvalidSwitchBounds { low, high } => {
+                write!(
+                    f,
+                    "tableswitch bounds {}..={} aren't a range of ints",
+                    low, high
+                )
+            }
+            InvalidFrameType { frame_type } => {
+                write!(f, "Invalid stack map frame type {}", frame_type)
+            }
+            InvalidFrameLocals { count } => {
+                write!(f, "A chop or append frame can't have {} locals", count)
+            }
+            FramesOutOfOrder { pc } => {
+                write!(f, "The stack map frame at pc {} is out of order", pc)
+            }
+            InvalidVerificationType { tag } => write!(f, "Invalid verification type {}", tag),
+            InvalidElementValueTag { tag } => write!(f, "Invalid element value tag {}", tag),
+            InvalidTargetType { target_type } => {
+                write!(f, "Invalid type annotation target type {:#04x}", target_type)
+            }
+            InvalidTypePathKind { type_path_kind } => {
+                write!(f, "Invalid type path kind {}", type_path_kind)
+            }
+            InvalidDescriptor {
+                descriptor,
+                position,
+            } => write!(f, "Invalid descriptor `{}` at {}", descriptor, position),
+            ConstantValueMismatch { descriptor, found } => write!(
+                f,
+                "A field of type `{}` can't have a `{}` constant value",
+                descriptor, found
+            ),
+            StringTooLong { length } => {
+                write!(f, "A Utf8 constant of {} bytes doesn't fit in a u2", length)
+            }
+            ConstantPoolFull => write!(f, "The constant pool is full"),
+            UnplacedLabel { label } => write!(f, "Label {} isn't placed in the code", label),
+            DuplicateLabel { label } => {
+                write!(f, "Label {} is placed more than once in the code", label)
+            }
+            PcOutOfRange { pc } => write!(f, "pc {} doesn't fit in a u2", pc),
+            AnalysisFailed { error } => write!(f, "Can't analyze {}", error),
+            NestingTooDeep => write!(f, "Items are nested too deeply"),
+        }
+    }
+}
+
+impl std::error::Error for ClassFileError {}
+
+/// Where in the input an error occurred
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Location {
+    /// Byte offset from the start of the input
+    pub offset: usize,
+    /// Breadcrumbs leading to the item, e.g. `methods[3].attributes[Code].code[pc=17]`
+    pub path: Vec<String>,
+}
+
+impl Display for Location {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "<root>")?;
+        } else {
+            write!(f, "{}", self.path.join("."))?;
+        }
+        write!(f, " (offset {:#X})", self.offset)
+    }
+}
+
+/// A [ClassFileError] together with the [Location] it was found at
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub error: ClassFileError,
+    pub location: Location,
+}
+
+impl ParseError {
+    pub fn new(error: ClassFileError, location: Location) -> ParseError {
+        ParseError { error, location }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.location, self.error)
+    }
+}
+
+impl std::error::Error for ParseError {}
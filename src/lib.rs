@@ -1,6 +1,9 @@
+pub mod analysis;
 pub mod bytecode;
 pub mod constant_pool;
+pub mod error;
 pub mod model;
+pub mod mutf8;
 pub mod typedefs;
 
 use crate::model::class::Class;
@@ -0,0 +1,286 @@
+use crate::bytecode::reader::{ByteReader, Take};
+use crate::bytecode::writer::ByteWriter;
+use crate::constant_pool::{Constant, ConstantPool, CpInfo, CpTag};
+use crate::error::{ClassFileError, ParseError};
+use crate::mutf8::JavaString;
+use crate::{w1, w2, w4, w8};
+use serde::{Deserialize, Serialize};
+
+// https://docs.oracle.com/javase/specs/jvms/se12/html/jvms-4.html#jvms-4.7.16
+pub type Annotations = Vec<Annotation>;
+
+/// The annotations of each parameter of a method, in order.
+/// There may be fewer entries than the descriptor has parameters, see
+/// https://docs.oracle.com/javase/specs/jvms/se12/html/jvms-4.html#jvms-4.7.18
+pub type ParameterAnnotations = Vec<Annotations>;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Annotation {
+    /// Field descriptor of the annotation interface, e.g. `Ljava/lang/Deprecated;`
+    pub descriptor: String,
+    /// The elements given a value, in the order they're written in
+    pub elements: Vec<ElementValuePair>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ElementValuePair {
+    pub name: String,
+    pub value: ElementValue,
+}
+
+/// The value of an annotation element, or the default value of an annotation interface's method
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ElementValue {
+    Byte(i8),
+    Char(u16),
+    Double(f64),
+    Float(f32),
+    Integer(i32),
+    Long(i64),
+    Short(i16),
+    Boolean(bool),
+    String(JavaString),
+    Enum {
+        /// Field descriptor of the enum class
+        descriptor: String,
+        name: String,
+    },
+    /// A class literal, given by its return descriptor, e.g. `Ljava/lang/Object;`, `I` or `V`
+    Class(String),
+    Annotation(Annotation),
+    Array(Vec<ElementValue>),
+}
+
+pub fn parse_annotations(
+    bytes: &mut ByteReader,
+    constant_pool: &ConstantPool,
+) -> Result<Annotations, ParseError> {
+    let num_annotations: w2 = bytes.take()?;
+    let mut annotations = Vec::with_capacity(num_annotations.into());
+    for i in 0..num_annotations {
+        annotations.push(bytes.context(format!("annotations[{}]", i), |bytes| {
+            parse_annotation(bytes, constant_pool)
+        })?);
+    }
+    Ok(annotations)
+}
+
+pub fn parse_parameter_annotations(
+    bytes: &mut ByteReader,
+    constant_pool: &ConstantPool,
+) -> Result<ParameterAnnotations, ParseError> {
+    let num_parameters: w1 = bytes.take()?;
+    let mut parameter_annotations = Vec::with_capacity(num_parameters.into());
+    for i in 0..num_parameters {
+        parameter_annotations.push(
+            bytes.context(format!("parameter_annotations[{}]", i), |bytes| {
+                parse_annotations(bytes, constant_pool)
+            })?,
+        );
+    }
+    Ok(parameter_annotations)
+}
+
+pub fn parse_annotation(
+    bytes: &mut ByteReader,
+    constant_pool: &ConstantPool,
+) -> Result<Annotation, ParseError> {
+    let descriptor = bytes.take_constant(|index| constant_pool.get_utf8(index))?;
+    let num_element_value_pairs: w2 = bytes.take()?;
+    let mut elements = Vec::with_capacity(num_element_value_pairs.into());
+    for i in 0..num_element_value_pairs {
+        bytes.context(format!("element_value_pairs[{}]", i), |bytes| {
+            let name = bytes.take_constant(|index| constant_pool.get_utf8(index))?;
+            let value = parse_element_value(bytes, constant_pool)?;
+            elements.push(ElementValuePair { name, value });
+            Ok(())
+        })?;
+    }
+    Ok(Annotation {
+        descriptor,
+        elements,
+    })
+}
+
+/// The `int` of the `Integer` constant at [index]
+fn get_int(constant_pool: &ConstantPool, index: w2) -> Result<i32, ClassFileError> {
+    match constant_pool.get(index)? {
+        Constant(CpTag::Integer, CpInfo::Integer { int }) => Ok(*int as i32),
+        Constant(tag, _) => Err(ClassFileError::WrongConstantType {
+            index,
+            expected: &[CpTag::Integer],
+            found: *tag,
+        }),
+    }
+}
+
+pub fn parse_element_value(
+    bytes: &mut ByteReader,
+    constant_pool: &ConstantPool,
+) -> Result<ElementValue, ParseError> {
+    let offset = bytes.offset();
+    let tag: w1 = bytes.take()?;
+    let int = |bytes: &mut ByteReader| bytes.take_constant(|index| get_int(constant_pool, index));
+    Ok(match tag {
+        b'B' => ElementValue::Byte(int(bytes)? as i8),
+        b'C' => ElementValue::Char(int(bytes)? as u16),
+        b'I' => ElementValue::Integer(int(bytes)?),
+        b'S' => ElementValue::Short(int(bytes)? as i16),
+        b'Z' => ElementValue::Boolean(int(bytes)? != 0),
+        b'D' => ElementValue::Double(bytes.take_constant(
+            |index| match constant_pool.get(index)? {
+                Constant(CpTag::Double, CpInfo::Double { double }) => Ok(*double),
+                Constant(tag, _) => Err(ClassFileError::WrongConstantType {
+                    index,
+                    expected: &[CpTag::Double],
+                    found: *tag,
+                }),
+            },
+        )?),
+        b'F' => ElementValue::Float(bytes.take_constant(
+            |index| match constant_pool.get(index)? {
+                Constant(CpTag::Float, CpInfo::Float { float }) => Ok(*float),
+                Constant(tag, _) => Err(ClassFileError::WrongConstantType {
+                    index,
+                    expected: &[CpTag::Float],
+                    found: *tag,
+                }),
+            },
+        )?),
+        b'J' => ElementValue::Long(bytes.take_constant(
+            |index| match constant_pool.get(index)? {
+                Constant(CpTag::Long, CpInfo::Long { long }) => Ok(*long as i64),
+                Constant(tag, _) => Err(ClassFileError::WrongConstantType {
+                    index,
+                    expected: &[CpTag::Long],
+                    found: *tag,
+                }),
+            },
+        )?),
+        b's' => {
+            ElementValue::String(bytes.take_constant(|index| constant_pool.get_java_string(index))?)
+        }
+        b'e' => {
+            let descriptor = bytes.take_constant(|index| constant_pool.get_utf8(index))?;
+            let name = bytes.take_constant(|index| constant_pool.get_utf8(index))?;
+            ElementValue::Enum { descriptor, name }
+        }
+        b'c' => ElementValue::Class(bytes.take_constant(|index| constant_pool.get_utf8(index))?),
+        b'@' => ElementValue::Annotation(bytes.context("annotation_value", |bytes| {
+            parse_annotation(bytes, constant_pool)
+        })?),
+        b'[' => {
+            let num_values: w2 = bytes.take()?;
+            let mut values = Vec::with_capacity(num_values.into());
+            for i in 0..num_values {
+                values.push(bytes.context(format!("values[{}]", i), |bytes| {
+                    parse_element_value(bytes, constant_pool)
+                })?);
+            }
+            ElementValue::Array(values)
+        }
+        tag => {
+            return Err(bytes.error_at(offset, ClassFileError::InvalidElementValueTag { tag }));
+        }
+    })
+}
+
+pub fn write_annotations(
+    annotations: Annotations,
+    constant_pool: &mut ConstantPool,
+) -> Result<Vec<u8>, ClassFileError> {
+    let mut writer = ByteWriter::new();
+    write_annotation_list(annotations, constant_pool, &mut writer)?;
+    Ok(writer.into())
+}
+
+pub fn write_parameter_annotations(
+    parameter_annotations: ParameterAnnotations,
+    constant_pool: &mut ConstantPool,
+) -> Result<Vec<u8>, ClassFileError> {
+    let mut writer = ByteWriter::new();
+    writer.write(parameter_annotations.len() as w1);
+    for annotations in parameter_annotations {
+        write_annotation_list(annotations, constant_pool, &mut writer)?;
+    }
+    Ok(writer.into())
+}
+
+fn write_annotation_list(
+    annotations: Annotations,
+    constant_pool: &mut ConstantPool,
+    writer: &mut ByteWriter,
+) -> Result<(), ClassFileError> {
+    writer.write(annotations.len() as w2);
+    for annotation in annotations {
+        write_annotation(annotation, constant_pool, writer)?;
+    }
+    Ok(())
+}
+
+pub fn write_annotation(
+    annotation: Annotation,
+    constant_pool: &mut ConstantPool,
+    writer: &mut ByteWriter,
+) -> Result<(), ClassFileError> {
+    writer.write(constant_pool.push_utf8(annotation.descriptor)?);
+    writer.write(annotation.elements.len() as w2);
+    for ElementValuePair { name, value } in annotation.elements {
+        writer.write(constant_pool.push_utf8(name)?);
+        write_element_value(value, constant_pool, writer)?;
+    }
+    Ok(())
+}
+
+pub fn write_element_value(
+    value: ElementValue,
+    constant_pool: &mut ConstantPool,
+    writer: &mut ByteWriter,
+) -> Result<(), ClassFileError> {
+    let int = |constant_pool: &mut ConstantPool, int: i32| {
+        constant_pool.push(Constant(CpTag::Integer, CpInfo::Integer { int: int as w4 }))
+    };
+    let (tag, index) = match value {
+        ElementValue::Byte(byte) => (b'B', int(constant_pool, byte.into())?),
+        ElementValue::Char(char) => (b'C', int(constant_pool, char.into())?),
+        ElementValue::Integer(integer) => (b'I', int(constant_pool, integer)?),
+        ElementValue::Short(short) => (b'S', int(constant_pool, short.into())?),
+        ElementValue::Boolean(boolean) => (b'Z', int(constant_pool, boolean.into())?),
+        ElementValue::Double(double) => (
+            b'D',
+            constant_pool.push(Constant(CpTag::Double, CpInfo::Double { double }))?,
+        ),
+        ElementValue::Float(float) => (
+            b'F',
+            constant_pool.push(Constant(CpTag::Float, CpInfo::Float { float }))?,
+        ),
+        ElementValue::Long(long) => (
+            b'J',
+            constant_pool.push(Constant(CpTag::Long, CpInfo::Long { long: long as w8 }))?,
+        ),
+        ElementValue::String(string) => (b's', constant_pool.push_utf8(string)?),
+        ElementValue::Class(class) => (b'c', constant_pool.push_utf8(class)?),
+        ElementValue::Enum { descriptor, name } => {
+            writer.write(b'e');
+            writer.write(constant_pool.push_utf8(descriptor)?);
+            writer.write(constant_pool.push_utf8(name)?);
+            return Ok(());
+        }
+        ElementValue::Annotation(annotation) => {
+            writer.write(b'@');
+            write_annotation(annotation, constant_pool, writer)?;
+            return Ok(());
+        }
+        ElementValue::Array(values) => {
+            writer.write(b'[');
+            writer.write(values.len() as w2);
+            for value in values {
+                write_element_value(value, constant_pool, writer)?;
+            }
+            return Ok(());
+        }
+    };
+    writer.write(tag);
+    writer.write(index);
+    Ok(())
+}
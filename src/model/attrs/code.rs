@@ -1,253 +1,1080 @@
-use std::convert::{TryFrom, TryInto};
-use crate::bytecode::reader::{ByteSize, Take};
-use crate::bytecode::writer::{ByteWriter, Writeable};
-use serde::{Deserialize, Serialize};
-use crate::bytecode::reader::ByteReader;
+use crate::bytecode::reader::{ByteReader, Take};
+use crate::bytecode::writer::ByteWriter;
+use crate::constant_pool::{Constant, ConstantPool, CpTag};
+use crate::error::{ClassFileError, ParseError};
 use crate::model::attrs::Attribute;
+use crate::model::constant::{CallSite, Loadable};
+use crate::model::descriptor::{resolve_descriptor, FieldType, MethodDescriptor};
 use crate::{w1, w2, w4};
-use crate::constant_pool::{Constant, ConstantPool, CpInfo, CpTag};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::convert::{TryFrom, TryInto};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Code {
     pub max_stack: w2,
     pub max_locals: w2,
     /// Len: w4
-    pub code: Vec<OpcodeInfo>,
+    pub code: Vec<Instruction>,
     /// Len: w2
     pub exception_table: Vec<exception_table::ExceptionTableElement>,
     /// Len: w2
     pub attributes: Vec<Attribute>,
 }
 
+/// A position in [Code::code], which branches, exception ranges and debug tables point at.
+/// Labels read from a class file are named after the pc they mark.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Label(pub w4);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Instruction {
+    /// Marks the position of the instruction that follows it
+    Label {
+        label: Label,
+    },
+    Op(OpcodeInfo),
+}
+
+impl From<OpcodeInfo> for Instruction {
+    fn from(op: OpcodeInfo) -> Self {
+        Instruction::Op(op)
+    }
+}
+
+impl From<Label> for Instruction {
+    fn from(label: Label) -> Self {
+        Instruction::Label { label }
+    }
+}
+
+/// The pc each label was placed at when encoding a [Code]
+#[derive(Debug, Default)]
+pub struct Labels(HashMap<Label, usize>);
+
+impl Labels {
+    /// Where [label] was placed; fails if it isn't in the code
+    pub fn pc(&self, label: Label) -> Result<usize, ClassFileError> {
+        self.0
+            .get(&label)
+            .copied()
+            .ok_or(ClassFileError::UnplacedLabel { label: label.0 })
+    }
+
+    /// [Labels::pc] for the `u2` pcs of exception tables and attributes,
+    /// which can't point past the first 65536 bytes of the code
+    pub fn pc_w2(&self, label: Label) -> Result<w2, ClassFileError> {
+        let pc = self.pc(label)?;
+        w2::try_from(pc).map_err(|_| ClassFileError::PcOutOfRange { pc })
+    }
+}
+
+impl Code {
+    /// Reads the whole code array; branch targets are labels named after their pc
+    pub(crate) fn decode_instructions(
+        reader: &mut ByteReader,
+        constant_pool: &ConstantPool,
+    ) -> Result<Vec<(usize, OpcodeInfo)>, ParseError> {
+        let mut ops = vec![];
+        while !reader.is_empty() {
+            let pc = reader.position();
+            let op = reader.context(format!("code[pc={}]", pc), |reader| {
+                OpcodeInfo::decode_opcode_info(reader, constant_pool)
+            })?;
+            ops.push((pc, op));
+        }
+        Ok(ops)
+    }
+
+    /// Puts a label before each instruction one of [labels] points at.
+    /// Fails if a label points into the middle of an instruction or past the end of the code.
+    pub(crate) fn place_labels(
+        ops: Vec<(usize, OpcodeInfo)>,
+        code_length: usize,
+        labels: BTreeSet<Label>,
+    ) -> Result<Vec<Instruction>, ClassFileError> {
+        let mut labels = labels.into_iter().peekable();
+        let mut code: Vec<Instruction> = Vec::with_capacity(ops.len() + labels.len());
+        let mut place = |code: &mut Vec<Instruction>, pc: usize| {
+            while let Some(&label) = labels.peek() {
+                if label.0 as usize > pc {
+                    break;
+                }
+                if (label.0 as usize) < pc {
+                    return Err(ClassFileError::InvalidCodeOffset { pc: label.0 as i64 });
+                }
+                code.push(label.into());
+                labels.next();
+            }
+            Ok(())
+        };
+        for (pc, op) in ops {
+            place(&mut code, pc)?;
+            code.push(op.into());
+        }
+        place(&mut code, code_length)?;
+        match labels.next() {
+            Some(label) => Err(ClassFileError::InvalidCodeOffset { pc: label.0 as i64 }),
+            None => Ok(code),
+        }
+    }
+
+    /// Lays out the instructions and writes the code array, returning where each label ended up.
+    /// A branch whose target is out of reach of a 16-bit offset is widened:
+    /// `goto` and `jsr` become `goto_w` and `jsr_w`, while a conditional branch
+    /// jumps over a `goto_w` when its condition doesn't hold.
+    pub(crate) fn encode_instructions(
+        code: Vec<Instruction>,
+        constant_pool: &mut ConstantPool,
+    ) -> Result<(Vec<w1>, Labels), ClassFileError> {
+        let (pcs, labels, widened) = Self::widen(&code, constant_pool)?;
+        let mut writer = ByteWriter::new();
+        for (i, instruction) in code.iter().enumerate() {
+            if let Instruction::Op(op) = instruction {
+                Self::encode_op(op, widened[i], pcs[i], constant_pool, &mut writer, &labels)?;
+            }
+        }
+        Ok((writer.into(), labels))
+    }
+
+    /// The pc each instruction in [Code::code] would be written at with [constant_pool].
+    /// Constants it doesn't have yet are added to a copy of it, so if the code is written
+    /// with a pool that has more constants, an `ldc` may become an `ldc_w`
+    /// and the pcs after it be one higher.
+    pub(crate) fn pcs(&self, constant_pool: &ConstantPool) -> Result<Vec<usize>, ClassFileError> {
+        Ok(Self::widen(&self.code, &mut constant_pool.clone())?.0)
+    }
+
+    /// Lays out the instructions, widening branches until every one of them reaches its target
+    fn widen(
+        code: &[Instruction],
+        constant_pool: &mut ConstantPool,
+    ) -> Result<(Vec<usize>, Labels, Vec<bool>), ClassFileError> {
+        let mut widened = vec![false; code.len()];
+        loop {
+            let (pcs, labels) = Self::layout(code, &widened, constant_pool)?;
+            let mut changed = false;
+            for (i, instruction) in code.iter().enumerate() {
+                let target = match instruction {
+                    Instruction::Op(op) if !widened[i] => op.short_branch(),
+                    _ => None,
+                };
+                if let Some(target) = target {
+                    let offset = labels.pc(target)? as i64 - pcs[i] as i64;
+                    if i16::try_from(offset).is_err() {
+                        widened[i] = true;
+                        changed = true;
+                    }
+                }
+            }
+            if !changed {
+                return Ok((pcs, labels, widened));
+            }
+        }
+    }
+
+    /// The pc of every instruction, and where each label is, given which branches are widened.
+    /// Fails if a label is placed more than once.
+    fn layout(
+        code: &[Instruction],
+        widened: &[bool],
+        constant_pool: &mut ConstantPool,
+    ) -> Result<(Vec<usize>, Labels), ClassFileError> {
+        // An op's size doesn't depend on where its labels are, only on whether they're placed
+        let placeholders = Labels(
+            code.iter()
+                .filter_map(|instruction| match instruction {
+                    Instruction::Label { label } => Some((*label, 0)),
+                    Instruction::Op(_) => None,
+                })
+                .collect(),
+        );
+        let mut pcs = Vec::with_capacity(code.len());
+        let mut labels = Labels::default();
+        let mut pc = 0;
+        for (i, instruction) in code.iter().enumerate() {
+            pcs.push(pc);
+            match instruction {
+                Instruction::Label { label } => {
+                    if labels.0.insert(*label, pc).is_some() {
+                        return Err(ClassFileError::DuplicateLabel { label: label.0 });
+                    }
+                }
+                Instruction::Op(op) => {
+                    let mut writer = ByteWriter::new();
+                    Self::encode_op(
+                        op,
+                        widened[i],
+                        pc,
+                        constant_pool,
+                        &mut writer,
+                        &placeholders,
+                    )?;
+                    pc += Vec::<w1>::from(writer).len();
+                }
+            }
+        }
+        Ok((pcs, labels))
+    }
+
+    fn encode_op(
+        op: &OpcodeInfo,
+        widened: bool,
+        pc: usize,
+        constant_pool: &mut ConstantPool,
+        writer: &mut ByteWriter,
+        labels: &Labels,
+    ) -> Result<(), ClassFileError> {
+        match (widened, op.short_branch()) {
+            (true, Some(branch)) => match op {
+                OpcodeInfo::goto { .. } => OpcodeInfo::goto_w { branch }.encode_opcode_info(
+                    constant_pool,
+                    writer,
+                    pc,
+                    labels,
+                ),
+                OpcodeInfo::jsr { .. } => OpcodeInfo::jsr_w { branch }.encode_opcode_info(
+                    constant_pool,
+                    writer,
+                    pc,
+                    labels,
+                ),
+                _ => {
+                    // if<!cond> +8; goto_w branch
+                    let inverse = op.opcode().inverse_condition().unwrap_or(Opcodes::nop);
+                    writer.write(w1::from(inverse));
+                    writer.write(8 as w2);
+                    OpcodeInfo::goto_w { branch }.encode_opcode_info(
+                        constant_pool,
+                        writer,
+                        pc + 3,
+                        labels,
+                    )
+                }
+            },
+            _ => match op {
+                OpcodeInfo::ldc { constant }
+                | OpcodeInfo::ldc_w { constant }
+                | OpcodeInfo::ldc2_w { constant } => {
+                    let opcode = ldc_opcode(op.opcode(), constant, constant_pool)?;
+                    writer.write(w1::from(opcode));
+                    constant.encode(constant_pool, writer, Position { pc, opcode }, labels)
+                }
+                _ => op.encode_opcode_info(constant_pool, writer, pc, labels),
+            },
+        }
+    }
+}
 
 pub mod exception_table {
-    use crate::bytecode::reader::Take;
-use serde::{Deserialize, Serialize};
     use crate::bytecode::reader::ByteReader;
+    use crate::bytecode::reader::Take;
     use crate::bytecode::writer::ByteWriter;
-    use crate::constant_pool::{Constant, ConstantPool, CpInfo, CpTag};
-    use crate::typedefs::w2;
+    use crate::constant_pool::ConstantPool;
+    use crate::error::{ClassFileError, ParseError};
+    use crate::model::attrs::code::{Label, Labels};
+    use crate::typedefs::{w2, w4};
+    use serde::{Deserialize, Serialize};
 
     #[derive(Debug, Serialize, Deserialize)]
     pub struct ExceptionTableElement {
-        pub start_pc: w2,
-        pub end_pc: w2,
-        pub handler_pc: w2,
+        /// First instruction covered by the handler
+        pub start: Label,
+        /// End of the covered range (exclusive)
+        pub end: Label,
+        pub handler: Label,
         pub catch_type: Option<String>,
     }
 
-    pub fn parse_exception_table(constant_pool: &ConstantPool, bytes: &mut ByteReader) -> Result<Vec<ExceptionTableElement>, String> {
+    impl ExceptionTableElement {
+        pub fn labels(&self) -> Vec<Label> {
+            vec![self.start, self.end, self.handler]
+        }
+    }
+
+    pub fn parse_exception_table(
+        constant_pool: &ConstantPool,
+        bytes: &mut ByteReader,
+    ) -> Result<Vec<ExceptionTableElement>, ParseError> {
         let exception_table_length: w2 = bytes.take()?;
         let mut exception_table: Vec<ExceptionTableElement> = vec![];
-        for _ in 0..exception_table_length {
-            let start_pc: w2 = bytes.take()?;
-            let end_pc: w2 = bytes.take()?;
-            let handler_pc: w2 = bytes.take()?;
-
-            let catch_type_index: w2 = bytes.take()?;
-            let catch_type = if catch_type_index == 0 {
-                Option::None
-            } else {
-                Option::Some(constant_pool.get_class_name(catch_type_index).map_err(
-                    |e| {
-                        format!(
-                            "Failed finding exception class name at index {}: {}",
-                            catch_type_index, e
-                        )
-                    },
-                )?)
-            };
-            exception_table.push(ExceptionTableElement {
-                start_pc,
-                end_pc,
-                handler_pc,
-                catch_type,
-            });
+        for i in 0..exception_table_length {
+            bytes.context(format!("exception_table[{}]", i), |bytes| {
+                let start_pc: w2 = bytes.take()?;
+                let end_pc: w2 = bytes.take()?;
+                let handler_pc: w2 = bytes.take()?;
+
+                let catch_type = bytes.take_constant(|catch_type_index| {
+                    if catch_type_index == 0 {
+                        Ok(Option::None)
+                    } else {
+                        constant_pool
+                            .get_class_name(catch_type_index)
+                            .map(Option::Some)
+                    }
+                })?;
+                exception_table.push(ExceptionTableElement {
+                    start: Label(start_pc as w4),
+                    end: Label(end_pc as w4),
+                    handler: Label(handler_pc as w4),
+                    catch_type,
+                });
+                Ok(())
+            })?;
         }
         Ok(exception_table)
     }
 
-    pub fn write_exception_table(constant_pool: &mut ConstantPool, exception_table: Vec<ExceptionTableElement>, writer: &mut ByteWriter) {
+    pub fn write_exception_table(
+        constant_pool: &mut ConstantPool,
+        exception_table: Vec<ExceptionTableElement>,
+        labels: &Labels,
+        writer: &mut ByteWriter,
+    ) -> Result<(), ClassFileError> {
         writer.write(exception_table.len() as w2);
         for ExceptionTableElement {
-            start_pc,
-            end_pc,
-            handler_pc,
+            start,
+            end,
+            handler,
             catch_type,
         } in exception_table
         {
-            writer.write(start_pc);
-            writer.write(end_pc);
-            writer.write(handler_pc);
+            writer.write(labels.pc_w2(start)?);
+            writer.write(labels.pc_w2(end)?);
+            writer.write(labels.pc_w2(handler)?);
 
             let catch_type_index: w2 = match catch_type {
                 Option::None => 0,
                 Option::Some(exception_type_name) => {
-                    let class_name_index = constant_pool.push(Constant(
-                        CpTag::Utf8,
-                        CpInfo::Utf8 {
-                            string: exception_type_name,
-                        },
-                    ));
-                    constant_pool.push(Constant(
-                        CpTag::Class,
-                        CpInfo::Class {
-                            name_index: class_name_index,
-                        },
-                    ))
+                    constant_pool.push_class(exception_type_name)?
                 }
             };
             writer.write(catch_type_index);
         }
+        Ok(())
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct ClassRef(pub String);
-impl ClassRef {
-    fn decode(bytes: &mut ByteReader, constant_pool: &ConstantPool) -> Result<ClassRef, String> {
-        Ok(Self(constant_pool.get_class_name(bytes.take()?)?))
-    }
-    fn encode(self, constant_pool: &mut ConstantPool, writer: &mut ByteWriter) {
-        let class_name_index =
-            constant_pool.push(Constant(CpTag::Utf8, CpInfo::Utf8 { string: self.0 }));
-        let class_index = constant_pool.push(Constant(
-            CpTag::Class,
-            CpInfo::Class {
-                name_index: class_name_index,
-            },
-        ));
-        writer.write(class_index);
+/// The instruction an operand belongs to
+#[derive(Debug, Copy, Clone)]
+struct Position {
+    pc: usize,
+    opcode: Opcodes,
+}
+
+/// An operand of an instruction in the code array
+trait Operand: Sized {
+    fn decode(
+        bytes: &mut ByteReader,
+        constant_pool: &ConstantPool,
+        at: Position,
+    ) -> Result<Self, ParseError>;
+    fn encode(
+        &self,
+        constant_pool: &mut ConstantPool,
+        writer: &mut ByteWriter,
+        at: Position,
+        labels: &Labels,
+    ) -> Result<(), ClassFileError>;
+    /// The labels this operand points at
+    fn labels(&self) -> Vec<Label> {
+        vec![]
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct FieldRef { pub class: ClassRef, pub name: String, pub descriptor: String }
-impl FieldRef {
-    fn decode(bytes: &mut ByteReader, constant_pool: &ConstantPool) -> Result<Self, String> {
-        let field_index:  w2 = bytes.take()?;
-        let (class_index, name_and_type_index) = match constant_pool[field_index]
-            .as_ref()
-            .ok_or(format!("Invalid index: {}", field_index))?
-        {
-            Constant(CpTag::Fieldref, CpInfo::Fieldref { class_index, name_and_type_index }) => (*class_index, *name_and_type_index),
-            Constant(tag, _) => {
-                return Err(format!(
-                    "Wrong constant type at index {idx}: expected `FieldRef`, found `{found}`",
-                    idx = field_index,
-                    found = tag
-                ))
+macro_rules! impl_operand_for {
+    ($($t:ty),*) => {
+        $(impl Operand for $t {
+            fn decode(bytes: &mut ByteReader, _: &ConstantPool, _: Position) -> Result<Self, ParseError> {
+                bytes.take()
             }
-        };
-        let class = ClassRef(constant_pool.get_class_name(class_index)?);
-        let (name, descriptor) = constant_pool.get_name_and_type(name_and_type_index)?;
-        Ok(Self { class, name, descriptor })
+            fn encode(&self, _: &mut ConstantPool, writer: &mut ByteWriter, _: Position, _: &Labels) -> Result<(), ClassFileError> {
+                writer.write(*self);
+                Ok(())
+            }
+        })*
+    };
+}
+
+impl_operand_for!(w1, w2, i8, i16);
+
+impl Label {
+    /// Reads a branch offset of type [T] and turns it into the label of its target
+    fn take_offset<T: Into<i64>>(bytes: &mut ByteReader, at: Position) -> Result<Label, ParseError>
+    where
+        ByteReader: Take<T>,
+    {
+        let offset_position = bytes.offset();
+        let offset: T = bytes.take()?;
+        let target = at.pc as i64 + offset.into();
+        w4::try_from(target).map(Label).map_err(|_| {
+            bytes.error_at(
+                offset_position,
+                ClassFileError::InvalidCodeOffset { pc: target },
+            )
+        })
+    }
+
+    /// The offset of this label from the instruction at [at]
+    fn offset_from(self, at: Position, labels: &Labels) -> Result<i64, ClassFileError> {
+        Ok(labels.pc(self)? as i64 - at.pc as i64)
+    }
+}
+
+impl Operand for Label {
+    /// Branch offsets are relative to the pc of the instruction;
+    /// `goto_w` and `jsr_w` have 32-bit offsets, all other branches 16-bit ones
+    fn decode(bytes: &mut ByteReader, _: &ConstantPool, at: Position) -> Result<Self, ParseError> {
+        if at.opcode.is_wide_branch() {
+            Label::take_offset::<i32>(bytes, at)
+        } else {
+            Label::take_offset::<i16>(bytes, at)
+        }
+    }
+
+    fn encode(
+        &self,
+        _: &mut ConstantPool,
+        writer: &mut ByteWriter,
+        at: Position,
+        labels: &Labels,
+    ) -> Result<(), ClassFileError> {
+        let offset = self.offset_from(at, labels)?;
+        if at.opcode.is_wide_branch() {
+            writer.write(offset as i32);
+        } else {
+            writer.write(offset as i16);
+        }
+        Ok(())
+    }
+
+    fn labels(&self) -> Vec<Label> {
+        vec![*self]
+    }
+}
+
+/// Switch operands start at a multiple of 4 bytes from the start of the code
+fn switch_padding(at: Position) -> usize {
+    (4 - (at.pc + 1) % 4) % 4
+}
+
+/// Jumps to `targets[key - low]`, or to `default` if the key is out of range
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TableSwitch {
+    pub default: Label,
+    pub low: i32,
+    pub targets: Vec<Label>,
+}
+
+impl Operand for TableSwitch {
+    fn decode(bytes: &mut ByteReader, _: &ConstantPool, at: Position) -> Result<Self, ParseError> {
+        bytes.take_bytes(switch_padding(at))?;
+        let default = Label::take_offset::<i32>(bytes, at)?;
+        let low: i32 = bytes.take()?;
+        let high_position = bytes.offset();
+        let high: i32 = bytes.take()?;
+        if high < low {
+            return Err(bytes.error_at(
+                high_position,
+                ClassFileError::InvalidSwitchBounds {
+                    low,
+                    high: high as i64,
+                },
+            ));
+        }
+        let mut targets = vec![];
+        for _ in low..=high {
+            targets.push(Label::take_offset::<i32>(bytes, at)?);
+        }
+        Ok(TableSwitch {
+            default,
+            low,
+            targets,
+        })
+    }
+
+    fn encode(
+        &self,
+        _: &mut ConstantPool,
+        writer: &mut ByteWriter,
+        at: Position,
+        labels: &Labels,
+    ) -> Result<(), ClassFileError> {
+        // At least one target, and `high` has to fit in an int
+        let high = self.low as i64 + self.targets.len() as i64 - 1;
+        if self.targets.is_empty() || high > i32::MAX as i64 {
+            return Err(ClassFileError::InvalidSwitchBounds {
+                low: self.low,
+                high,
+            });
+        }
+        for _ in 0..switch_padding(at) {
+            writer.write(0 as w1);
+        }
+        writer.write(self.default.offset_from(at, labels)? as i32);
+        writer.write(self.low);
+        writer.write(high as i32);
+        for target in &self.targets {
+            writer.write(target.offset_from(at, labels)? as i32);
+        }
+        Ok(())
+    }
+
+    fn labels(&self) -> Vec<Label> {
+        let mut labels = vec![self.default];
+        labels.extend(&self.targets);
+        labels
+    }
+}
+
+/// Jumps to the target paired with the key, or to `default` if there's none.
+/// Pairs are sorted by key when written.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LookupSwitch {
+    pub default: Label,
+    pub pairs: Vec<(i32, Label)>,
+}
+
+impl Operand for LookupSwitch {
+    fn decode(bytes: &mut ByteReader, _: &ConstantPool, at: Position) -> Result<Self, ParseError> {
+        bytes.take_bytes(switch_padding(at))?;
+        let default = Label::take_offset::<i32>(bytes, at)?;
+        let npairs: w4 = bytes.take()?;
+        let mut pairs = vec![];
+        for _ in 0..npairs {
+            let key: i32 = bytes.take()?;
+            pairs.push((key, Label::take_offset::<i32>(bytes, at)?));
+        }
+        Ok(LookupSwitch { default, pairs })
+    }
+
+    fn encode(
+        &self,
+        _: &mut ConstantPool,
+        writer: &mut ByteWriter,
+        at: Position,
+        labels: &Labels,
+    ) -> Result<(), ClassFileError> {
+        for _ in 0..switch_padding(at) {
+            writer.write(0 as w1);
+        }
+        writer.write(self.default.offset_from(at, labels)? as i32);
+        writer.write(self.pairs.len() as w4);
+        let mut pairs = self.pairs.clone();
+        pairs.sort_by_key(|(key, _)| *key);
+        for (key, target) in pairs {
+            writer.write(key);
+            writer.write(target.offset_from(at, labels)? as i32);
+        }
+        Ok(())
+    }
+
+    fn labels(&self) -> Vec<Label> {
+        let mut labels = vec![self.default];
+        labels.extend(self.pairs.iter().map(|(_, target)| *target));
+        labels
+    }
+}
+
+/// An instruction modified by `wide` to take a 16-bit local variable index
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WideInstruction {
+    iload { index: w2 },
+    lload { index: w2 },
+    fload { index: w2 },
+    dload { index: w2 },
+    aload { index: w2 },
+    istore { index: w2 },
+    lstore { index: w2 },
+    fstore { index: w2 },
+    dstore { index: w2 },
+    astore { index: w2 },
+    ret { index: w2 },
+    iinc { index: w2, constant: i16 },
+}
+
+impl WideInstruction {
+    pub fn opcode(&self) -> Opcodes {
+        use WideInstruction::*;
+        match self {
+            iload { .. } => Opcodes::iload,
+            lload { .. } => Opcodes::lload,
+            fload { .. } => Opcodes::fload,
+            dload { .. } => Opcodes::dload,
+            aload { .. } => Opcodes::aload,
+            istore { .. } => Opcodes::istore,
+            lstore { .. } => Opcodes::lstore,
+            fstore { .. } => Opcodes::fstore,
+            dstore { .. } => Opcodes::dstore,
+            astore { .. } => Opcodes::astore,
+            ret { .. } => Opcodes::ret,
+            iinc { .. } => Opcodes::iinc,
+        }
     }
 
-    fn encode(self, constant_pool: &mut ConstantPool, writer: &mut ByteWriter) {
-        let class_index: w2 = {
-            let mut class_writer = ByteWriter::new();
-            self.class.encode(constant_pool, &mut class_writer);
-            let buffer: Vec<w1> = class_writer.into();
-            w2::read(buffer.as_slice())
+    pub fn index(&self) -> w2 {
+        use WideInstruction::*;
+        match self {
+            iload { index }
+            | lload { index }
+            | fload { index }
+            | dload { index }
+            | aload { index }
+            | istore { index }
+            | lstore { index }
+            | fstore { index }
+            | dstore { index }
+            | astore { index }
+            | ret { index }
+            | iinc { index, .. } => *index,
+        }
+    }
+}
+
+impl Operand for WideInstruction {
+    fn decode(bytes: &mut ByteReader, _: &ConstantPool, at: Position) -> Result<Self, ParseError> {
+        use WideInstruction::*;
+        let offset = bytes.offset();
+        let byte: w1 = bytes.take()?;
+        let invalid = ClassFileError::InvalidOpcode {
+            pc: at.pc + 1,
+            byte,
         };
-        let name_index =
-            constant_pool.push(Constant(CpTag::Utf8, CpInfo::Utf8 { string: self.name }));
-        let descriptor_index =
-            constant_pool.push(Constant(CpTag::Utf8, CpInfo::Utf8 { string: self.descriptor }));
-        let name_and_type_index =
-            constant_pool.push(Constant(CpTag::NameAndType, CpInfo::NameAndType { name_index, descriptor_index }));
-        let field_index = constant_pool.push(Constant(CpTag::Fieldref, CpInfo::Fieldref { class_index , name_and_type_index }));
-        writer.write(field_index);
+        let opcode =
+            Opcodes::try_from(byte).map_err(|_| bytes.error_at(offset, invalid.clone()))?;
+        let index: w2 = bytes.take()?;
+        Ok(match opcode {
+            Opcodes::iload => iload { index },
+            Opcodes::lload => lload { index },
+            Opcodes::fload => fload { index },
+            Opcodes::dload => dload { index },
+            Opcodes::aload => aload { index },
+            Opcodes::istore => istore { index },
+            Opcodes::lstore => lstore { index },
+            Opcodes::fstore => fstore { index },
+            Opcodes::dstore => dstore { index },
+            Opcodes::astore => astore { index },
+            Opcodes::ret => ret { index },
+            Opcodes::iinc => iinc {
+                index,
+                constant: bytes.take()?,
+            },
+            _ => return Err(bytes.error_at(offset, invalid)),
+        })
+    }
+
+    fn encode(
+        &self,
+        _: &mut ConstantPool,
+        writer: &mut ByteWriter,
+        _: Position,
+        _: &Labels,
+    ) -> Result<(), ClassFileError> {
+        writer.write(w1::from(self.opcode()));
+        writer.write(self.index());
+        if let WideInstruction::iinc { constant, .. } = self {
+            writer.write(*constant);
+        }
+        Ok(())
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct MethodRef { pub class: ClassRef, pub name: String, pub descriptor: String }
-impl MethodRef {
-    fn decode(bytes: &mut ByteReader, constant_pool: &ConstantPool) -> Result<Self, String> {
-        let method_index:  w2 = bytes.take()?;
-        let (class_index, name_and_type_index) = match constant_pool[method_index]
-            .as_ref()
-            .ok_or(format!("Invalid index: {}", method_index))?
-        {
-            Constant(CpTag::Methodref, CpInfo::Methodref { class_index, name_and_type_index }) => (*class_index, *name_and_type_index),
-            Constant(tag, _) => {
-                return Err(format!(
-                    "Wrong constant type at index {idx}: expected `MethodRef`, found `{found}`",
-                    idx = method_index,
-                    found = tag
-                ))
-            }
+/// The element type of an array created by `newarray`
+/// (https://docs.oracle.com/javase/specs/jvms/se12/html/jvms-6.html#jvms-6.5.newarray)
+#[allow(non_camel_case_types)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ArrayType {
+    T_BOOLEAN = 4,
+    T_CHAR = 5,
+    T_FLOAT = 6,
+    T_DOUBLE = 7,
+    T_BYTE = 8,
+    T_SHORT = 9,
+    T_INT = 10,
+    T_LONG = 11,
+}
+
+impl ArrayType {
+    /// The field descriptor of the element type
+    ///
+    ///```rust
+    /// use rusty_javap::model::attrs::code::ArrayType;
+    /// assert_eq!(ArrayType::T_BOOLEAN.descriptor(), 'Z');
+    /// assert_eq!(ArrayType::T_LONG.descriptor(), 'J');
+    ///```
+    pub fn descriptor(self) -> char {
+        use ArrayType::*;
+        match self {
+            T_BOOLEAN => 'Z',
+            T_CHAR => 'C',
+            T_FLOAT => 'F',
+            T_DOUBLE => 'D',
+            T_BYTE => 'B',
+            T_SHORT => 'S',
+            T_INT => 'I',
+            T_LONG => 'J',
+        }
+    }
+}
+
+impl TryFrom<w1> for ArrayType {
+    /// The unrecognized byte
+    type Error = w1;
+
+    fn try_from(value: w1) -> Result<Self, Self::Error> {
+        use ArrayType::*;
+        Ok(match value {
+            4 => T_BOOLEAN,
+            5 => T_CHAR,
+            6 => T_FLOAT,
+            7 => T_DOUBLE,
+            8 => T_BYTE,
+            9 => T_SHORT,
+            10 => T_INT,
+            11 => T_LONG,
+            _ => return Err(value),
+        })
+    }
+}
+
+impl Operand for ArrayType {
+    fn decode(bytes: &mut ByteReader, _: &ConstantPool, _: Position) -> Result<Self, ParseError> {
+        let offset = bytes.offset();
+        let atype: w1 = bytes.take()?;
+        ArrayType::try_from(atype)
+            .map_err(|atype| bytes.error_at(offset, ClassFileError::InvalidArrayType { atype }))
+    }
+
+    fn encode(
+        &self,
+        _: &mut ConstantPool,
+        writer: &mut ByteWriter,
+        _: Position,
+        _: &Labels,
+    ) -> Result<(), ClassFileError> {
+        writer.write(*self as w1);
+        Ok(())
+    }
+}
+
+impl Operand for Loadable {
+    /// `ldc` has an 8-bit index, `ldc_w` and `ldc2_w` 16-bit ones
+    fn decode(
+        bytes: &mut ByteReader,
+        constant_pool: &ConstantPool,
+        at: Position,
+    ) -> Result<Self, ParseError> {
+        let offset = bytes.offset();
+        let index: w2 = match at.opcode {
+            Opcodes::ldc => w2::from(Take::<w1>::take(bytes)?),
+            _ => bytes.take()?,
         };
-        let class = ClassRef(constant_pool.get_class_name(class_index)?);
-        let (name, descriptor) = constant_pool.get_name_and_type(name_and_type_index)?;
-        Ok(Self { class, name, descriptor })
+        let (constant, found) = constant_pool
+            .get_loadable(index)
+            .and_then(|constant| Ok((constant, constant_pool.get(index)?.0)))
+            .map_err(|e| bytes.error_at(offset, e))?;
+        if constant.is_wide() != (at.opcode == Opcodes::ldc2_w) {
+            return Err(bytes.error_at(
+                offset,
+                ClassFileError::WrongConstantType {
+                    index,
+                    expected: if at.opcode == Opcodes::ldc2_w {
+                        &[CpTag::Long, CpTag::Double, CpTag::Dynamic]
+                    } else {
+                        &[
+                            CpTag::Integer,
+                            CpTag::Float,
+                            CpTag::String,
+                            CpTag::Class,
+                            CpTag::MethodType,
+                            CpTag::MethodHandle,
+                            CpTag::Dynamic,
+                        ]
+                    },
+                    found,
+                },
+            ));
+        }
+        Ok(constant)
+    }
+
+    fn encode(
+        &self,
+        constant_pool: &mut ConstantPool,
+        writer: &mut ByteWriter,
+        at: Position,
+        _: &Labels,
+    ) -> Result<(), ClassFileError> {
+        let index = constant_pool.push_loadable(self.clone())?;
+        match at.opcode {
+            Opcodes::ldc => writer.write(index as w1),
+            _ => writer.write(index),
+        }
+        Ok(())
+    }
+}
+
+impl Operand for CallSite {
+    /// The index is followed by two zero bytes
+    fn decode(
+        bytes: &mut ByteReader,
+        constant_pool: &ConstantPool,
+        _: Position,
+    ) -> Result<Self, ParseError> {
+        let call_site = bytes.take_constant(|index| constant_pool.get_call_site(index))?;
+        let _zero: w2 = bytes.take()?;
+        Ok(call_site)
+    }
+
+    fn encode(
+        &self,
+        constant_pool: &mut ConstantPool,
+        writer: &mut ByteWriter,
+        _: Position,
+        _: &Labels,
+    ) -> Result<(), ClassFileError> {
+        writer.write(constant_pool.push_call_site(self.clone())?);
+        writer.write(0 as w2);
+        Ok(())
+    }
+}
+
+/// The instruction that loads [constant]: `ldc2_w` for longs and doubles,
+/// otherwise [requested] unless the constant's index doesn't fit `ldc`
+fn ldc_opcode(
+    requested: Opcodes,
+    constant: &Loadable,
+    constant_pool: &mut ConstantPool,
+) -> Result<Opcodes, ClassFileError> {
+    Ok(if constant.is_wide() {
+        Opcodes::ldc2_w
+    } else if requested == Opcodes::ldc
+        && constant_pool.push_loadable(constant.clone())? <= w1::MAX as w2
+    {
+        Opcodes::ldc
+    } else {
+        Opcodes::ldc_w
+    })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClassRef(pub String);
+impl Operand for ClassRef {
+    fn decode(
+        bytes: &mut ByteReader,
+        constant_pool: &ConstantPool,
+        _: Position,
+    ) -> Result<Self, ParseError> {
+        Ok(Self(bytes.take_constant(|index| {
+            constant_pool.get_class_name(index)
+        })?))
+    }
+    fn encode(
+        &self,
+        constant_pool: &mut ConstantPool,
+        writer: &mut ByteWriter,
+        _: Position,
+        _: &Labels,
+    ) -> Result<(), ClassFileError> {
+        writer.write(constant_pool.push_class(self.0.clone())?);
+        Ok(())
+    }
+}
+
+fn decode_member_ref<T>(
+    bytes: &mut ByteReader,
+    constant_pool: &ConstantPool,
+    tag: CpTag,
+    parse: fn(&str) -> Result<T, usize>,
+) -> Result<(ClassRef, String, T), ParseError> {
+    let (class, name, descriptor) = bytes.take_constant(|index| {
+        let (class, name, descriptor) = constant_pool.get_member_ref(index, tag)?;
+        Ok((class, name, resolve_descriptor(descriptor, parse)?))
+    })?;
+    Ok((ClassRef(class), name, descriptor))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldRef {
+    pub class: ClassRef,
+    pub name: String,
+    pub descriptor: FieldType,
+}
+
+impl Operand for FieldRef {
+    fn decode(
+        bytes: &mut ByteReader,
+        constant_pool: &ConstantPool,
+        _: Position,
+    ) -> Result<Self, ParseError> {
+        let (class, name, descriptor) =
+            decode_member_ref(bytes, constant_pool, CpTag::Fieldref, FieldType::parse)?;
+        Ok(Self {
+            class,
+            name,
+            descriptor,
+        })
+    }
+
+    fn encode(
+        &self,
+        constant_pool: &mut ConstantPool,
+        writer: &mut ByteWriter,
+        _: Position,
+        _: &Labels,
+    ) -> Result<(), ClassFileError> {
+        let index = constant_pool.push_member_ref(
+            CpTag::Fieldref,
+            self.class.0.clone(),
+            self.name.clone(),
+            self.descriptor.encode(),
+        )?;
+        writer.write(index);
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MethodRef {
+    pub class: ClassRef,
+    pub name: String,
+    pub descriptor: MethodDescriptor,
+    /// `invokestatic` and `invokespecial` can also call methods of interfaces
+    /// (through an `InterfaceMethodref`)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub interface: bool,
+}
+
+impl Operand for MethodRef {
+    fn decode(
+        bytes: &mut ByteReader,
+        constant_pool: &ConstantPool,
+        at: Position,
+    ) -> Result<Self, ParseError> {
+        let may_be_interface = matches!(at.opcode, Opcodes::invokestatic | Opcodes::invokespecial);
+        let mut interface = false;
+        let (class, name, descriptor) = bytes.take_constant(|index| {
+            interface = may_be_interface
+                && matches!(
+                    constant_pool.get(index),
+                    Ok(Constant(CpTag::InterfaceMethodref, _))
+                );
+            let tag = if interface {
+                CpTag::InterfaceMethodref
+            } else {
+                CpTag::Methodref
+            };
+            let (class, name, descriptor) = constant_pool.get_member_ref(index, tag)?;
+            Ok((
+                class,
+                name,
+                resolve_descriptor(descriptor, MethodDescriptor::parse)?,
+            ))
+        })?;
+        let class = ClassRef(class);
+        Ok(Self {
+            class,
+            name,
+            descriptor,
+            interface,
+        })
     }
 
-    fn encode(self, constant_pool: &mut ConstantPool, writer: &mut ByteWriter) {
-        let class_index: w2 = {
-            let mut class_writer = ByteWriter::new();
-            self.class.encode(constant_pool, &mut class_writer);
-            let buffer: Vec<w1> = class_writer.into();
-            w2::read(buffer.as_slice())
+    fn encode(
+        &self,
+        constant_pool: &mut ConstantPool,
+        writer: &mut ByteWriter,
+        _: Position,
+        _: &Labels,
+    ) -> Result<(), ClassFileError> {
+        let tag = if self.interface {
+            CpTag::InterfaceMethodref
+        } else {
+            CpTag::Methodref
         };
-        let name_index =
-            constant_pool.push(Constant(CpTag::Utf8, CpInfo::Utf8 { string: self.name }));
-        let descriptor_index =
-            constant_pool.push(Constant(CpTag::Utf8, CpInfo::Utf8 { string: self.descriptor }));
-        let name_and_type_index =
-            constant_pool.push(Constant(CpTag::NameAndType, CpInfo::NameAndType { name_index, descriptor_index }));
-        let field_index = constant_pool.push(Constant(CpTag::Methodref, CpInfo::Methodref { class_index , name_and_type_index }));
-        writer.write(field_index);
+        let index = constant_pool.push_member_ref(
+            tag,
+            self.class.0.clone(),
+            self.name.clone(),
+            self.descriptor.encode(),
+        )?;
+        writer.write(index);
+        Ok(())
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct InterfaceMethodRef { class: ClassRef, name: String, descriptor: String }
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InterfaceMethodRef {
+    pub class: ClassRef,
+    pub name: String,
+    pub descriptor: MethodDescriptor,
+    /// The argument count read from the class file, if it isn't the one
+    /// the descriptor gives; `None` writes the descriptor's
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub count: Option<w1>,
+}
+
 impl InterfaceMethodRef {
-    fn decode(bytes: &mut ByteReader, constant_pool: &ConstantPool) -> Result<Self, String> {
-        let method_index:  w2 = bytes.take()?;
-        let (class_index, name_and_type_index) = match constant_pool[method_index]
-            .as_ref()
-            .ok_or(format!("Invalid index: {}", method_index))?
-        {
-            Constant(CpTag::InterfaceMethodref, CpInfo::InterfaceMethodref { class_index, name_and_type_index }) => (*class_index, *name_and_type_index),
-            Constant(tag, _) => {
-                return Err(format!(
-                    "Wrong constant type at index {idx}: expected `InterfaceMethodref`, found `{found}`",
-                    idx = method_index,
-                    found = tag
-                ))
-            }
-        };
-        let class = ClassRef(constant_pool.get_class_name(class_index)?);
-        let (name, descriptor) = constant_pool.get_name_and_type(name_and_type_index)?;
-        Ok(Self { class, name, descriptor })
+    /// The argument count the descriptor gives, in slots; the receiver takes one too
+    fn descriptor_count(descriptor: &MethodDescriptor) -> w1 {
+        (descriptor.argument_slots() + 1) as w1
     }
+}
 
-    fn encode(self, constant_pool: &mut ConstantPool, writer: &mut ByteWriter) {
-        let class_index: w2 = {
-            let mut class_writer = ByteWriter::new();
-            self.class.encode(constant_pool, &mut class_writer);
-            let buffer: Vec<w1> = class_writer.into();
-            w2::read(buffer.as_slice())
-        };
-        let name_index =
-            constant_pool.push(Constant(CpTag::Utf8, CpInfo::Utf8 { string: self.name }));
-        let descriptor_index =
-            constant_pool.push(Constant(CpTag::Utf8, CpInfo::Utf8 { string: self.descriptor }));
-        let name_and_type_index =
-            constant_pool.push(Constant(CpTag::NameAndType, CpInfo::NameAndType { name_index, descriptor_index }));
-        let field_index = constant_pool.push(Constant(CpTag::InterfaceMethodref, CpInfo::InterfaceMethodref { class_index , name_and_type_index }));
-        writer.write(field_index);
+impl Operand for InterfaceMethodRef {
+    /// `invokeinterface` follows the index with its argument count and a zero byte
+    fn decode(
+        bytes: &mut ByteReader,
+        constant_pool: &ConstantPool,
+        _: Position,
+    ) -> Result<Self, ParseError> {
+        let (class, name, descriptor) = decode_member_ref(
+            bytes,
+            constant_pool,
+            CpTag::InterfaceMethodref,
+            MethodDescriptor::parse,
+        )?;
+        let count: w1 = bytes.take()?;
+        let _zero: w1 = bytes.take()?;
+        let count = (count != Self::descriptor_count(&descriptor)).then_some(count);
+        Ok(Self {
+            class,
+            name,
+            descriptor,
+            count,
+        })
+    }
+
+    fn encode(
+        &self,
+        constant_pool: &mut ConstantPool,
+        writer: &mut ByteWriter,
+        _: Position,
+        _: &Labels,
+    ) -> Result<(), ClassFileError> {
+        let index = constant_pool.push_member_ref(
+            CpTag::InterfaceMethodref,
+            self.class.0.clone(),
+            self.name.clone(),
+            self.descriptor.encode(),
+        )?;
+        writer.write(index);
+        writer.write(
+            self.count
+                .unwrap_or_else(|| Self::descriptor_count(&self.descriptor)),
+        );
+        writer.write(0 as w1);
+        Ok(())
     }
 }
 
 macro_rules! opcodes {
     ($($opname:ident = $opcode:literal $({ $($fieldname:ident: $fieldtype:ty),+ })?;)*) => {
         #[allow(non_camel_case_types)]
-        #[derive(Debug, Serialize, Deserialize, Copy, Clone)]
+        #[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq)]
         #[repr(u8)]
         pub enum Opcodes {
             $($opname = $opcode,)*
@@ -259,75 +1086,136 @@ macro_rules! opcodes {
             }
         }
         impl TryFrom<w1> for Opcodes {
-            type Error = String;
+            /// The unrecognized byte
+            type Error = w1;
 
             fn try_from(value: w1) -> Result<Self, Self::Error> {
                 match value {
                     $($opcode => Result::Ok(Self::$opname),)*
-                    _ => Result::Err(format!("Invalid opcode {}", value)),
+                    _ => Result::Err(value),
                 }
             }
         }
 
         #[allow(non_camel_case_types)]
-        #[derive(Debug, Serialize, Deserialize)]
+        #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
         pub enum OpcodeInfo {
             $($opname $({ $($fieldname: $fieldtype),+ })? ,)*
         }
 
         impl OpcodeInfo {
+            pub fn opcode(&self) -> Opcodes {
+                match self {
+                    $(OpcodeInfo::$opname { .. } => Opcodes::$opname,)*
+                }
+            }
+
+            /// The labels this instruction points at
+            pub fn labels(&self) -> Vec<Label> {
+                match self {
+                    $(OpcodeInfo::$opname $({ $($fieldname),+ })? => {
+                        let labels: Vec<Vec<Label>> = vec![$($(<$fieldtype as Operand>::labels($fieldname)),+)?];
+                        labels.concat()
+                    },)*
+                }
+            }
+
             pub fn decode_opcode_info(
                 bytes: &mut ByteReader,
                 constants: &ConstantPool
-            ) -> Result<Self, String> {
-                trait Decode<T> {
-                    fn decode(
-                        bytes: &mut ByteReader,
-                        _constants: &ConstantPool
-                    ) -> Result<T, String>;
-                }
-                impl<T> Decode<T> for T where ByteReader: Take<T> {
-                    fn decode(
-                        bytes: &mut ByteReader,
-                        _constants: &ConstantPool
-                    ) -> Result<T, String> {
-                        bytes.take()
-                    }
-                }
+            ) -> Result<Self, ParseError> {
+                let pc = bytes.position();
+                let offset = bytes.offset();
                 let opcode: w1 = bytes.take()?;
-                Ok(match opcode.try_into()? {
-                    $(Opcodes::$opname => OpcodeInfo::$opname$({ $($fieldname: <$fieldtype>::decode(bytes, constants)?),+ })? ,)*
+                let opcode: Opcodes = opcode
+                    .try_into()
+                    .map_err(|byte| bytes.error_at(offset, ClassFileError::InvalidOpcode { pc, byte }))?;
+                #[allow(unused_variables)]
+                let at = Position { pc, opcode };
+                Ok(match opcode {
+                    $(Opcodes::$opname => OpcodeInfo::$opname$({ $($fieldname: <$fieldtype as Operand>::decode(bytes, constants, at)?),+ })? ,)*
                 })
             }
+
+            /// Writes the instruction as if it was at [pc], resolving branches with [labels]
             pub fn encode_opcode_info(
-                self,
+                &self,
                 constant_pool: &mut ConstantPool,
-                writer: &mut ByteWriter
-            ) {
-                trait Encode {
-                    fn encode(self,
-                        _constant_pool: &mut ConstantPool,
-                        writer: &mut ByteWriter);
-                }
-                impl<T> Encode for T where T: Writeable {
-                    fn encode(self,
-                        _constant_pool: &mut ConstantPool,
-                        writer: &mut ByteWriter) {
-                        writer.write(self);
-                    }
-                }
+                writer: &mut ByteWriter,
+                pc: usize,
+                labels: &Labels,
+            ) -> Result<(), ClassFileError> {
+                let at = Position { pc, opcode: self.opcode() };
+                writer.write(w1::from(at.opcode));
                 match self {
                     $(OpcodeInfo::$opname $({ $($fieldname),+ })? => {
-                        let opcode: w1 = Opcodes::$opname.into();
-                        writer.write(opcode);
-                        $( $(<$fieldtype>::encode($fieldname, constant_pool, writer);)+ )?
+                        $( $(<$fieldtype as Operand>::encode($fieldname, constant_pool, writer, at, labels)?;)+ )?
                     },)*
                 }
+                Ok(())
             }
         }
     };
 }
 
+impl Opcodes {
+    fn is_wide_branch(self) -> bool {
+        matches!(self, Opcodes::goto_w | Opcodes::jsr_w)
+    }
+
+    /// The conditional branch that's taken exactly when [self] isn't
+    pub fn inverse_condition(self) -> Option<Opcodes> {
+        use Opcodes::*;
+        Some(match self {
+            ifeq => ifne,
+            ifne => ifeq,
+            iflt => ifge,
+            ifge => iflt,
+            ifgt => ifle,
+            ifle => ifgt,
+            if_icmpeq => if_icmpne,
+            if_icmpne => if_icmpeq,
+            if_icmplt => if_icmpge,
+            if_icmpge => if_icmplt,
+            if_icmpgt => if_icmple,
+            if_icmple => if_icmpgt,
+            if_acmpeq => if_acmpne,
+            if_acmpne => if_acmpeq,
+            ifnull => ifnonnull,
+            ifnonnull => ifnull,
+            _ => return None,
+        })
+    }
+}
+
+impl OpcodeInfo {
+    /// The target of a branch with a 16-bit offset, which may have to be widened
+    fn short_branch(&self) -> Option<Label> {
+        use OpcodeInfo::*;
+        match self {
+            goto { branch }
+            | jsr { branch }
+            | ifeq { branch }
+            | ifne { branch }
+            | iflt { branch }
+            | ifge { branch }
+            | ifgt { branch }
+            | ifle { branch }
+            | if_icmpeq { branch }
+            | if_icmpne { branch }
+            | if_icmplt { branch }
+            | if_icmpge { branch }
+            | if_icmpgt { branch }
+            | if_icmple { branch }
+            | if_acmpeq { branch }
+            | if_acmpne { branch }
+            | ifnull { branch }
+            | ifnonnull { branch } => Some(*branch),
+            _ => None,
+        }
+    }
+}
+
 // https://docs.oracle.com/javase/specs/jvms/se12/html/jvms-6.html#jvms-6.5
 opcodes! {
     aaload = 0x32;
@@ -349,7 +1237,7 @@ opcodes! {
     athrow = 0xbf;
     baload = 0x33;
     bastore = 0x54;
-    bipush = 0x10;
+    bipush = 0x10 {byte: i8};
     caload = 0x34;
     castore = 0x55;
     checkcast = 0xc0 {class: ClassRef}; // Constant pool index of class
@@ -414,8 +1302,8 @@ opcodes! {
     fsub = 0x66;
     getfield = 0xb4 {field: FieldRef}; // Constant pool index of fieldref
     getstatic = 0xb2 {field: FieldRef}; // Constant pool index of fieldref
-    goto = 0xa7 {branch: w2};
-    goto_w = 0xc8 {branch: w4};
+    goto = 0xa7 {branch: Label};
+    goto_w = 0xc8 {branch: Label};
     i2b = 0x91;
     i2c = 0x92;
     i2d = 0x87;
@@ -435,23 +1323,23 @@ opcodes! {
     iconst_5 = 0x8;
     idiv = 0x6c;
 
-    if_acmpeq = 0xa5;
-    if_acmpne = 0xa6;
-    if_icmpeq = 0x9f;
-    if_icmpne = 0xa0;
-    if_icmplt = 0xa1;
-    if_icmpge = 0xa2;
-    if_icmpgt = 0xa3;
-    if_icmple = 0xa4;
-    ifeq = 0x99 {branch: w2};
-    ifne = 0x9a {branch: w2};
-    iflt = 0x9b {branch: w2};
-    ifge = 0x9c {branch: w2};
-    ifgt = 0x9d {branch: w2};
-    ifle = 0x9e {branch: w2};
-    ifnonnull = 0xc7 {branch: w2};
-    ifnull = 0xc6 {branch: w2};
-    iinc = 0x84 {index: w1, constant: w1};
+    if_acmpeq = 0xa5 {branch: Label};
+    if_acmpne = 0xa6 {branch: Label};
+    if_icmpeq = 0x9f {branch: Label};
+    if_icmpne = 0xa0 {branch: Label};
+    if_icmplt = 0xa1 {branch: Label};
+    if_icmpge = 0xa2 {branch: Label};
+    if_icmpgt = 0xa3 {branch: Label};
+    if_icmple = 0xa4 {branch: Label};
+    ifeq = 0x99 {branch: Label};
+    ifne = 0x9a {branch: Label};
+    iflt = 0x9b {branch: Label};
+    ifge = 0x9c {branch: Label};
+    ifgt = 0x9d {branch: Label};
+    ifle = 0x9e {branch: Label};
+    ifnonnull = 0xc7 {branch: Label};
+    ifnull = 0xc6 {branch: Label};
+    iinc = 0x84 {index: w1, constant: i8};
     iload = 0x15 {index: w1};
     iload_0 = 0x1a;
     iload_1 = 0x1b;
@@ -460,8 +1348,8 @@ opcodes! {
     imul = 0x68;
     ineg = 0x74;
     instanceof = 0xc1 {class: ClassRef}; // Constant pool index of class
-    invokedynamic = 0xba {index: w2, _zero: w2}; // Constant pool index of ?; zero
-    invokeinterface = 0xb9 {method: InterfaceMethodRef, count: w1, _zero: w1}; // Constant pool index of interface method ref; nargs; zero
+    invokedynamic = 0xba {call_site: CallSite};
+    invokeinterface = 0xb9 {method: InterfaceMethodRef}; // Constant pool index of interface method ref, then nargs and a zero byte
     invokespecial = 0xb7 {method: MethodRef}; // Constant pool index of method ref
     invokestatic = 0xb8 {method: MethodRef}; // Constant pool index of method ref
     invokevirtual = 0xb6 {method: MethodRef}; // Constant pool index of method ref
@@ -478,8 +1366,8 @@ opcodes! {
     isub = 0x64;
     iushr = 0x7c;
     ixor = 0x82;
-    jsr = 0xa8 {branch: w2};
-    jsr_w = 0xc9 {branch: w4};
+    jsr = 0xa8 {branch: Label};
+    jsr_w = 0xc9 {branch: Label};
     l2d = 0x8a;
     l2f = 0x89;
     l2i = 0x88;
@@ -491,9 +1379,9 @@ opcodes! {
     lconst_0 = 0x9;
     lconst_1 = 0xa;
 
-    ldc = 0x12 {index: w1}; // Constant pool index of constant
-    ldc_w = 0x13 {index: w2}; // Constant pool index of constant
-    ldc2_w = 0x14 {index: w2}; // Constant pool index of constant
+    ldc = 0x12 {constant: Loadable}; // Widened to ldc_w when its index doesn't fit a byte
+    ldc_w = 0x13 {constant: Loadable};
+    ldc2_w = 0x14 {constant: Loadable};
 
     ldiv = 0x6d;
     lload = 0x16 {index: w1};
@@ -504,7 +1392,7 @@ opcodes! {
     lmul = 0x69;
     lneg = 0x75;
 
-    // TODO lookupswitch = 0xab
+    lookupswitch = 0xab {table: LookupSwitch};
     lor = 0x81;
     lrem = 0x71;
     lreturn = 0xad;
@@ -524,22 +1412,21 @@ opcodes! {
 
     multianewarray = 0xc5 {class: ClassRef, dimensions: w1};
     new = 0xbb {class: ClassRef}; // Constant pool index of class
-    // (https://docs.oracle.com/javase/specs/jvms/se12/html/jvms-6.html#jvms-6.5.newarray)
-    newarray = 0xbc {atype: w1}; // TODO: create enum
+    newarray = 0xbc {atype: ArrayType};
     nop = 0x00;
     pop = 0x57;
     pop2 = 0x58;
     putfield = 0xb5 {field: FieldRef}; // Constant pool index of field ref
     putstatic = 0xb3 {field: FieldRef}; // Constant pool index of field ref
 
-    ret = 0xa9;
+    ret = 0xa9 {index: w1};
     r#return = 0xb1;
 
     saload = 0x35;
     sastore = 0x56;
-    sipush = 0x11 {short: w2};
+    sipush = 0x11 {short: i16};
     swap = 0x5f;
 
-    // TODO tableswitch = 0xaa;
-    // TODO wide = 0xc4;
+    tableswitch = 0xaa {table: TableSwitch};
+    wide = 0xc4 {instruction: WideInstruction};
 }
@@ -1,12 +1,54 @@
 use serde::{Deserialize, Serialize};
 
-use crate::{w4, w8};
+use crate::constant_pool::CpTag;
+use crate::model::descriptor::{BaseType, FieldType};
+use crate::mutf8::JavaString;
 
-#[derive(Debug, Serialize, Deserialize)]
+/// The value of a constant field, typed by the constant pool entry holding it
+/// (https://docs.oracle.com/javase/specs/jvms/se12/html/jvms-4.html#jvms-4.7.2)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum ConstantValue {
-    Integer(w4),
-    Long(w8),
+    /// Also holds `boolean`, `byte`, `char` and `short` values
+    Integer(i32),
+    Long(i64),
     Float(f32),
     Double(f64),
-    String(String),
+    String(JavaString),
+}
+
+impl ConstantValue {
+    /// Whether a field of [field_type] can hold the value
+    ///
+    ///```rust
+    /// use rusty_javap::model::attrs::constant_value::ConstantValue;
+    /// let field_type = |descriptor: &str| descriptor.parse().unwrap();
+    /// assert!(ConstantValue::Integer(1).fits(&field_type("Z")));
+    /// assert!(ConstantValue::String("1".into()).fits(&field_type("Ljava/lang/String;")));
+    /// assert!(!ConstantValue::Integer(1).fits(&field_type("J")));
+    /// assert!(!ConstantValue::String("1".into()).fits(&field_type("Ljava/lang/Object;")));
+    ///```
+    pub fn fits(&self, field_type: &FieldType) -> bool {
+        use BaseType::*;
+        match (self, field_type) {
+            (ConstantValue::Integer(_), FieldType::Base(Int | Short | Char | Byte | Boolean)) => {
+                true
+            }
+            (ConstantValue::Long(_), FieldType::Base(Long)) => true,
+            (ConstantValue::Float(_), FieldType::Base(Float)) => true,
+            (ConstantValue::Double(_), FieldType::Base(Double)) => true,
+            (ConstantValue::String(_), FieldType::Object(class)) => class == "java/lang/String",
+            _ => false,
+        }
+    }
+
+    /// The kind of constant pool entry holding the value
+    pub fn tag(&self) -> CpTag {
+        match self {
+            ConstantValue::Integer(_) => CpTag::Integer,
+            ConstantValue::Long(_) => CpTag::Long,
+            ConstantValue::Float(_) => CpTag::Float,
+            ConstantValue::Double(_) => CpTag::Double,
+            ConstantValue::String(_) => CpTag::String,
+        }
+    }
 }
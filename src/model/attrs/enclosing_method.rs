@@ -0,0 +1,51 @@
+use crate::bytecode::reader::ByteReader;
+use crate::bytecode::writer::ByteWriter;
+use crate::constant_pool::ConstantPool;
+use crate::error::{ClassFileError, ParseError};
+use crate::w2;
+use serde::{Deserialize, Serialize};
+
+// https://docs.oracle.com/javase/specs/jvms/se12/html/jvms-4.html#jvms-4.7.7
+/// Where a local or anonymous class is declared
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EnclosingMethod {
+    pub class: String,
+    /// `None` if the class isn't declared in a method or constructor, e.g. in a field initializer
+    pub method: Option<NameAndType>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NameAndType {
+    pub name: String,
+    pub descriptor: String,
+}
+
+pub fn parse_enclosing_method(
+    bytes: &mut ByteReader,
+    constant_pool: &ConstantPool,
+) -> Result<EnclosingMethod, ParseError> {
+    let class = bytes.take_constant(|index| constant_pool.get_class_name(index))?;
+    let method = bytes.take_constant(|index| {
+        if index == 0 {
+            return Ok(None);
+        }
+        let (name, descriptor) = constant_pool.get_name_and_type(index)?;
+        Ok(Some(NameAndType { name, descriptor }))
+    })?;
+    Ok(EnclosingMethod { class, method })
+}
+
+pub fn write_enclosing_method(
+    enclosing_method: EnclosingMethod,
+    constant_pool: &mut ConstantPool,
+) -> Result<Vec<u8>, ClassFileError> {
+    let mut writer = ByteWriter::new();
+    writer.write(constant_pool.push_class(enclosing_method.class)?);
+    let method_index: w2 = enclosing_method
+        .method
+        .map_or(Ok(0), |NameAndType { name, descriptor }| {
+            constant_pool.push_name_and_type(name, descriptor)
+        })?;
+    writer.write(method_index);
+    Ok(writer.into())
+}
@@ -0,0 +1,116 @@
+use crate::bytecode::access::impl_rw_for_modifiers;
+use crate::bytecode::reader::{ByteReader, Take};
+use crate::bytecode::writer::{ByteWriter, Writeable};
+use crate::constant_pool::ConstantPool;
+use crate::error::{ClassFileError, ParseError};
+use crate::w2;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::iter::FromIterator;
+
+// https://docs.oracle.com/javase/specs/jvms/se12/html/jvms-4.html#jvms-4.7.6
+pub type InnerClasses = Vec<InnerClass>;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InnerClass {
+    pub inner_class: String,
+    /// The class [inner_class] is a member of, `None` for local and anonymous classes
+    pub outer_class: Option<String>,
+    /// The simple name in the source, `None` for anonymous classes
+    pub inner_name: Option<String>,
+    /// The access flags in the source, which the class file's own flags don't fully keep
+    pub inner_class_access_flags: Vec<InnerClassAccessFlags>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub enum InnerClassAccessFlags {
+    PUBLIC = 0x0001,
+    PRIVATE = 0x0002,
+    PROTECTED = 0x0004,
+    STATIC = 0x0008,
+    FINAL = 0x0010,
+    INTERFACE = 0x0200,
+    ABSTRACT = 0x0400,
+    SYNTHETIC = 0x1000,
+    ANNOTATION = 0x2000,
+    ENUM = 0x4000,
+}
+
+impl InnerClassAccessFlags {
+    pub fn variants() -> Vec<Self> {
+        use InnerClassAccessFlags::*;
+        vec![
+            PUBLIC, PRIVATE, PROTECTED, STATIC, FINAL, INTERFACE, ABSTRACT, SYNTHETIC, ANNOTATION,
+            ENUM,
+        ]
+    }
+}
+
+impl Display for InnerClassAccessFlags {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl_rw_for_modifiers!(InnerClassAccessFlags);
+
+pub fn parse_inner_classes(
+    bytes: &mut ByteReader,
+    constant_pool: &ConstantPool,
+) -> Result<InnerClasses, ParseError> {
+    let number_of_classes: w2 = bytes.take()?;
+    let mut inner_classes = Vec::with_capacity(number_of_classes.into());
+    for i in 0..number_of_classes {
+        bytes.context(format!("classes[{}]", i), |bytes| {
+            let inner_class = bytes.take_constant(|index| constant_pool.get_class_name(index))?;
+            let outer_class = bytes.take_constant(|index| {
+                if index == 0 {
+                    Ok(None)
+                } else {
+                    constant_pool.get_class_name(index).map(Some)
+                }
+            })?;
+            let inner_name = bytes.take_constant(|index| {
+                if index == 0 {
+                    Ok(None)
+                } else {
+                    constant_pool.get_utf8(index).map(Some)
+                }
+            })?;
+            let inner_class_access_flags = bytes.take()?;
+            inner_classes.push(InnerClass {
+                inner_class,
+                outer_class,
+                inner_name,
+                inner_class_access_flags,
+            });
+            Ok(())
+        })?;
+    }
+    Ok(inner_classes)
+}
+
+pub fn write_inner_classes(
+    inner_classes: InnerClasses,
+    constant_pool: &mut ConstantPool,
+) -> Result<Vec<u8>, ClassFileError> {
+    let mut writer = ByteWriter::new();
+    writer.write(inner_classes.len() as w2);
+    for InnerClass {
+        inner_class,
+        outer_class,
+        inner_name,
+        inner_class_access_flags,
+    } in inner_classes
+    {
+        writer.write(constant_pool.push_class(inner_class)?);
+        let outer_class_index: w2 =
+            outer_class.map_or(Ok(0), |class| constant_pool.push_class(class))?;
+        writer.write(outer_class_index);
+        let inner_name_index: w2 =
+            inner_name.map_or(Ok(0), |name| constant_pool.push_utf8(name))?;
+        writer.write(inner_name_index);
+        writer.write(inner_class_access_flags);
+    }
+    Ok(writer.into())
+}
@@ -1,18 +1,20 @@
 use crate::bytecode::reader::{ByteReader, Take};
-use crate::bytecode::writer::{ByteWriter, Writeable};
-use crate::w2;
+use crate::bytecode::writer::ByteWriter;
+use crate::error::{ClassFileError, ParseError};
+use crate::model::attrs::code::{Label, Labels};
+use crate::{w2, w4};
 use serde::{Deserialize, Serialize};
 
 pub type LineNumberTable = Vec<LineNumberTableElement>;
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct LineNumberTableElement {
-    pub start_pc: w2,
+    pub start: Label,
     pub line_number: w2,
 }
 
 impl Take<LineNumberTable> for ByteReader {
-    fn take(self: &mut ByteReader) -> Result<LineNumberTable, String> {
+    fn take(self: &mut ByteReader) -> Result<LineNumberTable, ParseError> {
         let line_number_table_length: w2 = self.take()?;
         let mut line_number_table: Vec<LineNumberTableElement> =
             Vec::with_capacity(line_number_table_length.into());
@@ -20,7 +22,7 @@ impl Take<LineNumberTable> for ByteReader {
             let start_pc: w2 = self.take()?;
             let line_number: w2 = self.take()?;
             line_number_table.push(LineNumberTableElement {
-                start_pc,
+                start: Label(start_pc as w4),
                 line_number,
             });
         }
@@ -28,16 +30,15 @@ impl Take<LineNumberTable> for ByteReader {
     }
 }
 
-impl Writeable for LineNumberTable {
-    fn write(self, writer: &mut ByteWriter) {
-        writer.write(self.len() as w2);
-        for LineNumberTableElement {
-            start_pc,
-            line_number,
-        } in self
-        {
-            writer.write(start_pc);
-            writer.write(line_number);
-        }
+pub fn write_line_number_table(
+    line_number_table: LineNumberTable,
+    labels: &Labels,
+) -> Result<Vec<u8>, ClassFileError> {
+    let mut writer = ByteWriter::new();
+    writer.write(line_number_table.len() as w2);
+    for LineNumberTableElement { start, line_number } in line_number_table {
+        writer.write(labels.pc_w2(start)?);
+        writer.write(line_number);
     }
+    Ok(writer.into())
 }
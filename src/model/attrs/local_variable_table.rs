@@ -1,42 +1,47 @@
 use crate::bytecode::reader::{ByteReader, Take};
 use crate::bytecode::writer::ByteWriter;
-use crate::constant_pool::{Constant, ConstantPool, CpInfo, CpTag};
-use crate::w2;
+use crate::constant_pool::ConstantPool;
+use crate::error::{ClassFileError, ParseError};
+use crate::model::attrs::code::{Label, Labels};
+use crate::model::descriptor::FieldType;
+use crate::{w2, w4};
 use serde::{Deserialize, Serialize};
 
 pub type LocalVariableTable = Vec<LocalVariableTableElement>;
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct LocalVariableTableElement {
-    pub start_pc: w2,
-    pub length: w2,
+    pub start: Label,
+    /// End of the variable's scope (exclusive)
+    pub end: Label,
     pub name: String,
-    pub descriptor: String,
+    pub descriptor: FieldType,
     pub index: w2,
 }
 
 pub fn parse_local_variable_table(
     bytes: &mut ByteReader,
     constant_pool: &ConstantPool,
-) -> Result<LocalVariableTable, String> {
+) -> Result<LocalVariableTable, ParseError> {
     let local_variable_table_length: w2 = bytes.take()?;
     let mut local_variable_table: Vec<LocalVariableTableElement> =
         Vec::with_capacity(local_variable_table_length.into());
-    for _ in 0..local_variable_table_length {
-        let start_pc: w2 = bytes.take()?;
-        let length: w2 = bytes.take()?;
-        let name_index: w2 = bytes.take()?;
-        let name = constant_pool.get_utf8(name_index)?;
-        let descriptor_index: w2 = bytes.take()?;
-        let descriptor = constant_pool.get_utf8(descriptor_index)?;
-        let index: w2 = bytes.take()?;
-        local_variable_table.push(LocalVariableTableElement {
-            start_pc,
-            length,
-            name,
-            descriptor,
-            index,
-        });
+    for i in 0..local_variable_table_length {
+        bytes.context(format!("local_variable_table[{}]", i), |bytes| {
+            let start_pc: w2 = bytes.take()?;
+            let length: w2 = bytes.take()?;
+            let name = bytes.take_constant(|index| constant_pool.get_utf8(index))?;
+            let descriptor = bytes.take_constant(|index| constant_pool.get_field_type(index))?;
+            let index: w2 = bytes.take()?;
+            local_variable_table.push(LocalVariableTableElement {
+                start: Label(start_pc as w4),
+                end: Label(start_pc as w4 + length as w4),
+                name,
+                descriptor,
+                index,
+            });
+            Ok(())
+        })?;
     }
     Ok(local_variable_table)
 }
@@ -44,26 +49,27 @@ pub fn parse_local_variable_table(
 pub fn write_local_variable_table(
     line_number_table: Vec<LocalVariableTableElement>,
     constant_pool: &mut ConstantPool,
-) -> Vec<u8> {
+    labels: &Labels,
+) -> Result<Vec<u8>, ClassFileError> {
     let mut writer = ByteWriter::new();
     writer.write(line_number_table.len() as w2);
     for LocalVariableTableElement {
-        start_pc,
-        length,
+        start,
+        end,
         name,
         descriptor,
         index,
     } in line_number_table
     {
+        let start_pc = labels.pc_w2(start)?;
         writer.write(start_pc);
-        writer.write(length);
-        let name_index = constant_pool.push(Constant(CpTag::Utf8, CpInfo::Utf8 { string: name }));
+        writer.write(labels.pc_w2(end)?.saturating_sub(start_pc));
+        let name_index = constant_pool.push_utf8(name)?;
         writer.write(name_index);
-        let descriptor_index =
-            constant_pool.push(Constant(CpTag::Utf8, CpInfo::Utf8 { string: descriptor }));
+        let descriptor_index = constant_pool.push_utf8(descriptor.encode())?;
         writer.write(descriptor_index);
         writer.write(index);
     }
 
-    writer.into()
+    Ok(writer.into())
 }
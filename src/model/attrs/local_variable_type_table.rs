@@ -0,0 +1,76 @@
+use crate::bytecode::reader::{ByteReader, Take};
+use crate::bytecode::writer::ByteWriter;
+use crate::constant_pool::ConstantPool;
+use crate::error::{ClassFileError, ParseError};
+use crate::model::attrs::code::{Label, Labels};
+use crate::{w2, w4};
+use serde::{Deserialize, Serialize};
+
+// https://docs.oracle.com/javase/specs/jvms/se12/html/jvms-4.html#jvms-4.7.14
+/// The generic types of the local variables whose types use type variables or
+/// parameterized types, alongside their erased entries in the `LocalVariableTable`
+pub type LocalVariableTypeTable = Vec<LocalVariableTypeTableElement>;
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct LocalVariableTypeTableElement {
+    pub start: Label,
+    /// End of the variable's scope (exclusive)
+    pub end: Label,
+    pub name: String,
+    /// Field type signature, e.g. `Ljava/util/List<TT;>;`
+    pub signature: String,
+    pub index: w2,
+}
+
+pub fn parse_local_variable_type_table(
+    bytes: &mut ByteReader,
+    constant_pool: &ConstantPool,
+) -> Result<LocalVariableTypeTable, ParseError> {
+    let local_variable_type_table_length: w2 = bytes.take()?;
+    let mut local_variable_type_table: Vec<LocalVariableTypeTableElement> =
+        Vec::with_capacity(local_variable_type_table_length.into());
+    for i in 0..local_variable_type_table_length {
+        bytes.context(format!("local_variable_type_table[{}]", i), |bytes| {
+            let start_pc: w2 = bytes.take()?;
+            let length: w2 = bytes.take()?;
+            let name = bytes.take_constant(|index| constant_pool.get_utf8(index))?;
+            let signature = bytes.take_constant(|index| constant_pool.get_utf8(index))?;
+            let index: w2 = bytes.take()?;
+            local_variable_type_table.push(LocalVariableTypeTableElement {
+                start: Label(start_pc as w4),
+                end: Label(start_pc as w4 + length as w4),
+                name,
+                signature,
+                index,
+            });
+            Ok(())
+        })?;
+    }
+    Ok(local_variable_type_table)
+}
+
+pub fn write_local_variable_type_table(
+    local_variable_type_table: LocalVariableTypeTable,
+    constant_pool: &mut ConstantPool,
+    labels: &Labels,
+) -> Result<Vec<u8>, ClassFileError> {
+    let mut writer = ByteWriter::new();
+    writer.write(local_variable_type_table.len() as w2);
+    for LocalVariableTypeTableElement {
+        start,
+        end,
+        name,
+        signature,
+        index,
+    } in local_variable_type_table
+    {
+        let start_pc = labels.pc_w2(start)?;
+        writer.write(start_pc);
+        writer.write(labels.pc_w2(end)?.saturating_sub(start_pc));
+        writer.write(constant_pool.push_utf8(name)?);
+        writer.write(constant_pool.push_utf8(signature)?);
+        writer.write(index);
+    }
+
+    Ok(writer.into())
+}
@@ -1,47 +1,78 @@
+pub mod annotations;
 pub mod code;
 pub mod constant_value;
+pub mod enclosing_method;
+pub mod inner_classes;
 pub mod line_number_table;
 pub mod local_variable_table;
+pub mod local_variable_type_table;
 pub mod method_parameters;
+pub mod module;
+pub mod record;
+pub mod source_debug_extension;
+pub mod stack_map_table;
+pub mod type_annotations;
 
+use crate::model::attrs::annotations::{Annotations, ElementValue, ParameterAnnotations};
 use crate::model::attrs::code::Code;
 use crate::model::attrs::constant_value::ConstantValue;
+use crate::model::attrs::enclosing_method::EnclosingMethod;
+use crate::model::attrs::inner_classes::InnerClasses;
 use crate::model::attrs::line_number_table::LineNumberTable;
 use crate::model::attrs::local_variable_table::LocalVariableTable;
+use crate::model::attrs::local_variable_type_table::LocalVariableTypeTable;
 use crate::model::attrs::method_parameters::MethodParameters;
-use crate::w2;
+use crate::model::attrs::module::Module;
+use crate::model::attrs::record::Record;
+use crate::model::attrs::source_debug_extension::SourceDebugExtension;
+use crate::model::attrs::stack_map_table::StackMapTable;
+use crate::model::attrs::type_annotations::TypeAnnotations;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub enum Attribute {
     ConstantValue(ConstantValue),
     Code(Code),
-    // Exceptions,
+    /// The checked exceptions a method declares it throws
+    Exceptions(Vec<String>),
     SourceFile(String),
     LineNumberTable(LineNumberTable),
     LocalVariableTable(LocalVariableTable),
-    // InnerClasses,
+    InnerClasses(InnerClasses),
     Synthetic,
     Deprecated,
-    // EnclosingMethod,
-    Signature {
-        signature_index: w2,
-    },
-    // SourceDebugExtension,
-    // LocalVariableTypeTable,
-    // RuntimeVisibleAnnotations { num_annotations: w2}, // TODO: needs annotations
-    // RuntimeInvisibleAnnotations { num_annotations: w2}, // TODO: needs annotations
-    // StackMapTable { entries: Vec<StackMapFrame>}, } // TODO
-    // BootstrapMethods,
-    // AnnotationDefault,
-    // RuntimeVisibleTypeAnnotations { num_annotations: w2}, // TODO: needs annotations
-    // RuntimeInvisibleTypeAnnotations { num_annotations: w2}, // TODO: needs annotations
+    EnclosingMethod(EnclosingMethod),
+    /// The generic signature of a class, method, field or record component, which
+    /// [signature](crate::model::signature) parses according to which of them it belongs to
+    Signature(String),
+    SourceDebugExtension(SourceDebugExtension),
+    LocalVariableTypeTable(LocalVariableTypeTable),
+    RuntimeVisibleAnnotations(Annotations),
+    RuntimeInvisibleAnnotations(Annotations),
+    RuntimeVisibleParameterAnnotations(ParameterAnnotations),
+    RuntimeInvisibleParameterAnnotations(ParameterAnnotations),
+    StackMapTable(StackMapTable),
+    /// Where the class' `BootstrapMethods` table goes; the table itself is kept in the
+    /// constant pool, where it's rebuilt from the call sites and `Dynamic` constants
+    BootstrapMethods,
+    /// The default value of the annotation interface element a method stands for
+    AnnotationDefault(ElementValue),
+    RuntimeVisibleTypeAnnotations(TypeAnnotations),
+    RuntimeInvisibleTypeAnnotations(TypeAnnotations),
     MethodParameters(MethodParameters),
-    // Module,
-    // ModulePackages,
-    // ModuleMainClass,
-    // NestHost,
-    // NestMembers,
+    Module(Module),
+    /// All the packages of the module, in internal form
+    ModulePackages(Vec<String>),
+    /// The class with the module's `main` method
+    ModuleMainClass(String),
+    /// The class whose nest this class is a member of
+    NestHost(String),
+    /// The classes that are members of the nest this class hosts
+    NestMembers(Vec<String>),
+    /// The components of a record class
+    Record(Record),
+    /// The classes that can directly extend or implement this sealed class
+    PermittedSubclasses(Vec<String>),
     #[allow(non_camel_case_types)]
     UNIMPLEMENTED_ATTRIBUTE_TODO {
         name: String,
@@ -0,0 +1,375 @@
+use crate::bytecode::access::impl_rw_for_modifiers;
+use crate::bytecode::reader::{ByteReader, Take};
+use crate::bytecode::writer::{ByteWriter, Writeable};
+use crate::constant_pool::ConstantPool;
+use crate::error::{ClassFileError, ParseError};
+use crate::w2;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::iter::FromIterator;
+
+// https://docs.oracle.com/javase/specs/jvms/se12/html/jvms-4.html#jvms-4.7.25
+/// The module a `module-info` class declares
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Module {
+    pub name: String,
+    pub module_flags: Vec<ModuleFlags>,
+    pub version: Option<String>,
+    pub requires: Vec<Requires>,
+    pub exports: Vec<Exports>,
+    pub opens: Vec<Opens>,
+    /// The service interfaces the module may look up with `ServiceLoader`
+    pub uses: Vec<String>,
+    pub provides: Vec<Provides>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Requires {
+    pub module: String,
+    pub requires_flags: Vec<RequiresFlags>,
+    /// The version of [module] this module was compiled against
+    pub version: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Exports {
+    pub package: String,
+    pub exports_flags: Vec<ExportsFlags>,
+    /// The modules the package is exported to, empty if it's exported to all of them
+    pub exports_to: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Opens {
+    pub package: String,
+    pub opens_flags: Vec<OpensFlags>,
+    /// The modules the package is opened to, empty if it's opened to all of them
+    pub opens_to: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Provides {
+    /// The service interface
+    pub service: String,
+    /// The classes implementing [service]
+    pub provides_with: Vec<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub enum ModuleFlags {
+    OPEN = 0x0020,
+    SYNTHETIC = 0x1000,
+    MANDATED = 0x8000,
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub enum RequiresFlags {
+    TRANSITIVE = 0x0020,
+    STATIC_PHASE = 0x0040,
+    SYNTHETIC = 0x1000,
+    MANDATED = 0x8000,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub enum ExportsFlags {
+    SYNTHETIC = 0x1000,
+    MANDATED = 0x8000,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub enum OpensFlags {
+    SYNTHETIC = 0x1000,
+    MANDATED = 0x8000,
+}
+
+impl ModuleFlags {
+    pub fn variants() -> Vec<Self> {
+        use ModuleFlags::*;
+        vec![OPEN, SYNTHETIC, MANDATED]
+    }
+}
+
+impl RequiresFlags {
+    pub fn variants() -> Vec<Self> {
+        use RequiresFlags::*;
+        vec![TRANSITIVE, STATIC_PHASE, SYNTHETIC, MANDATED]
+    }
+}
+
+impl ExportsFlags {
+    pub fn variants() -> Vec<Self> {
+        use ExportsFlags::*;
+        vec![SYNTHETIC, MANDATED]
+    }
+}
+
+impl OpensFlags {
+    pub fn variants() -> Vec<Self> {
+        use OpensFlags::*;
+        vec![SYNTHETIC, MANDATED]
+    }
+}
+
+impl Display for ModuleFlags {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl Display for RequiresFlags {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl Display for ExportsFlags {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl Display for OpensFlags {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl_rw_for_modifiers!(ModuleFlags);
+impl_rw_for_modifiers!(RequiresFlags);
+impl_rw_for_modifiers!(ExportsFlags);
+impl_rw_for_modifiers!(OpensFlags);
+
+/// A count followed by that many constants, each resolved with [get]
+fn take_list(
+    bytes: &mut ByteReader,
+    segment: &str,
+    get: impl Fn(w2) -> Result<String, ClassFileError>,
+) -> Result<Vec<String>, ParseError> {
+    let count: w2 = bytes.take()?;
+    let mut list = Vec::with_capacity(count.into());
+    for i in 0..count {
+        bytes.context(format!("{}[{}]", segment, i), |bytes| {
+            list.push(bytes.take_constant(&get)?);
+            Ok(())
+        })?;
+    }
+    Ok(list)
+}
+
+fn take_version(
+    bytes: &mut ByteReader,
+    constant_pool: &ConstantPool,
+) -> Result<Option<String>, ParseError> {
+    bytes.take_constant(|index| {
+        if index == 0 {
+            Ok(None)
+        } else {
+            constant_pool.get_utf8(index).map(Some)
+        }
+    })
+}
+
+pub fn parse_module(
+    bytes: &mut ByteReader,
+    constant_pool: &ConstantPool,
+) -> Result<Module, ParseError> {
+    let name = bytes.take_constant(|index| constant_pool.get_module_name(index))?;
+    let module_flags = bytes.take()?;
+    let version = take_version(bytes, constant_pool)?;
+
+    let requires_count: w2 = bytes.take()?;
+    let mut requires = Vec::with_capacity(requires_count.into());
+    for i in 0..requires_count {
+        bytes.context(format!("requires[{}]", i), |bytes| {
+            let module = bytes.take_constant(|index| constant_pool.get_module_name(index))?;
+            let requires_flags = bytes.take()?;
+            let version = take_version(bytes, constant_pool)?;
+            requires.push(Requires {
+                module,
+                requires_flags,
+                version,
+            });
+            Ok(())
+        })?;
+    }
+
+    let exports_count: w2 = bytes.take()?;
+    let mut exports = Vec::with_capacity(exports_count.into());
+    for i in 0..exports_count {
+        bytes.context(format!("exports[{}]", i), |bytes| {
+            let package = bytes.take_constant(|index| constant_pool.get_package_name(index))?;
+            let exports_flags = bytes.take()?;
+            let exports_to = take_list(bytes, "exports_to", |index| {
+                constant_pool.get_module_name(index)
+            })?;
+            exports.push(Exports {
+                package,
+                exports_flags,
+                exports_to,
+            });
+            Ok(())
+        })?;
+    }
+
+    let opens_count: w2 = bytes.take()?;
+    let mut opens = Vec::with_capacity(opens_count.into());
+    for i in 0..opens_count {
+        bytes.context(format!("opens[{}]", i), |bytes| {
+            let package = bytes.take_constant(|index| constant_pool.get_package_name(index))?;
+            let opens_flags = bytes.take()?;
+            let opens_to = take_list(bytes, "opens_to", |index| {
+                constant_pool.get_module_name(index)
+            })?;
+            opens.push(Opens {
+                package,
+                opens_flags,
+                opens_to,
+            });
+            Ok(())
+        })?;
+    }
+
+    let uses = take_list(bytes, "uses", |index| constant_pool.get_class_name(index))?;
+
+    let provides_count: w2 = bytes.take()?;
+    let mut provides = Vec::with_capacity(provides_count.into());
+    for i in 0..provides_count {
+        bytes.context(format!("provides[{}]", i), |bytes| {
+            let service = bytes.take_constant(|index| constant_pool.get_class_name(index))?;
+            let provides_with = take_list(bytes, "provides_with", |index| {
+                constant_pool.get_class_name(index)
+            })?;
+            provides.push(Provides {
+                service,
+                provides_with,
+            });
+            Ok(())
+        })?;
+    }
+
+    Ok(Module {
+        name,
+        module_flags,
+        version,
+        requires,
+        exports,
+        opens,
+        uses,
+        provides,
+    })
+}
+
+fn write_version(
+    version: Option<String>,
+    constant_pool: &mut ConstantPool,
+) -> Result<w2, ClassFileError> {
+    version.map_or(Ok(0), |version| constant_pool.push_utf8(version))
+}
+
+fn write_list(indices: Vec<w2>, writer: &mut ByteWriter) {
+    writer.write(indices.len() as w2);
+    for index in indices {
+        writer.write(index);
+    }
+}
+
+pub fn write_module(
+    module: Module,
+    constant_pool: &mut ConstantPool,
+) -> Result<Vec<u8>, ClassFileError> {
+    let mut writer = ByteWriter::new();
+    writer.write(constant_pool.push_module(module.name)?);
+    writer.write(module.module_flags);
+    writer.write(write_version(module.version, constant_pool)?);
+
+    writer.write(module.requires.len() as w2);
+    for Requires {
+        module,
+        requires_flags,
+        version,
+    } in module.requires
+    {
+        writer.write(constant_pool.push_module(module)?);
+        writer.write(requires_flags);
+        writer.write(write_version(version, constant_pool)?);
+    }
+
+    writer.write(module.exports.len() as w2);
+    for Exports {
+        package,
+        exports_flags,
+        exports_to,
+    } in module.exports
+    {
+        writer.write(constant_pool.push_package(package)?);
+        writer.write(exports_flags);
+        let exports_to = exports_to
+            .into_iter()
+            .map(|module| constant_pool.push_module(module))
+            .collect::<Result<_, _>>()?;
+        write_list(exports_to, &mut writer);
+    }
+
+    writer.write(module.opens.len() as w2);
+    for Opens {
+        package,
+        opens_flags,
+        opens_to,
+    } in module.opens
+    {
+        writer.write(constant_pool.push_package(package)?);
+        writer.write(opens_flags);
+        let opens_to = opens_to
+            .into_iter()
+            .map(|module| constant_pool.push_module(module))
+            .collect::<Result<_, _>>()?;
+        write_list(opens_to, &mut writer);
+    }
+
+    let uses = module
+        .uses
+        .into_iter()
+        .map(|class| constant_pool.push_class(class))
+        .collect::<Result<_, _>>()?;
+    write_list(uses, &mut writer);
+
+    writer.write(module.provides.len() as w2);
+    for Provides {
+        service,
+        provides_with,
+    } in module.provides
+    {
+        writer.write(constant_pool.push_class(service)?);
+        let provides_with = provides_with
+            .into_iter()
+            .map(|class| constant_pool.push_class(class))
+            .collect::<Result<_, _>>()?;
+        write_list(provides_with, &mut writer);
+    }
+
+    Ok(writer.into())
+}
+
+/// The packages of a module, including those it doesn't export or open
+pub fn parse_module_packages(
+    bytes: &mut ByteReader,
+    constant_pool: &ConstantPool,
+) -> Result<Vec<String>, ParseError> {
+    take_list(bytes, "packages", |index| {
+        constant_pool.get_package_name(index)
+    })
+}
+
+pub fn write_module_packages(
+    packages: Vec<String>,
+    constant_pool: &mut ConstantPool,
+) -> Result<Vec<u8>, ClassFileError> {
+    let mut writer = ByteWriter::new();
+    let packages = packages
+        .into_iter()
+        .map(|package| constant_pool.push_package(package))
+        .collect::<Result<_, _>>()?;
+    write_list(packages, &mut writer);
+    Ok(writer.into())
+}
@@ -0,0 +1,14 @@
+use crate::model::attrs::Attribute;
+use crate::model::descriptor::FieldType;
+use serde::{Deserialize, Serialize};
+
+// https://docs.oracle.com/javase/specs/jvms/se16/html/jvms-4.html#jvms-4.7.30
+pub type Record = Vec<RecordComponent>;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecordComponent {
+    pub name: String,
+    pub descriptor: FieldType,
+    /// Such as the component's `Signature`, annotations and type annotations
+    pub attributes: Vec<Attribute>,
+}
@@ -0,0 +1,252 @@
+use crate::bytecode::reader::ByteReader;
+use crate::mutf8::{decode, encode, JavaString};
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+
+// https://docs.oracle.com/javase/specs/jvms/se12/html/jvms-4.html#jvms-4.7.11
+/// Extra debugging information, which the JVM itself ignores. Compilers of languages that
+/// compile to Java or to bytecode (Kotlin, JSP, ...) put a JSR-45 source map there.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum SourceDebugExtension {
+    SourceMap(SourceMap),
+    /// Contents that aren't a source map this crate understands, kept as they are
+    Other(Vec<u8>),
+}
+
+/// A JSR-45 "SMAP", mapping the lines of the class' output file (which its
+/// `LineNumberTable` refers to) back to the lines of the sources in each stratum
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SourceMap {
+    /// The name of the file the line numbers of the class refer to, e.g. `Foo_jsp.java`
+    pub output_file: String,
+    /// The stratum a debugger shows unless asked for another one
+    pub default_stratum: String,
+    pub strata: Vec<Stratum>,
+    /// The text this was parsed from, written back as it was unless the map was edited
+    #[serde(skip)]
+    text: Option<String>,
+}
+
+/// Maps are equal when they map the same lines, however their text was laid out
+impl PartialEq for SourceMap {
+    fn eq(&self, other: &Self) -> bool {
+        self.output_file == other.output_file
+            && self.default_stratum == other.default_stratum
+            && self.strata == other.strata
+    }
+}
+
+/// One view of the sources, e.g. `Kotlin` or `JSP`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Stratum {
+    pub name: String,
+    pub files: Vec<FileInfo>,
+    pub lines: Vec<LineInfo>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileInfo {
+    pub id: u32,
+    pub name: String,
+    /// The path of the file relative to a source directory, if given
+    pub path: Option<String>,
+}
+
+/// Maps [repeat_count] lines of file [file_id], from [input_start_line] on, to
+/// [output_line_increment] lines each of the output file, from [output_start_line] on
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LineInfo {
+    pub input_start_line: u32,
+    pub file_id: u32,
+    pub repeat_count: u32,
+    pub output_start_line: u32,
+    pub output_line_increment: u32,
+}
+
+impl SourceMap {
+    pub fn new(output_file: String, default_stratum: String, strata: Vec<Stratum>) -> Self {
+        Self {
+            output_file,
+            default_stratum,
+            strata,
+            text: None,
+        }
+    }
+
+    /// Parses the text of an SMAP, `None` if [text] isn't one or has vendor or embedded
+    /// sections, which aren't modeled. An edited map is written back laid out as JSR-45
+    /// lays it out, which may differ from what [text] had, e.g. in where `*E` lines are.
+    pub fn parse(text: &str) -> Option<SourceMap> {
+        let mut lines = text.lines();
+        if lines.next()? != "SMAP" {
+            return None;
+        }
+        let output_file = lines.next()?.to_string();
+        let default_stratum = lines.next()?.to_string();
+        let mut strata: Vec<Stratum> = vec![];
+        let mut section: Option<char> = None;
+        // Kotlin ends each stratum with `*E`, rather than just the last one
+        let mut ended = false;
+        while let Some(line) = lines.next() {
+            if let Some(header) = line.strip_prefix('*') {
+                match header.chars().next()? {
+                    'S' => {
+                        strata.push(Stratum {
+                            name: header[1..].trim().to_string(),
+                            files: vec![],
+                            lines: vec![],
+                        });
+                        section = None;
+                        ended = false;
+                    }
+                    kind @ ('F' | 'L') if header.len() == 1 && !ended => section = Some(kind),
+                    'E' if header.len() == 1 => {
+                        section = None;
+                        ended = true;
+                    }
+                    _ => return None,
+                }
+                continue;
+            }
+            if line.is_empty() && ended {
+                continue;
+            }
+            let stratum = strata.last_mut()?;
+            if section? == 'F' {
+                let (info, has_path) = match line.strip_prefix("+ ") {
+                    Some(info) => (info, true),
+                    None => (line, false),
+                };
+                let (id, name) = info.split_once(' ')?;
+                let path = match has_path {
+                    true => Some(lines.next()?.to_string()),
+                    false => None,
+                };
+                stratum.files.push(FileInfo {
+                    id: id.parse().ok()?,
+                    name: name.to_string(),
+                    path,
+                });
+            } else {
+                // An omitted file id is the previous line's
+                let previous_file_id = stratum.lines.last().map_or(0, |line| line.file_id);
+                stratum.lines.push(LineInfo::parse(line, previous_file_id)?);
+            }
+        }
+        ended.then_some(SourceMap {
+            output_file,
+            default_stratum,
+            strata,
+            text: Some(text.to_string()),
+        })
+    }
+
+    /// The text [SourceMap::parse] got, if the map still says the same, or else the map as
+    /// [Display] lays it out
+    fn text(&self) -> String {
+        match &self.text {
+            Some(text) if SourceMap::parse(text).as_ref() == Some(self) => text.clone(),
+            _ => self.to_string(),
+        }
+    }
+
+    /// The file and line of [stratum] that [output_line] of the output file comes from
+    pub fn map_line(&self, stratum: &str, output_line: u32) -> Option<(&FileInfo, u32)> {
+        let stratum = self.strata.iter().find(|it| it.name == stratum)?;
+        stratum.lines.iter().find_map(|line| {
+            let offset = output_line.checked_sub(line.output_start_line)?;
+            let repeat = match line.output_line_increment {
+                0 if offset == 0 => 0,
+                0 => return None,
+                increment => offset / increment,
+            };
+            if repeat >= line.repeat_count {
+                return None;
+            }
+            let file = stratum.files.iter().find(|file| file.id == line.file_id)?;
+            Some((file, line.input_start_line + repeat))
+        })
+    }
+}
+
+impl LineInfo {
+    /// Parses `InputStartLine[#LineFileID][,RepeatCount]:OutputStartLine[,OutputLineIncrement]`
+    fn parse(line: &str, previous_file_id: u32) -> Option<LineInfo> {
+        let (input, output) = line.split_once(':')?;
+        let (input, repeat_count) = match input.split_once(',') {
+            Some((input, repeat_count)) => (input, repeat_count.parse().ok()?),
+            None => (input, 1),
+        };
+        let (input_start_line, file_id) = match input.split_once('#') {
+            Some((input_start_line, file_id)) => (input_start_line, file_id.parse().ok()?),
+            None => (input, previous_file_id),
+        };
+        let (output_start_line, output_line_increment) = match output.split_once(',') {
+            Some((output_start_line, increment)) => (output_start_line, increment.parse().ok()?),
+            None => (output, 1),
+        };
+        Some(LineInfo {
+            input_start_line: input_start_line.parse().ok()?,
+            file_id,
+            repeat_count,
+            output_start_line: output_start_line.parse().ok()?,
+            output_line_increment,
+        })
+    }
+}
+
+/// Writes the SMAP back as text, leaving out the parts of line infos that have their
+/// default values
+impl Display for SourceMap {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "SMAP")?;
+        writeln!(f, "{}", self.output_file)?;
+        writeln!(f, "{}", self.default_stratum)?;
+        for stratum in &self.strata {
+            writeln!(f, "*S {}", stratum.name)?;
+            writeln!(f, "*F")?;
+            for file in &stratum.files {
+                match &file.path {
+                    Some(path) => writeln!(f, "+ {} {}\n{}", file.id, file.name, path)?,
+                    None => writeln!(f, "{} {}", file.id, file.name)?,
+                }
+            }
+            writeln!(f, "*L")?;
+            let mut previous_file_id = 0;
+            for line in &stratum.lines {
+                write!(f, "{}", line.input_start_line)?;
+                if line.file_id != previous_file_id {
+                    write!(f, "#{}", line.file_id)?;
+                    previous_file_id = line.file_id;
+                }
+                if line.repeat_count != 1 {
+                    write!(f, ",{}", line.repeat_count)?;
+                }
+                write!(f, ":{}", line.output_start_line)?;
+                if line.output_line_increment != 1 {
+                    write!(f, ",{}", line.output_line_increment)?;
+                }
+                writeln!(f)?;
+            }
+        }
+        writeln!(f, "*E")
+    }
+}
+
+pub fn parse_source_debug_extension(bytes: ByteReader) -> SourceDebugExtension {
+    let debug_extension = bytes.deplete();
+    let source_map = decode(&debug_extension)
+        .ok()
+        .and_then(|text| text.as_str().and_then(SourceMap::parse));
+    match source_map {
+        Some(source_map) => SourceDebugExtension::SourceMap(source_map),
+        None => SourceDebugExtension::Other(debug_extension),
+    }
+}
+
+pub fn write_source_debug_extension(source_debug_extension: SourceDebugExtension) -> Vec<u8> {
+    match source_debug_extension {
+        SourceDebugExtension::SourceMap(source_map) => encode(&JavaString::from(source_map.text())),
+        SourceDebugExtension::Other(debug_extension) => debug_extension,
+    }
+}
@@ -0,0 +1,306 @@
+use crate::bytecode::reader::{ByteReader, Take};
+use crate::bytecode::writer::ByteWriter;
+use crate::constant_pool::ConstantPool;
+use crate::error::{ClassFileError, ParseError};
+use crate::model::attrs::code::{Label, Labels};
+use crate::{w1, w2, w4};
+use serde::{Deserialize, Serialize};
+
+// https://docs.oracle.com/javase/specs/jvms/se12/html/jvms-4.html#jvms-4.7.4
+pub type StackMapTable = Vec<StackMapFrame>;
+
+/// The types of the locals and the operand stack at [label], relative to the previous frame.
+/// Frames read from a class file keep their kind; when written, a compact frame whose offset
+/// no longer fits its frame type is written as its extended form instead.
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum StackMapFrame {
+    /// Same locals as the previous frame, empty stack
+    same_frame {
+        label: Label,
+    },
+    /// Same locals as the previous frame, one item on the stack
+    same_locals_1_stack_item_frame {
+        label: Label,
+        stack: VerificationType,
+    },
+    same_locals_1_stack_item_frame_extended {
+        label: Label,
+        stack: VerificationType,
+    },
+    /// The last [chopped] (1 to 3) locals of the previous frame are gone, empty stack
+    chop_frame {
+        label: Label,
+        chopped: w1,
+    },
+    same_frame_extended {
+        label: Label,
+    },
+    /// 1 to 3 more locals than the previous frame, empty stack
+    append_frame {
+        label: Label,
+        locals: Vec<VerificationType>,
+    },
+    full_frame {
+        label: Label,
+        locals: Vec<VerificationType>,
+        stack: Vec<VerificationType>,
+    },
+}
+
+/// Longs and doubles take up a single entry, even though they take two local variables
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum VerificationType {
+    Top,
+    Integer,
+    Float,
+    Double,
+    Long,
+    Null,
+    /// `this` in a constructor before the super constructor is called
+    UninitializedThis,
+    /// An instance of a class, or an array
+    Object(String),
+    /// The result of the `new` instruction at the label, before its constructor is called
+    Uninitialized(Label),
+}
+
+impl StackMapFrame {
+    pub fn label(&self) -> Label {
+        use StackMapFrame::*;
+        match self {
+            same_frame { label }
+            | same_locals_1_stack_item_frame { label, .. }
+            | same_locals_1_stack_item_frame_extended { label, .. }
+            | chop_frame { label, .. }
+            | same_frame_extended { label }
+            | append_frame { label, .. }
+            | full_frame { label, .. } => *label,
+        }
+    }
+
+    /// The labels this frame points at: its own, and those of uninitialized objects
+    pub fn labels(&self) -> Vec<Label> {
+        use StackMapFrame::*;
+        let types: Vec<&VerificationType> = match self {
+            same_frame { .. } | chop_frame { .. } | same_frame_extended { .. } => vec![],
+            same_locals_1_stack_item_frame { stack, .. }
+            | same_locals_1_stack_item_frame_extended { stack, .. } => vec![stack],
+            append_frame { locals, .. } => locals.iter().collect(),
+            full_frame { locals, stack, .. } => locals.iter().chain(stack).collect(),
+        };
+        let mut labels = vec![self.label()];
+        labels.extend(types.into_iter().filter_map(|item| match item {
+            VerificationType::Uninitialized(label) => Some(*label),
+            _ => None,
+        }));
+        labels
+    }
+}
+
+fn parse_verification_type(
+    bytes: &mut ByteReader,
+    constant_pool: &ConstantPool,
+) -> Result<VerificationType, ParseError> {
+    use VerificationType::*;
+    let offset = bytes.offset();
+    let tag: w1 = bytes.take()?;
+    Ok(match tag {
+        0 => Top,
+        1 => Integer,
+        2 => Float,
+        3 => Double,
+        4 => Long,
+        5 => Null,
+        6 => UninitializedThis,
+        7 => Object(bytes.take_constant(|index| constant_pool.get_class_name(index))?),
+        8 => {
+            let offset: w2 = bytes.take()?;
+            Uninitialized(Label(offset as w4))
+        }
+        _ => return Err(bytes.error_at(offset, ClassFileError::InvalidVerificationType { tag })),
+    })
+}
+
+fn parse_verification_types(
+    bytes: &mut ByteReader,
+    constant_pool: &ConstantPool,
+    count: usize,
+) -> Result<Vec<VerificationType>, ParseError> {
+    (0..count)
+        .map(|_| parse_verification_type(bytes, constant_pool))
+        .collect()
+}
+
+pub fn parse_stack_map_table(
+    bytes: &mut ByteReader,
+    constant_pool: &ConstantPool,
+) -> Result<StackMapTable, ParseError> {
+    use StackMapFrame::*;
+    let number_of_entries: w2 = bytes.take()?;
+    let mut stack_map_table: StackMapTable = Vec::with_capacity(number_of_entries.into());
+    // The first frame's offset is absolute, every later one is relative to the previous frame
+    let mut previous: Option<w4> = None;
+    for i in 0..number_of_entries {
+        bytes.context(format!("entries[{}]", i), |bytes| {
+            let offset = bytes.offset();
+            let frame_type: w1 = bytes.take()?;
+            let offset_delta: w2 = match frame_type {
+                0..=63 => frame_type as w2,
+                64..=127 => frame_type as w2 - 64,
+                247..=255 => bytes.take()?,
+                _ => {
+                    return Err(
+                        bytes.error_at(offset, ClassFileError::InvalidFrameType { frame_type })
+                    )
+                }
+            };
+            let label = Label(
+                previous
+                    .map_or(0, |pc| pc.saturating_add(1))
+                    .saturating_add(offset_delta as w4),
+            );
+            previous = Some(label.0);
+            stack_map_table.push(match frame_type {
+                0..=63 => same_frame { label },
+                64..=127 => same_locals_1_stack_item_frame {
+                    label,
+                    stack: parse_verification_type(bytes, constant_pool)?,
+                },
+                247 => same_locals_1_stack_item_frame_extended {
+                    label,
+                    stack: parse_verification_type(bytes, constant_pool)?,
+                },
+                248..=250 => chop_frame {
+                    label,
+                    chopped: 251 - frame_type,
+                },
+                251 => same_frame_extended { label },
+                252..=254 => append_frame {
+                    label,
+                    locals: parse_verification_types(
+                        bytes,
+                        constant_pool,
+                        (frame_type - 251) as usize,
+                    )?,
+                },
+                _ => {
+                    let number_of_locals: w2 = bytes.take()?;
+                    let locals =
+                        parse_verification_types(bytes, constant_pool, number_of_locals as usize)?;
+                    let number_of_stack_items: w2 = bytes.take()?;
+                    let stack = parse_verification_types(
+                        bytes,
+                        constant_pool,
+                        number_of_stack_items as usize,
+                    )?;
+                    full_frame {
+                        label,
+                        locals,
+                        stack,
+                    }
+                }
+            });
+            Ok(())
+        })?;
+    }
+    Ok(stack_map_table)
+}
+
+fn write_verification_type(
+    verification_type: VerificationType,
+    constant_pool: &mut ConstantPool,
+    labels: &Labels,
+    writer: &mut ByteWriter,
+) -> Result<(), ClassFileError> {
+    use VerificationType::*;
+    match verification_type {
+        Top => writer.write(0 as w1),
+        Integer => writer.write(1 as w1),
+        Float => writer.write(2 as w1),
+        Double => writer.write(3 as w1),
+        Long => writer.write(4 as w1),
+        Null => writer.write(5 as w1),
+        UninitializedThis => writer.write(6 as w1),
+        Object(class_name) => {
+            writer.write(7 as w1);
+            writer.write(constant_pool.push_class(class_name)?);
+        }
+        Uninitialized(label) => {
+            writer.write(8 as w1);
+            writer.write(labels.pc_w2(label)?);
+        }
+    }
+    Ok(())
+}
+
+/// The number of locals a `chop_frame` or `append_frame` encodes in its frame type
+fn frame_locals(count: usize) -> Result<w1, ClassFileError> {
+    match count {
+        1..=3 => Ok(count as w1),
+        _ => Err(ClassFileError::InvalidFrameLocals { count }),
+    }
+}
+
+pub fn write_stack_map_table(
+    stack_map_table: StackMapTable,
+    constant_pool: &mut ConstantPool,
+    labels: &Labels,
+) -> Result<Vec<u8>, ClassFileError> {
+    use StackMapFrame::*;
+    let mut writer = ByteWriter::new();
+    writer.write(stack_map_table.len() as w2);
+    let mut previous: Option<usize> = None;
+    for frame in stack_map_table {
+        let pc = labels.pc_w2(frame.label())? as usize;
+        // Frames are relative to the one before them, so they can't be reordered
+        let offset_delta = match previous {
+            None => pc,
+            Some(previous) if pc > previous => pc - previous - 1,
+            Some(_) => return Err(ClassFileError::FramesOutOfOrder { pc }),
+        };
+        previous = Some(pc);
+        let compact = offset_delta <= 63;
+        match frame {
+            same_frame { .. } if compact => writer.write(offset_delta as w1),
+            same_frame { .. } | same_frame_extended { .. } => {
+                writer.write(251 as w1);
+                writer.write(offset_delta as w2);
+            }
+            same_locals_1_stack_item_frame { stack, .. } if compact => {
+                writer.write(64 + offset_delta as w1);
+                write_verification_type(stack, constant_pool, labels, &mut writer)?;
+            }
+            same_locals_1_stack_item_frame { stack, .. }
+            | same_locals_1_stack_item_frame_extended { stack, .. } => {
+                writer.write(247 as w1);
+                writer.write(offset_delta as w2);
+                write_verification_type(stack, constant_pool, labels, &mut writer)?;
+            }
+            chop_frame { chopped, .. } => {
+                writer.write(251 - frame_locals(chopped as usize)?);
+                writer.write(offset_delta as w2);
+            }
+            append_frame { locals, .. } => {
+                writer.write(251 + frame_locals(locals.len())?);
+                writer.write(offset_delta as w2);
+                for local in locals {
+                    write_verification_type(local, constant_pool, labels, &mut writer)?;
+                }
+            }
+            full_frame { locals, stack, .. } => {
+                writer.write(255 as w1);
+                writer.write(offset_delta as w2);
+                writer.write(locals.len() as w2);
+                for local in locals {
+                    write_verification_type(local, constant_pool, labels, &mut writer)?;
+                }
+                writer.write(stack.len() as w2);
+                for item in stack {
+                    write_verification_type(item, constant_pool, labels, &mut writer)?;
+                }
+            }
+        }
+    }
+    Ok(writer.into())
+}
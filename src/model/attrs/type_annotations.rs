@@ -0,0 +1,441 @@
+use crate::bytecode::reader::{ByteReader, Take};
+use crate::bytecode::writer::ByteWriter;
+use crate::constant_pool::ConstantPool;
+use crate::error::{ClassFileError, ParseError};
+use crate::model::attrs::annotations::{parse_annotation, write_annotation, Annotation};
+use crate::model::attrs::code::{Label, Labels};
+use crate::{w1, w2, w4};
+use serde::{Deserialize, Serialize};
+
+// https://docs.oracle.com/javase/specs/jvms/se12/html/jvms-4.html#jvms-4.7.20
+pub type TypeAnnotations = Vec<TypeAnnotation>;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TypeAnnotation {
+    /// Which type in the declaration or the code is annotated
+    pub target: TypeAnnotationTarget,
+    /// Where the annotation is within [target], empty if it's on the type itself
+    pub target_path: TypePath,
+    pub annotation: Annotation,
+}
+
+/// A `target_type` with its `target_info`. Targets in a `Code` attribute point at
+/// instructions with labels, and appear only in the `Code` attribute's own type annotations.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum TypeAnnotationTarget {
+    /// A type parameter of a generic class or interface
+    ClassTypeParameter {
+        type_parameter_index: w1,
+    },
+    /// A type parameter of a generic method or constructor
+    MethodTypeParameter {
+        type_parameter_index: w1,
+    },
+    /// The superclass if [supertype_index] is 65535, otherwise the interface at that index
+    Supertype {
+        supertype_index: w2,
+    },
+    ClassTypeParameterBound {
+        type_parameter_index: w1,
+        bound_index: w1,
+    },
+    MethodTypeParameterBound {
+        type_parameter_index: w1,
+        bound_index: w1,
+    },
+    /// The type of a field or record component
+    Field,
+    /// The return type of a method, or the type of a newly constructed object
+    Return,
+    /// The receiver type of a method or constructor
+    Receiver,
+    FormalParameter {
+        formal_parameter_index: w1,
+    },
+    /// The type at [throws_type_index] in the method's `Exceptions` attribute
+    Throws {
+        throws_type_index: w2,
+    },
+    /// A local variable, which may live in several ranges of the code
+    LocalVariable {
+        table: Vec<LocalVariableTarget>,
+    },
+    /// A resource variable of a `try`-with-resources
+    ResourceVariable {
+        table: Vec<LocalVariableTarget>,
+    },
+    /// The exception parameter type of the handler at [exception_table_index]
+    /// in the `Code` attribute's exception table
+    Catch {
+        exception_table_index: w2,
+    },
+    /// The type of an `instanceof` expression
+    InstanceOf {
+        label: Label,
+    },
+    /// The type of a `new` expression
+    New {
+        label: Label,
+    },
+    /// The type before `::new` in a constructor reference
+    ConstructorReference {
+        label: Label,
+    },
+    /// The type before `::` in a method reference
+    MethodReference {
+        label: Label,
+    },
+    Cast {
+        label: Label,
+        /// Which type of an intersection cast
+        type_argument_index: w1,
+    },
+    ConstructorInvocationTypeArgument {
+        label: Label,
+        type_argument_index: w1,
+    },
+    MethodInvocationTypeArgument {
+        label: Label,
+        type_argument_index: w1,
+    },
+    ConstructorReferenceTypeArgument {
+        label: Label,
+        type_argument_index: w1,
+    },
+    MethodReferenceTypeArgument {
+        label: Label,
+        type_argument_index: w1,
+    },
+}
+
+/// A range of code where a local variable holds a value
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LocalVariableTarget {
+    pub start: Label,
+    /// End of the range (exclusive)
+    pub end: Label,
+    pub index: w2,
+}
+
+/// The steps from the outermost type of a target to the annotated part of it
+pub type TypePath = Vec<TypePathElement>;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum TypePathElement {
+    /// Into the component type of an array type
+    Array,
+    /// Into a nested type of a type
+    Nested,
+    /// Into the bound of a wildcard type argument
+    WildcardBound,
+    /// Into a type argument of a parameterized type
+    TypeArgument(w1),
+}
+
+impl TypeAnnotationTarget {
+    /// The labels a target in a `Code` attribute points at
+    pub fn labels(&self) -> Vec<Label> {
+        use TypeAnnotationTarget::*;
+        match self {
+            LocalVariable { table } | ResourceVariable { table } => table
+                .iter()
+                .flat_map(|target| [target.start, target.end])
+                .collect(),
+            InstanceOf { label }
+            | New { label }
+            | ConstructorReference { label }
+            | MethodReference { label }
+            | Cast { label, .. }
+            | ConstructorInvocationTypeArgument { label, .. }
+            | MethodInvocationTypeArgument { label, .. }
+            | ConstructorReferenceTypeArgument { label, .. }
+            | MethodReferenceTypeArgument { label, .. } => vec![*label],
+            _ => vec![],
+        }
+    }
+
+    fn target_type(&self) -> w1 {
+        use TypeAnnotationTarget::*;
+        match self {
+            ClassTypeParameter { .. } => 0x00,
+            MethodTypeParameter { .. } => 0x01,
+            Supertype { .. } => 0x10,
+            ClassTypeParameterBound { .. } => 0x11,
+            MethodTypeParameterBound { .. } => 0x12,
+            Field => 0x13,
+            Return => 0x14,
+            Receiver => 0x15,
+            FormalParameter { .. } => 0x16,
+            Throws { .. } => 0x17,
+            LocalVariable { .. } => 0x40,
+            ResourceVariable { .. } => 0x41,
+            Catch { .. } => 0x42,
+            InstanceOf { .. } => 0x43,
+            New { .. } => 0x44,
+            ConstructorReference { .. } => 0x45,
+            MethodReference { .. } => 0x46,
+            Cast { .. } => 0x47,
+            ConstructorInvocationTypeArgument { .. } => 0x48,
+            MethodInvocationTypeArgument { .. } => 0x49,
+            ConstructorReferenceTypeArgument { .. } => 0x4A,
+            MethodReferenceTypeArgument { .. } => 0x4B,
+        }
+    }
+}
+
+pub fn parse_type_annotations(
+    bytes: &mut ByteReader,
+    constant_pool: &ConstantPool,
+) -> Result<TypeAnnotations, ParseError> {
+    let num_annotations: w2 = bytes.take()?;
+    let mut annotations = Vec::with_capacity(num_annotations.into());
+    for i in 0..num_annotations {
+        bytes.context(format!("annotations[{}]", i), |bytes| {
+            let target = parse_target(bytes)?;
+            let target_path = parse_type_path(bytes)?;
+            let annotation = parse_annotation(bytes, constant_pool)?;
+            annotations.push(TypeAnnotation {
+                target,
+                target_path,
+                annotation,
+            });
+            Ok(())
+        })?;
+    }
+    Ok(annotations)
+}
+
+fn take_label(bytes: &mut ByteReader) -> Result<Label, ParseError> {
+    let offset: w2 = bytes.take()?;
+    Ok(Label(offset as w4))
+}
+
+fn parse_target(bytes: &mut ByteReader) -> Result<TypeAnnotationTarget, ParseError> {
+    use TypeAnnotationTarget::*;
+    let offset = bytes.offset();
+    let target_type: w1 = bytes.take()?;
+    Ok(match target_type {
+        0x00 => ClassTypeParameter {
+            type_parameter_index: bytes.take()?,
+        },
+        0x01 => MethodTypeParameter {
+            type_parameter_index: bytes.take()?,
+        },
+        0x10 => Supertype {
+            supertype_index: bytes.take()?,
+        },
+        0x11 => ClassTypeParameterBound {
+            type_parameter_index: bytes.take()?,
+            bound_index: bytes.take()?,
+        },
+        0x12 => MethodTypeParameterBound {
+            type_parameter_index: bytes.take()?,
+            bound_index: bytes.take()?,
+        },
+        0x13 => Field,
+        0x14 => Return,
+        0x15 => Receiver,
+        0x16 => FormalParameter {
+            formal_parameter_index: bytes.take()?,
+        },
+        0x17 => Throws {
+            throws_type_index: bytes.take()?,
+        },
+        0x40 | 0x41 => {
+            let table_length: w2 = bytes.take()?;
+            let mut table = Vec::with_capacity(table_length.into());
+            for i in 0..table_length {
+                bytes.context(format!("table[{}]", i), |bytes| {
+                    let start_pc: w2 = bytes.take()?;
+                    let length: w2 = bytes.take()?;
+                    table.push(LocalVariableTarget {
+                        start: Label(start_pc as w4),
+                        end: Label(start_pc as w4 + length as w4),
+                        index: bytes.take()?,
+                    });
+                    Ok(())
+                })?;
+            }
+            if target_type == 0x40 {
+                LocalVariable { table }
+            } else {
+                ResourceVariable { table }
+            }
+        }
+        0x42 => Catch {
+            exception_table_index: bytes.take()?,
+        },
+        0x43 => InstanceOf {
+            label: take_label(bytes)?,
+        },
+        0x44 => New {
+            label: take_label(bytes)?,
+        },
+        0x45 => ConstructorReference {
+            label: take_label(bytes)?,
+        },
+        0x46 => MethodReference {
+            label: take_label(bytes)?,
+        },
+        0x47..=0x4B => {
+            let label = take_label(bytes)?;
+            let type_argument_index: w1 = bytes.take()?;
+            match target_type {
+                0x47 => Cast {
+                    label,
+                    type_argument_index,
+                },
+                0x48 => ConstructorInvocationTypeArgument {
+                    label,
+                    type_argument_index,
+                },
+                0x49 => MethodInvocationTypeArgument {
+                    label,
+                    type_argument_index,
+                },
+                0x4A => ConstructorReferenceTypeArgument {
+                    label,
+                    type_argument_index,
+                },
+                _ => MethodReferenceTypeArgument {
+                    label,
+                    type_argument_index,
+                },
+            }
+        }
+        _ => {
+            return Err(bytes.error_at(offset, ClassFileError::InvalidTargetType { target_type }));
+        }
+    })
+}
+
+fn parse_type_path(bytes: &mut ByteReader) -> Result<TypePath, ParseError> {
+    let path_length: w1 = bytes.take()?;
+    let mut path = Vec::with_capacity(path_length.into());
+    for i in 0..path_length {
+        bytes.context(format!("path[{}]", i), |bytes| {
+            let offset = bytes.offset();
+            let type_path_kind: w1 = bytes.take()?;
+            let type_argument_index: w1 = bytes.take()?;
+            path.push(match type_path_kind {
+                0 => TypePathElement::Array,
+                1 => TypePathElement::Nested,
+                2 => TypePathElement::WildcardBound,
+                3 => TypePathElement::TypeArgument(type_argument_index),
+                _ => {
+                    return Err(bytes.error_at(
+                        offset,
+                        ClassFileError::InvalidTypePathKind { type_path_kind },
+                    ))
+                }
+            });
+            Ok(())
+        })?;
+    }
+    Ok(path)
+}
+
+/// Writes [annotations], resolving the labels of targets in a `Code` attribute with [labels]
+pub fn write_type_annotations(
+    annotations: TypeAnnotations,
+    constant_pool: &mut ConstantPool,
+    labels: &Labels,
+) -> Result<Vec<u8>, ClassFileError> {
+    let mut writer = ByteWriter::new();
+    writer.write(annotations.len() as w2);
+    for TypeAnnotation {
+        target,
+        target_path,
+        annotation,
+    } in annotations
+    {
+        write_target(target, labels, &mut writer)?;
+        writer.write(target_path.len() as w1);
+        for element in target_path {
+            let (type_path_kind, type_argument_index): (w1, w1) = match element {
+                TypePathElement::Array => (0, 0),
+                TypePathElement::Nested => (1, 0),
+                TypePathElement::WildcardBound => (2, 0),
+                TypePathElement::TypeArgument(index) => (3, index),
+            };
+            writer.write(type_path_kind);
+            writer.write(type_argument_index);
+        }
+        write_annotation(annotation, constant_pool, &mut writer)?;
+    }
+    Ok(writer.into())
+}
+
+fn write_target(
+    target: TypeAnnotationTarget,
+    labels: &Labels,
+    writer: &mut ByteWriter,
+) -> Result<(), ClassFileError> {
+    use TypeAnnotationTarget::*;
+    writer.write(target.target_type());
+    match target {
+        ClassTypeParameter {
+            type_parameter_index,
+        }
+        | MethodTypeParameter {
+            type_parameter_index,
+        } => writer.write(type_parameter_index),
+        Supertype { supertype_index } => writer.write(supertype_index),
+        ClassTypeParameterBound {
+            type_parameter_index,
+            bound_index,
+        }
+        | MethodTypeParameterBound {
+            type_parameter_index,
+            bound_index,
+        } => {
+            writer.write(type_parameter_index);
+            writer.write(bound_index);
+        }
+        Field | Return | Receiver => {}
+        FormalParameter {
+            formal_parameter_index,
+        } => writer.write(formal_parameter_index),
+        Throws { throws_type_index } => writer.write(throws_type_index),
+        LocalVariable { table } | ResourceVariable { table } => {
+            writer.write(table.len() as w2);
+            for LocalVariableTarget { start, end, index } in table {
+                let start_pc = labels.pc_w2(start)?;
+                writer.write(start_pc);
+                writer.write(labels.pc_w2(end)?.saturating_sub(start_pc));
+                writer.write(index);
+            }
+        }
+        Catch {
+            exception_table_index,
+        } => writer.write(exception_table_index),
+        InstanceOf { label }
+        | New { label }
+        | ConstructorReference { label }
+        | MethodReference { label } => writer.write(labels.pc_w2(label)?),
+        Cast {
+            label,
+            type_argument_index,
+        }
+        | ConstructorInvocationTypeArgument {
+            label,
+            type_argument_index,
+        }
+        | MethodInvocationTypeArgument {
+            label,
+            type_argument_index,
+        }
+        | ConstructorReferenceTypeArgument {
+            label,
+            type_argument_index,
+        }
+        | MethodReferenceTypeArgument {
+            label,
+            type_argument_index,
+        } => {
+            writer.write(labels.pc_w2(label)?);
+            writer.write(type_argument_index);
+        }
+    }
+    Ok(())
+}
@@ -1,3 +1,5 @@
+use crate::constant_pool::ConstantPool;
+use crate::model::attrs::record::RecordComponent;
 use crate::model::attrs::Attribute;
 use crate::model::field::Field;
 use crate::model::interface::Interface;
@@ -18,6 +20,28 @@ pub struct Class {
     pub fields: Vec<Field>,
     pub methods: Vec<Method>,
     pub attributes: Vec<Attribute>,
+    /// The constant pool the class was read with, if it was read in
+    /// [lossless](crate::bytecode::reader::ByteReader::lossless) mode.
+    /// When present, writing reuses its entries instead of building a new pool.
+    #[serde(skip)]
+    pub constant_pool: Option<ConstantPool>,
+}
+
+impl Class {
+    /// Whether this is a record class, i.e. has a `Record` attribute
+    pub fn is_record(&self) -> bool {
+        self.record_components().is_some()
+    }
+
+    /// The components of a record class, `None` if this isn't one
+    pub fn record_components(&self) -> Option<&[RecordComponent]> {
+        self.attributes
+            .iter()
+            .find_map(|attribute| match attribute {
+                Attribute::Record(components) => Some(components.as_slice()),
+                _ => None,
+            })
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
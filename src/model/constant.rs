@@ -0,0 +1,120 @@
+//! Constants that are used by value rather than just referenced by name,
+//! e.g. the operand of `ldc`
+
+use crate::model::descriptor::{FieldType, MethodDescriptor};
+use crate::mutf8::JavaString;
+use serde::{Deserialize, Serialize};
+
+/// A constant `ldc`, `ldc_w` or `ldc2_w` can push onto the stack
+/// (https://docs.oracle.com/javase/specs/jvms/se12/html/jvms-4.html#jvms-4.4)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Loadable {
+    Integer(i32),
+    Float(f32),
+    Long(i64),
+    Double(f64),
+    String(JavaString),
+    /// A class name, or an array descriptor
+    Class(String),
+    MethodType(MethodDescriptor),
+    MethodHandle(MethodHandle),
+    Dynamic(Dynamic),
+}
+
+impl Loadable {
+    /// Whether the constant takes two stack slots, so that it has to be loaded with `ldc2_w`
+    ///
+    ///```rust
+    /// use rusty_javap::model::constant::Loadable;
+    /// assert!(Loadable::Long(1).is_wide());
+    /// assert!(!Loadable::Integer(1).is_wide());
+    ///```
+    pub fn is_wide(&self) -> bool {
+        match self {
+            Loadable::Long(_) | Loadable::Double(_) => true,
+            Loadable::Dynamic(dynamic) => dynamic.descriptor.slots() == 2,
+            _ => false,
+        }
+    }
+}
+
+/// How a [MethodHandle] accesses its member
+/// (https://docs.oracle.com/javase/specs/jvms/se12/html/jvms-5.html#jvms-5.4.3.5)
+#[allow(non_camel_case_types)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ReferenceKind {
+    REF_getField = 1,
+    REF_getStatic = 2,
+    REF_putField = 3,
+    REF_putStatic = 4,
+    REF_invokeVirtual = 5,
+    REF_invokeStatic = 6,
+    REF_invokeSpecial = 7,
+    REF_newInvokeSpecial = 8,
+    REF_invokeInterface = 9,
+}
+
+impl ReferenceKind {
+    /// Whether the handle refers to a field rather than a method
+    pub fn is_field(self) -> bool {
+        (self as u8) <= ReferenceKind::REF_putStatic as u8
+    }
+}
+
+impl TryFrom<u8> for ReferenceKind {
+    /// The unrecognized kind
+    type Error = u8;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        use ReferenceKind::*;
+        Ok(match value {
+            1 => REF_getField,
+            2 => REF_getStatic,
+            3 => REF_putField,
+            4 => REF_putStatic,
+            5 => REF_invokeVirtual,
+            6 => REF_invokeStatic,
+            7 => REF_invokeSpecial,
+            8 => REF_newInvokeSpecial,
+            9 => REF_invokeInterface,
+            _ => return Err(value),
+        })
+    }
+}
+
+/// A handle to a field or method
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct MethodHandle {
+    pub kind: ReferenceKind,
+    pub class: String,
+    pub name: String,
+    pub descriptor: String,
+    /// Whether [class] is an interface; `REF_invokeStatic` and `REF_invokeSpecial`
+    /// handles may refer to interface methods
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub interface: bool,
+}
+
+/// A method that computes a [Dynamic] constant or links a [CallSite],
+/// together with the static arguments it's called with
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BootstrapMethod {
+    pub handle: MethodHandle,
+    pub arguments: Vec<Loadable>,
+}
+
+/// A dynamically-computed constant
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Dynamic {
+    pub name: String,
+    pub descriptor: FieldType,
+    pub bootstrap: BootstrapMethod,
+}
+
+/// A dynamically-computed call site, which `invokedynamic` calls
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CallSite {
+    pub name: String,
+    pub descriptor: MethodDescriptor,
+    pub bootstrap: BootstrapMethod,
+}
@@ -0,0 +1,342 @@
+//! Field and method descriptors
+//! (https://docs.oracle.com/javase/specs/jvms/se12/html/jvms-4.html#jvms-4.3).
+//!
+//! Descriptors are parsed from, [encoded](FieldType::encode) back to and serialized as
+//! the text the constant pool holds, and displayed in Java syntax.
+
+use crate::error::ClassFileError;
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// The most dimensions an array type can have
+pub(crate) const MAX_DIMENSIONS: usize = 255;
+
+/// The type of a field, parameter, local variable or value
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum FieldType {
+    Base(BaseType),
+    /// A class or interface, by its name in internal form, e.g. `java/lang/String`
+    Object(String),
+    /// An array, by its component type
+    Array(Box<FieldType>),
+}
+
+/// The parameter types and return type of a method
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MethodDescriptor {
+    pub parameters: Vec<FieldType>,
+    /// `None` for `void`
+    pub return_type: Option<FieldType>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum BaseType {
+    Byte,
+    Char,
+    Double,
+    Float,
+    Int,
+    Long,
+    Short,
+    Boolean,
+}
+
+impl FieldType {
+    /// Parses a field descriptor, or returns the position where it stops being one
+    ///
+    ///```rust
+    /// use rusty_javap::model::descriptor::{BaseType, FieldType};
+    /// let parsed = FieldType::parse("[[Ljava/lang/String;").unwrap();
+    /// assert_eq!(parsed.to_string(), "java.lang.String[][]");
+    /// assert_eq!(parsed.encode(), "[[Ljava/lang/String;");
+    /// assert_eq!(FieldType::parse("J"), Ok(FieldType::Base(BaseType::Long)));
+    /// assert_eq!(FieldType::parse("Ljava/lang/String"), Err(17));
+    /// assert_eq!(FieldType::parse("II"), Err(1));
+    /// assert_eq!(FieldType::parse("V"), Err(0));
+    ///```
+    pub fn parse(descriptor: &str) -> Result<FieldType, usize> {
+        let (field_type, end) = take_field_type(descriptor, 0)?;
+        match end == descriptor.len() {
+            true => Ok(field_type),
+            false => Err(end),
+        }
+    }
+
+    /// The descriptor as the constant pool holds it
+    pub fn encode(&self) -> String {
+        let mut descriptor = String::new();
+        self.encode_into(&mut descriptor);
+        descriptor
+    }
+
+    fn encode_into(&self, descriptor: &mut String) {
+        match self {
+            FieldType::Base(base) => descriptor.push(base.descriptor()),
+            FieldType::Object(class) => {
+                descriptor.push('L');
+                descriptor.push_str(class);
+                descriptor.push(';');
+            }
+            FieldType::Array(component) => {
+                descriptor.push('[');
+                component.encode_into(descriptor);
+            }
+        }
+    }
+
+    /// The number of local variable (or operand stack) slots a value of the type takes
+    ///
+    ///```rust
+    /// use rusty_javap::model::descriptor::FieldType;
+    /// assert_eq!(FieldType::parse("D").unwrap().slots(), 2);
+    /// assert_eq!(FieldType::parse("[D").unwrap().slots(), 1);
+    ///```
+    pub fn slots(&self) -> usize {
+        match self {
+            FieldType::Base(BaseType::Long | BaseType::Double) => 2,
+            _ => 1,
+        }
+    }
+}
+
+impl MethodDescriptor {
+    /// Parses a method descriptor, or returns the position where it stops being one
+    ///
+    ///```rust
+    /// use rusty_javap::model::descriptor::MethodDescriptor;
+    /// let parsed = MethodDescriptor::parse("(J[DLjava/lang/String;)I").unwrap();
+    /// assert_eq!(parsed.encode(), "(J[DLjava/lang/String;)I");
+    /// assert_eq!(parsed.to_string(), "int (long, double[], java.lang.String)");
+    /// assert_eq!(MethodDescriptor::parse("(I)"), Err(3));
+    /// assert_eq!(MethodDescriptor::parse("(V)V"), Err(1));
+    ///```
+    pub fn parse(descriptor: &str) -> Result<MethodDescriptor, usize> {
+        let bytes = descriptor.as_bytes();
+        if bytes.first() != Some(&b'(') {
+            return Err(0);
+        }
+        let mut position = 1;
+        let mut parameters = vec![];
+        while bytes.get(position) != Some(&b')') {
+            let (parameter, end) = take_field_type(descriptor, position)?;
+            parameters.push(parameter);
+            position = end;
+        }
+        position += 1;
+        let return_type = match bytes.get(position) {
+            Some(b'V') if position + 1 == bytes.len() => None,
+            Some(b'V') => return Err(position + 1),
+            _ => Some(FieldType::parse(&descriptor[position..]).map_err(|end| position + end)?),
+        };
+        Ok(MethodDescriptor {
+            parameters,
+            return_type,
+        })
+    }
+
+    /// The descriptor as the constant pool holds it
+    pub fn encode(&self) -> String {
+        let mut descriptor = String::from("(");
+        for parameter in &self.parameters {
+            parameter.encode_into(&mut descriptor);
+        }
+        descriptor.push(')');
+        match &self.return_type {
+            Some(return_type) => return_type.encode_into(&mut descriptor),
+            None => descriptor.push('V'),
+        }
+        descriptor
+    }
+
+    /// The number of local variable slots the arguments take, not counting the receiver
+    ///
+    ///```rust
+    /// use rusty_javap::model::descriptor::MethodDescriptor;
+    /// let descriptor = MethodDescriptor::parse("(J[DI[[JLjava/lang/String;D)V").unwrap();
+    /// assert_eq!(descriptor.argument_slots(), 8);
+    ///```
+    pub fn argument_slots(&self) -> usize {
+        self.parameters.iter().map(FieldType::slots).sum()
+    }
+
+    /// How the method would be declared in Java, without modifiers
+    ///
+    ///```rust
+    /// use rusty_javap::model::descriptor::MethodDescriptor;
+    /// let descriptor = MethodDescriptor::parse("([Ljava/lang/String;)V").unwrap();
+    /// assert_eq!(descriptor.declaration("main"), "void main(java.lang.String[])");
+    ///```
+    pub fn declaration(&self, name: &str) -> String {
+        let return_type = match &self.return_type {
+            Some(return_type) => return_type.to_string(),
+            None => "void".to_string(),
+        };
+        let parameters: Vec<String> = self.parameters.iter().map(ToString::to_string).collect();
+        format!("{} {}({})", return_type, name, parameters.join(", "))
+    }
+}
+
+impl BaseType {
+    /// The field descriptor of the type
+    pub fn descriptor(self) -> char {
+        use BaseType::*;
+        match self {
+            Byte => 'B',
+            Char => 'C',
+            Double => 'D',
+            Float => 'F',
+            Int => 'I',
+            Long => 'J',
+            Short => 'S',
+            Boolean => 'Z',
+        }
+    }
+
+    pub(crate) fn from_descriptor(descriptor: u8) -> Option<BaseType> {
+        use BaseType::*;
+        Some(match descriptor {
+            b'B' => Byte,
+            b'C' => Char,
+            b'D' => Double,
+            b'F' => Float,
+            b'I' => Int,
+            b'J' => Long,
+            b'S' => Short,
+            b'Z' => Boolean,
+            _ => return None,
+        })
+    }
+}
+
+/// Parses the field type [descriptor] has at [start], returning it with the position after it
+fn take_field_type(descriptor: &str, start: usize) -> Result<(FieldType, usize), usize> {
+    let bytes = descriptor.as_bytes();
+    let mut position = start;
+    while bytes.get(position) == Some(&b'[') {
+        if position - start == MAX_DIMENSIONS {
+            return Err(position);
+        }
+        position += 1;
+    }
+    let dimensions = position - start;
+    let element = match bytes.get(position) {
+        Some(b'L') => {
+            let name_start = position + 1;
+            let name_end = descriptor[name_start..]
+                .find(';')
+                .map(|end| name_start + end)
+                .ok_or(descriptor.len())?;
+            let class = &descriptor[name_start..name_end];
+            // Package and class names are non-empty, and can't contain `.` or `[`
+            let mut offset = name_start;
+            for name in class.split('/') {
+                match name.find(['.', '[']) {
+                    _ if name.is_empty() => return Err(offset),
+                    Some(invalid) => return Err(offset + invalid),
+                    None => offset += name.len() + 1,
+                }
+            }
+            position = name_end;
+            FieldType::Object(class.to_string())
+        }
+        Some(&byte) => FieldType::Base(BaseType::from_descriptor(byte).ok_or(position)?),
+        None => return Err(position),
+    };
+    let field_type = (0..dimensions).fold(element, |component, _| {
+        FieldType::Array(Box::new(component))
+    });
+    Ok((field_type, position + 1))
+}
+
+/// Parses a descriptor read from a class file
+pub(crate) fn resolve_descriptor<T>(
+    descriptor: String,
+    parse: impl FnOnce(&str) -> Result<T, usize>,
+) -> Result<T, ClassFileError> {
+    parse(&descriptor).map_err(|position| ClassFileError::InvalidDescriptor {
+        descriptor,
+        position,
+    })
+}
+
+impl FromStr for FieldType {
+    /// Where the descriptor stops being valid
+    type Err = usize;
+
+    fn from_str(descriptor: &str) -> Result<Self, Self::Err> {
+        FieldType::parse(descriptor)
+    }
+}
+
+impl FromStr for MethodDescriptor {
+    /// Where the descriptor stops being valid
+    type Err = usize;
+
+    fn from_str(descriptor: &str) -> Result<Self, Self::Err> {
+        MethodDescriptor::parse(descriptor)
+    }
+}
+
+/// `int`, `java.lang.String[]`
+impl Display for FieldType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FieldType::Base(base) => write!(f, "{}", base),
+            FieldType::Object(class) => write!(f, "{}", class.replace('/', ".")),
+            FieldType::Array(component) => write!(f, "{}[]", component),
+        }
+    }
+}
+
+/// `void (java.lang.String[])`, where the method's name would go between the return
+/// and parameter types, see [declaration](MethodDescriptor::declaration)
+impl Display for MethodDescriptor {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.declaration(""))
+    }
+}
+
+/// The keyword of the type, e.g. `int`
+impl Display for BaseType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&format!("{:?}", self).to_lowercase())
+    }
+}
+
+impl Serialize for FieldType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.encode())
+    }
+}
+
+impl<'de> Deserialize<'de> for FieldType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let descriptor = String::deserialize(deserializer)?;
+        FieldType::parse(&descriptor).map_err(|position| {
+            D::Error::custom(format!(
+                "invalid field descriptor `{}` at {}",
+                descriptor, position
+            ))
+        })
+    }
+}
+
+impl Serialize for MethodDescriptor {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.encode())
+    }
+}
+
+impl<'de> Deserialize<'de> for MethodDescriptor {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let descriptor = String::deserialize(deserializer)?;
+        MethodDescriptor::parse(&descriptor).map_err(|position| {
+            D::Error::custom(format!(
+                "invalid method descriptor `{}` at {}",
+                descriptor, position
+            ))
+        })
+    }
+}
@@ -1,4 +1,5 @@
 use crate::model::attrs::Attribute;
+use crate::model::descriptor::FieldType;
 use serde::{Deserialize, Serialize};
 use std::fmt::{Display, Formatter};
 
@@ -6,7 +7,7 @@ use std::fmt::{Display, Formatter};
 pub struct Field {
     pub access_flags: Vec<FieldAccessModifier>,
     pub name: String,
-    pub descriptor: String, // TODO: add a descriptor struct?
+    pub descriptor: FieldType,
     pub attributes: Vec<Attribute>,
 }
 
@@ -14,7 +15,7 @@ impl Field {
     pub fn new(
         access_flags: Vec<FieldAccessModifier>,
         name: String,
-        descriptor: String, // TODO: add a descriptor struct?
+        descriptor: FieldType,
         attributes: Vec<Attribute>,
     ) -> Field {
         Field {
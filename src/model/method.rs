@@ -1,11 +1,12 @@
 use crate::model::attrs::Attribute;
+use crate::model::descriptor::MethodDescriptor;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Method {
     pub access_flags: Vec<MethodAccessModifier>,
     pub name: String,
-    pub descriptor: String, // TODO: add a descriptor struct?
+    pub descriptor: MethodDescriptor,
     pub attributes: Vec<Attribute>,
 }
 
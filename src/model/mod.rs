@@ -1,5 +1,8 @@
 pub mod attrs;
 pub mod class;
+pub mod constant;
+pub mod descriptor;
 pub mod field;
 pub mod interface;
 pub mod method;
+pub mod signature;
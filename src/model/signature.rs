@@ -0,0 +1,658 @@
+//! The generic signatures kept in `Signature` attributes
+//! (https://docs.oracle.com/javase/specs/jvms/se12/html/jvms-4.html#jvms-4.7.9.1).
+//!
+//! Each signature is parsed from, and [encoded](ClassSignature::encode) back to, the
+//! text the attribute holds, and displayed in Java syntax, with fully qualified class
+//! names, or simple ones when the alternate flag (`{:#}`) is set. Types nested in more than
+//! 255 arrays, or in more than 32 type argument lists, aren't parsed.
+
+use crate::bytecode::reader::MAX_NESTING;
+use crate::model::descriptor::{BaseType, MAX_DIMENSIONS};
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+
+/// The type parameters, superclass and superinterfaces of a generic class or interface
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClassSignature {
+    pub type_parameters: Vec<TypeParameter>,
+    pub super_class: ClassTypeSignature,
+    pub interfaces: Vec<ClassTypeSignature>,
+}
+
+/// The type parameters, parameter types, return type and thrown types of a generic method
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MethodSignature {
+    pub type_parameters: Vec<TypeParameter>,
+    pub parameters: Vec<JavaTypeSignature>,
+    /// `None` for `void`
+    pub result: Option<JavaTypeSignature>,
+    /// Class types or type variables
+    pub throws: Vec<ReferenceTypeSignature>,
+}
+
+/// The type of a field, record component or local variable
+pub type FieldSignature = ReferenceTypeSignature;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TypeParameter {
+    pub name: String,
+    /// Left out when the only bounds are interfaces
+    pub class_bound: Option<ReferenceTypeSignature>,
+    pub interface_bounds: Vec<ReferenceTypeSignature>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum JavaTypeSignature {
+    Base(BaseType),
+    Reference(ReferenceTypeSignature),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ReferenceTypeSignature {
+    Class(ClassTypeSignature),
+    TypeVariable(String),
+    Array(Box<JavaTypeSignature>),
+}
+
+/// A class type, e.g. `Ljava/util/Map$Entry<TK;TV;>;` or `Ljava/util/Map<TK;TV;>.Entry;`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClassTypeSignature {
+    /// In internal form, e.g. `java/util`, empty for the unnamed package
+    pub package: String,
+    pub class: SimpleClassTypeSignature,
+    /// The inner classes of [class](Self::class) that are parameterized or have a
+    /// parameterized outer class, outermost first
+    pub inner_classes: Vec<SimpleClassTypeSignature>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SimpleClassTypeSignature {
+    pub name: String,
+    pub type_arguments: Vec<TypeArgument>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum TypeArgument {
+    /// `?`
+    Any,
+    Exact(ReferenceTypeSignature),
+    /// `? extends`
+    Extends(ReferenceTypeSignature),
+    /// `? super`
+    Super(ReferenceTypeSignature),
+}
+
+impl ClassSignature {
+    /// Parses a class signature, or returns the position where it stops being one
+    ///
+    ///```rust
+    /// use rusty_javap::model::signature::ClassSignature;
+    /// let signature = "<T::Ljava/lang/Comparable<-TT;>;>Ljava/lang/Object;Ljava/util/function/Supplier<TT;>;";
+    /// let parsed = ClassSignature::parse(signature).unwrap();
+    /// assert_eq!(parsed.encode(), signature);
+    /// assert_eq!(
+    ///     format!("{:#}", parsed),
+    ///     "<T extends Comparable<? super T>> extends Object implements Supplier<T>"
+    /// );
+    /// assert_eq!(ClassSignature::parse("<T>Ljava/lang/Object;"), Err(2));
+    ///```
+    pub fn parse(signature: &str) -> Result<ClassSignature, usize> {
+        SignatureParser::parse(signature, |parser| {
+            let type_parameters = parser.type_parameters()?;
+            let super_class = parser.class_type()?;
+            let mut interfaces = vec![];
+            while !parser.is_done() {
+                interfaces.push(parser.class_type()?);
+            }
+            Ok(ClassSignature {
+                type_parameters,
+                super_class,
+                interfaces,
+            })
+        })
+    }
+
+    /// The signature as a `Signature` attribute holds it
+    pub fn encode(&self) -> String {
+        let mut signature = String::new();
+        encode_type_parameters(&self.type_parameters, &mut signature);
+        self.super_class.encode_into(&mut signature);
+        for interface in &self.interfaces {
+            interface.encode_into(&mut signature);
+        }
+        signature
+    }
+}
+
+impl MethodSignature {
+    /// Parses a method signature, or returns the position where it stops being one
+    ///
+    ///```rust
+    /// use rusty_javap::model::signature::MethodSignature;
+    /// let signature = "<T:Ljava/lang/Object;>([TT;I)Ljava/util/List<TT;>;";
+    /// let parsed = MethodSignature::parse(signature).unwrap();
+    /// assert_eq!(parsed.encode(), signature);
+    /// assert_eq!(format!("{}", parsed), "<T> java.util.List<T> (T[], int)");
+    /// assert_eq!(
+    ///     MethodSignature::parse("<E:Ljava/lang/Exception;>()V^TE;").unwrap().to_string(),
+    ///     "<E extends java.lang.Exception> void () throws E"
+    /// );
+    /// assert_eq!(MethodSignature::parse("(V)V"), Err(1));
+    ///```
+    pub fn parse(signature: &str) -> Result<MethodSignature, usize> {
+        SignatureParser::parse(signature, |parser| {
+            let type_parameters = parser.type_parameters()?;
+            parser.expect(b'(')?;
+            let mut parameters = vec![];
+            while !parser.eat(b')') {
+                parameters.push(parser.java_type()?);
+            }
+            let result = match parser.eat(b'V') {
+                true => None,
+                false => Some(parser.java_type()?),
+            };
+            let mut throws = vec![];
+            while parser.eat(b'^') {
+                throws.push(match parser.peek() {
+                    Some(b'T') => parser.reference_type()?,
+                    _ => ReferenceTypeSignature::Class(parser.class_type()?),
+                });
+            }
+            Ok(MethodSignature {
+                type_parameters,
+                parameters,
+                result,
+                throws,
+            })
+        })
+    }
+
+    /// The signature as a `Signature` attribute holds it
+    pub fn encode(&self) -> String {
+        let mut signature = String::new();
+        encode_type_parameters(&self.type_parameters, &mut signature);
+        signature.push('(');
+        for parameter in &self.parameters {
+            parameter.encode_into(&mut signature);
+        }
+        signature.push(')');
+        match &self.result {
+            Some(result) => result.encode_into(&mut signature),
+            None => signature.push('V'),
+        }
+        for throws in &self.throws {
+            signature.push('^');
+            throws.encode_into(&mut signature);
+        }
+        signature
+    }
+}
+
+impl ReferenceTypeSignature {
+    /// Parses a field signature, or returns the position where it stops being one
+    ///
+    ///```rust
+    /// use rusty_javap::model::signature::FieldSignature;
+    /// let signature = "Ljava/util/Map<Ljava/lang/String;Ljava/util/List<+TT;>;>;";
+    /// let parsed = FieldSignature::parse(signature).unwrap();
+    /// assert_eq!(parsed.encode(), signature);
+    /// assert_eq!(format!("{:#}", parsed), "Map<String, List<? extends T>>");
+    /// assert_eq!(
+    ///     FieldSignature::parse("Ljava/util/Map<TK;TV;>.Entry<TK;TV;>;").unwrap().to_string(),
+    ///     "java.util.Map<K, V>.Entry<K, V>"
+    /// );
+    /// assert_eq!(FieldSignature::parse("I"), Err(0));
+    ///```
+    pub fn parse(signature: &str) -> Result<ReferenceTypeSignature, usize> {
+        SignatureParser::parse(signature, SignatureParser::reference_type)
+    }
+
+    /// The signature as a `Signature` attribute holds it
+    pub fn encode(&self) -> String {
+        let mut signature = String::new();
+        self.encode_into(&mut signature);
+        signature
+    }
+
+    fn encode_into(&self, signature: &mut String) {
+        match self {
+            ReferenceTypeSignature::Class(class) => class.encode_into(signature),
+            ReferenceTypeSignature::TypeVariable(name) => {
+                signature.push('T');
+                signature.push_str(name);
+                signature.push(';');
+            }
+            ReferenceTypeSignature::Array(component) => {
+                signature.push('[');
+                component.encode_into(signature);
+            }
+        }
+    }
+}
+
+impl JavaTypeSignature {
+    fn encode_into(&self, signature: &mut String) {
+        match self {
+            JavaTypeSignature::Base(base) => signature.push(base.descriptor()),
+            JavaTypeSignature::Reference(reference) => reference.encode_into(signature),
+        }
+    }
+}
+
+impl ClassTypeSignature {
+    fn encode_into(&self, signature: &mut String) {
+        signature.push('L');
+        if !self.package.is_empty() {
+            signature.push_str(&self.package);
+            signature.push('/');
+        }
+        self.class.encode_into(signature);
+        for inner_class in &self.inner_classes {
+            signature.push('.');
+            inner_class.encode_into(signature);
+        }
+        signature.push(';');
+    }
+}
+
+impl SimpleClassTypeSignature {
+    fn encode_into(&self, signature: &mut String) {
+        signature.push_str(&self.name);
+        if !self.type_arguments.is_empty() {
+            signature.push('<');
+            for type_argument in &self.type_arguments {
+                match type_argument {
+                    TypeArgument::Any => signature.push('*'),
+                    TypeArgument::Exact(bound) => bound.encode_into(signature),
+                    TypeArgument::Extends(bound) => {
+                        signature.push('+');
+                        bound.encode_into(signature);
+                    }
+                    TypeArgument::Super(bound) => {
+                        signature.push('-');
+                        bound.encode_into(signature);
+                    }
+                }
+            }
+            signature.push('>');
+        }
+    }
+}
+
+fn encode_type_parameters(type_parameters: &[TypeParameter], signature: &mut String) {
+    if type_parameters.is_empty() {
+        return;
+    }
+    signature.push('<');
+    for type_parameter in type_parameters {
+        signature.push_str(&type_parameter.name);
+        signature.push(':');
+        if let Some(class_bound) = &type_parameter.class_bound {
+            class_bound.encode_into(signature);
+        }
+        for interface_bound in &type_parameter.interface_bounds {
+            signature.push(':');
+            interface_bound.encode_into(signature);
+        }
+    }
+    signature.push('>');
+}
+
+struct SignatureParser<'a> {
+    signature: &'a str,
+    position: usize,
+    /// How many type argument lists the parser is in, at most [MAX_NESTING]
+    depth: usize,
+    /// How many arrays the type being parsed is in, at most [MAX_DIMENSIONS]
+    dimensions: usize,
+}
+
+impl<'a> SignatureParser<'a> {
+    /// Parses the whole of [signature] with [parse]
+    fn parse<T>(
+        signature: &'a str,
+        parse: impl FnOnce(&mut Self) -> Result<T, usize>,
+    ) -> Result<T, usize> {
+        let mut parser = SignatureParser {
+            signature,
+            position: 0,
+            depth: 0,
+            dimensions: 0,
+        };
+        let parsed = parse(&mut parser)?;
+        match parser.is_done() {
+            true => Ok(parsed),
+            false => Err(parser.position),
+        }
+    }
+
+    fn is_done(&self) -> bool {
+        self.position == self.signature.len()
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.signature.as_bytes().get(self.position).copied()
+    }
+
+    fn eat(&mut self, byte: u8) -> bool {
+        let eaten = self.peek() == Some(byte);
+        if eaten {
+            self.position += 1;
+        }
+        eaten
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), usize> {
+        match self.eat(byte) {
+            true => Ok(()),
+            false => Err(self.position),
+        }
+    }
+
+    /// A non-empty run of anything but the characters signatures are delimited with
+    fn identifier(&mut self) -> Result<String, usize> {
+        let start = self.position;
+        while let Some(byte) = self.peek() {
+            if matches!(byte, b'.' | b';' | b'[' | b'/' | b'<' | b'>' | b':') {
+                break;
+            }
+            // Identifiers may contain multibyte characters, none of whose bytes is ASCII
+            self.position += 1;
+        }
+        match self.position > start {
+            true => Ok(self.signature[start..self.position].to_string()),
+            false => Err(start),
+        }
+    }
+
+    fn type_parameters(&mut self) -> Result<Vec<TypeParameter>, usize> {
+        let mut type_parameters = vec![];
+        if !self.eat(b'<') {
+            return Ok(type_parameters);
+        }
+        loop {
+            let name = self.identifier()?;
+            self.expect(b':')?;
+            let class_bound = match self.peek() {
+                Some(b':') => None,
+                _ => Some(self.reference_type()?),
+            };
+            let mut interface_bounds = vec![];
+            while self.eat(b':') {
+                interface_bounds.push(self.reference_type()?);
+            }
+            type_parameters.push(TypeParameter {
+                name,
+                class_bound,
+                interface_bounds,
+            });
+            if self.eat(b'>') {
+                return Ok(type_parameters);
+            }
+        }
+    }
+
+    fn java_type(&mut self) -> Result<JavaTypeSignature, usize> {
+        match self.peek().and_then(BaseType::from_descriptor) {
+            Some(base) => {
+                self.position += 1;
+                Ok(JavaTypeSignature::Base(base))
+            }
+            None => Ok(JavaTypeSignature::Reference(self.reference_type()?)),
+        }
+    }
+
+    fn reference_type(&mut self) -> Result<ReferenceTypeSignature, usize> {
+        match self.peek() {
+            Some(b'L') => Ok(ReferenceTypeSignature::Class(self.class_type()?)),
+            Some(b'T') => {
+                self.position += 1;
+                let name = self.identifier()?;
+                self.expect(b';')?;
+                Ok(ReferenceTypeSignature::TypeVariable(name))
+            }
+            Some(b'[') => {
+                // Counted with those of the arrays whose type arguments this is in,
+                // so that no type is nested in more arrays than a descriptor can be
+                let start = self.position;
+                while self.peek() == Some(b'[') {
+                    if self.dimensions == MAX_DIMENSIONS {
+                        return Err(self.position);
+                    }
+                    self.dimensions += 1;
+                    self.position += 1;
+                }
+                let dimensions = self.position - start;
+                let component = self.java_type()?;
+                self.dimensions -= dimensions;
+                let mut array = ReferenceTypeSignature::Array(Box::new(component));
+                for _ in 1..dimensions {
+                    let component = JavaTypeSignature::Reference(array);
+                    array = ReferenceTypeSignature::Array(Box::new(component));
+                }
+                Ok(array)
+            }
+            _ => Err(self.position),
+        }
+    }
+
+    fn class_type(&mut self) -> Result<ClassTypeSignature, usize> {
+        self.expect(b'L')?;
+        let mut package: Vec<String> = vec![];
+        let mut name = self.identifier()?;
+        while self.eat(b'/') {
+            package.push(name);
+            name = self.identifier()?;
+        }
+        let class = SimpleClassTypeSignature {
+            name,
+            type_arguments: self.type_arguments()?,
+        };
+        let mut inner_classes = vec![];
+        while self.eat(b'.') {
+            inner_classes.push(SimpleClassTypeSignature {
+                name: self.identifier()?,
+                type_arguments: self.type_arguments()?,
+            });
+        }
+        self.expect(b';')?;
+        Ok(ClassTypeSignature {
+            package: package.join("/"),
+            class,
+            inner_classes,
+        })
+    }
+
+    fn type_arguments(&mut self) -> Result<Vec<TypeArgument>, usize> {
+        let mut type_arguments = vec![];
+        if self.peek() != Some(b'<') {
+            return Ok(type_arguments);
+        }
+        if self.depth == MAX_NESTING {
+            return Err(self.position);
+        }
+        self.position += 1;
+        self.depth += 1;
+        loop {
+            type_arguments.push(match self.peek() {
+                Some(b'*') => {
+                    self.position += 1;
+                    TypeArgument::Any
+                }
+                Some(b'+') => {
+                    self.position += 1;
+                    TypeArgument::Extends(self.reference_type()?)
+                }
+                Some(b'-') => {
+                    self.position += 1;
+                    TypeArgument::Super(self.reference_type()?)
+                }
+                _ => TypeArgument::Exact(self.reference_type()?),
+            });
+            if self.eat(b'>') {
+                self.depth -= 1;
+                return Ok(type_arguments);
+            }
+        }
+    }
+}
+
+/// Writes [items] separated by [separator], keeping the alternate flag of [f]
+fn display_all<T: Display>(
+    f: &mut Formatter<'_>,
+    items: &[T],
+    separator: &str,
+) -> std::fmt::Result {
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            write!(f, "{}", separator)?;
+        }
+        match f.alternate() {
+            true => write!(f, "{:#}", item)?,
+            false => write!(f, "{}", item)?,
+        }
+    }
+    Ok(())
+}
+
+fn display_type_parameters(
+    f: &mut Formatter<'_>,
+    type_parameters: &[TypeParameter],
+) -> std::fmt::Result {
+    if !type_parameters.is_empty() {
+        write!(f, "<")?;
+        display_all(f, type_parameters, ", ")?;
+        write!(f, "> ")?;
+    }
+    Ok(())
+}
+
+/// `<T extends java.lang.Comparable<T>> extends java.lang.Object implements java.io.Serializable`
+impl Display for ClassSignature {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        display_type_parameters(f, &self.type_parameters)?;
+        write!(f, "extends ")?;
+        display_all(f, std::slice::from_ref(&self.super_class), "")?;
+        if !self.interfaces.is_empty() {
+            write!(f, " implements ")?;
+            display_all(f, &self.interfaces, ", ")?;
+        }
+        Ok(())
+    }
+}
+
+/// `<T> java.util.List<T> (T[], int) throws java.io.IOException`, where the method's name
+/// would go between the return and parameter types
+impl Display for MethodSignature {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        display_type_parameters(f, &self.type_parameters)?;
+        match &self.result {
+            Some(result) => display_all(f, std::slice::from_ref(result), "")?,
+            None => write!(f, "void")?,
+        }
+        write!(f, " (")?;
+        display_all(f, &self.parameters, ", ")?;
+        write!(f, ")")?;
+        if !self.throws.is_empty() {
+            write!(f, " throws ")?;
+            display_all(f, &self.throws, ", ")?;
+        }
+        Ok(())
+    }
+}
+
+/// Leaves out the bound of a type parameter that is only bounded by `Object`
+impl Display for TypeParameter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)?;
+        let only_object = self.interface_bounds.is_empty()
+            && matches!(
+                &self.class_bound,
+                Some(ReferenceTypeSignature::Class(class)) if class.is_object()
+            );
+        if only_object {
+            return Ok(());
+        }
+        let bounds: Vec<&ReferenceTypeSignature> = self
+            .class_bound
+            .iter()
+            .chain(self.interface_bounds.iter())
+            .collect();
+        write!(f, " extends ")?;
+        display_all(f, &bounds, " & ")
+    }
+}
+
+impl Display for JavaTypeSignature {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JavaTypeSignature::Base(base) => write!(f, "{}", base),
+            JavaTypeSignature::Reference(reference) => {
+                display_all(f, std::slice::from_ref(reference), "")
+            }
+        }
+    }
+}
+
+impl Display for ReferenceTypeSignature {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReferenceTypeSignature::Class(class) => display_all(f, std::slice::from_ref(class), ""),
+            ReferenceTypeSignature::TypeVariable(name) => write!(f, "{}", name),
+            ReferenceTypeSignature::Array(component) => {
+                display_all(f, std::slice::from_ref(component.as_ref()), "")?;
+                write!(f, "[]")
+            }
+        }
+    }
+}
+
+impl ClassTypeSignature {
+    fn is_object(&self) -> bool {
+        self.package == "java/lang"
+            && self.class.name == "Object"
+            && self.class.type_arguments.is_empty()
+            && self.inner_classes.is_empty()
+    }
+}
+
+/// Inner classes are shown as `Outer.Inner`, whether they're written as `Outer$Inner`
+/// or as `Outer<T>.Inner`
+impl Display for ClassTypeSignature {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if !self.package.is_empty() && !f.alternate() {
+            write!(f, "{}.", self.package.replace('/', "."))?;
+        }
+        display_all(f, std::slice::from_ref(&self.class), "")?;
+        for inner_class in &self.inner_classes {
+            write!(f, ".")?;
+            display_all(f, std::slice::from_ref(inner_class), "")?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for SimpleClassTypeSignature {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name.replace('$', "."))?;
+        if !self.type_arguments.is_empty() {
+            write!(f, "<")?;
+            display_all(f, &self.type_arguments, ", ")?;
+            write!(f, ">")?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for TypeArgument {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let (prefix, bound) = match self {
+            TypeArgument::Any => return write!(f, "?"),
+            TypeArgument::Exact(bound) => ("", bound),
+            TypeArgument::Extends(bound) => ("? extends ", bound),
+            TypeArgument::Super(bound) => ("? super ", bound),
+        };
+        write!(f, "{}", prefix)?;
+        display_all(f, std::slice::from_ref(bound), "")
+    }
+}
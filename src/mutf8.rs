@@ -0,0 +1,200 @@
+//! The JVM's "modified UTF-8" encoding of `CONSTANT_Utf8` entries
+//! (https://docs.oracle.com/javase/specs/jvms/se12/html/jvms-4.html#jvms-4.4.7)
+//!
+//! It differs from standard UTF-8 in two ways: `U+0000` is encoded as `C0 80`,
+//! and supplementary characters are encoded as two 3-byte surrogates instead of 4 bytes.
+//! Since each surrogate is encoded separately, an entry can hold unpaired surrogates,
+//! which a Rust [String] can't represent -- [JavaString] keeps those as UTF-16 code units.
+
+use crate::{w1, w2};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt::{Debug, Display, Formatter};
+
+/// A string as the JVM sees it: a sequence of UTF-16 code units
+#[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct JavaString(Repr);
+
+/// Valid Unicode is always kept as [Repr::Unicode], so equal strings have equal representations
+#[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+enum Repr {
+    Unicode(String),
+    Utf16(Vec<w2>),
+}
+
+impl JavaString {
+    pub fn from_utf16(units: Vec<w2>) -> JavaString {
+        match String::from_utf16(&units) {
+            Ok(string) => JavaString(Repr::Unicode(string)),
+            Err(_) => JavaString(Repr::Utf16(units)),
+        }
+    }
+
+    /// The string, if it's valid Unicode (i.e. has no unpaired surrogates)
+    pub fn as_str(&self) -> Option<&str> {
+        match &self.0 {
+            Repr::Unicode(string) => Some(string),
+            Repr::Utf16(_) => None,
+        }
+    }
+
+    /// The string, with unpaired surrogates replaced by `U+FFFD`
+    pub fn to_string_lossy(&self) -> String {
+        match &self.0 {
+            Repr::Unicode(string) => string.clone(),
+            Repr::Utf16(units) => String::from_utf16_lossy(units),
+        }
+    }
+
+    pub fn to_utf16(&self) -> Vec<w2> {
+        match &self.0 {
+            Repr::Unicode(string) => string.encode_utf16().collect(),
+            Repr::Utf16(units) => units.clone(),
+        }
+    }
+}
+
+impl From<String> for JavaString {
+    fn from(string: String) -> Self {
+        JavaString(Repr::Unicode(string))
+    }
+}
+
+impl From<&str> for JavaString {
+    fn from(string: &str) -> Self {
+        JavaString(Repr::Unicode(string.to_string()))
+    }
+}
+
+impl TryFrom<JavaString> for String {
+    type Error = JavaString;
+
+    fn try_from(value: JavaString) -> Result<Self, Self::Error> {
+        match value.0 {
+            Repr::Unicode(string) => Ok(string),
+            Repr::Utf16(_) => Err(value),
+        }
+    }
+}
+
+impl Display for JavaString {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_string_lossy())
+    }
+}
+
+impl Debug for JavaString {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.0 {
+            Repr::Unicode(string) => write!(f, "{:?}", string),
+            Repr::Utf16(units) => write!(f, "utf16{:X?}", units),
+        }
+    }
+}
+
+/// Valid Unicode serializes as a plain string, anything else as `{"utf16": [...]}`
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum SerializedJavaString {
+    Unicode(String),
+    Utf16 { utf16: Vec<w2> },
+}
+
+impl Serialize for JavaString {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match &self.0 {
+            Repr::Unicode(string) => serializer.serialize_str(string),
+            Repr::Utf16(units) => SerializedJavaString::Utf16 {
+                utf16: units.clone(),
+            }
+            .serialize(serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for JavaString {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(match SerializedJavaString::deserialize(deserializer)? {
+            SerializedJavaString::Unicode(string) => JavaString::from(string),
+            SerializedJavaString::Utf16 { utf16 } => JavaString::from_utf16(utf16),
+        })
+    }
+}
+
+/// Decodes modified UTF-8, returning the position of the first malformed byte on failure
+///
+///```rust
+/// use rusty_javap::mutf8::{decode, JavaString};
+/// assert_eq!(decode(b"Hello"), Ok(JavaString::from("Hello")));
+/// assert_eq!(decode(&[0xC0, 0x80]), Ok(JavaString::from("\0")));
+/// assert_eq!(decode(&[0xED, 0xA0, 0xBD, 0xED, 0xB8, 0x80]), Ok(JavaString::from("😀")));
+/// assert_eq!(decode(&[0xED, 0xA0, 0xBD]), Ok(JavaString::from_utf16(vec![0xD83D])));
+/// assert_eq!(decode(&[b'a', 0x00]), Err(1));
+/// assert_eq!(decode(&[0xF0, 0x9F, 0x98, 0x80]), Err(0));
+/// assert_eq!(decode(&[0xE2, 0x82]), Err(0));
+/// assert_eq!(decode(&[b'a', 0xC1, 0x81]), Err(1));
+/// assert_eq!(decode(&[0xE0, 0x80, 0x80]), Err(0));
+///```
+pub fn decode(bytes: &[w1]) -> Result<JavaString, usize> {
+    let continuation = |position: usize| match bytes.get(position) {
+        Some(byte) if byte & 0xC0 == 0x80 => Some((byte & 0x3F) as w2),
+        _ => None,
+    };
+    let mut units: Vec<w2> = Vec::with_capacity(bytes.len());
+    let mut position = 0;
+    while let Some(&byte) = bytes.get(position) {
+        // Only the shortest form is allowed, apart from `C0 80` for `\0`
+        let (unit, width) = match byte {
+            0x01..=0x7F => (byte as w2, 1),
+            0xC0..=0xDF => {
+                let second = continuation(position + 1).ok_or(position)?;
+                let unit = (((byte & 0x1F) as w2) << 6) | second;
+                if unit != 0 && unit < 0x80 {
+                    return Err(position);
+                }
+                (unit, 2)
+            }
+            0xE0..=0xEF => {
+                let second = continuation(position + 1).ok_or(position)?;
+                let third = continuation(position + 2).ok_or(position)?;
+                let unit = (((byte & 0x0F) as w2) << 12) | (second << 6) | third;
+                if unit < 0x800 {
+                    return Err(position);
+                }
+                (unit, 3)
+            }
+            _ => return Err(position),
+        };
+        units.push(unit);
+        position += width;
+    }
+    Ok(JavaString::from_utf16(units))
+}
+
+/// Encodes a string as modified UTF-8
+///
+///```rust
+/// use rusty_javap::mutf8::{encode, JavaString};
+/// assert_eq!(encode(&JavaString::from("Hello")), b"Hello");
+/// assert_eq!(encode(&JavaString::from("\0")), vec![0xC0, 0x80]);
+/// assert_eq!(encode(&JavaString::from("é")), vec![0xC3, 0xA9]);
+/// assert_eq!(encode(&JavaString::from("😀")), vec![0xED, 0xA0, 0xBD, 0xED, 0xB8, 0x80]);
+/// assert_eq!(encode(&JavaString::from_utf16(vec![0xDE00])), vec![0xED, 0xB8, 0x80]);
+///```
+pub fn encode(string: &JavaString) -> Vec<w1> {
+    let mut bytes: Vec<w1> = vec![];
+    for unit in string.to_utf16() {
+        match unit {
+            0x0001..=0x007F => bytes.push(unit as w1),
+            0x0000 | 0x0080..=0x07FF => {
+                bytes.push(0xC0 | (unit >> 6) as w1);
+                bytes.push(0x80 | (unit & 0x3F) as w1);
+            }
+            _ => {
+                bytes.push(0xE0 | (unit >> 12) as w1);
+                bytes.push(0x80 | ((unit >> 6) & 0x3F) as w1);
+                bytes.push(0x80 | (unit & 0x3F) as w1);
+            }
+        }
+    }
+    bytes
+}
@@ -0,0 +1,203 @@
+mod common;
+
+use common::{read, run_java, write, write_classes};
+use rusty_javap::model::attrs::annotations::{Annotation, ElementValue, ElementValuePair};
+use rusty_javap::model::attrs::Attribute;
+
+// Compiled with `javac --release 17 -g:none` from
+// ```java
+// public class Annotated {
+//     @Retention(RetentionPolicy.RUNTIME)
+//     @interface Everything {
+//         byte b() default 1;
+//         char c() default 'c';
+//         double d() default 1.5;
+//         float f() default 2.5f;
+//         int i() default 3;
+//         long j() default 4L;
+//         short s() default 5;
+//         boolean z() default true;
+//         String string() default "default";
+//         ElementType e() default ElementType.TYPE;
+//         Class<?> type() default void.class;
+//         Retention annotation() default @Retention(RetentionPolicy.CLASS);
+//         int[] array() default {};
+//     }
+//
+//     @interface Invisible {
+//         String value();
+//     }
+//
+//     @Everything(b = -1, c = 'x', d = -0.5, f = 0.25f, i = Integer.MIN_VALUE, j = Long.MAX_VALUE, s = -2, z = false,
+//             string = "text", e = ElementType.FIELD, type = String[].class,
+//             annotation = @Retention(RetentionPolicy.RUNTIME), array = {1, 2})
+//     @Invisible("field")
+//     public int field;
+//
+//     public void method(@Everything int visible, @Invisible("parameter") int invisible) {}
+//
+//     public static void main(String[] args) {
+//         try {
+//             Everything everything = Annotated.class.getField("field").getAnnotation(Everything.class);
+//             Method[] elements = Everything.class.getDeclaredMethods();
+//             Arrays.sort(elements, Comparator.comparing(Method::getName));
+//             for (Method element : elements) {
+//                 Object value = element.invoke(everything);
+//                 System.out.println(element.getName() + " " + Arrays.deepToString(new Object[] {value})
+//                         + " " + Arrays.deepToString(new Object[] {element.getDefaultValue()}));
+//             }
+//             Annotation[][] parameters = Annotated.class.getMethod("method", int.class, int.class)
+//                     .getParameterAnnotations();
+//             System.out.println(parameters[0][0].annotationType().getName() + " " + parameters[1].length);
+//         } catch (ReflectiveOperationException e) {
+//             throw new RuntimeException(e);
+//         }
+//     }
+// }
+// ```
+const CLASSES: [(&str, &[u8]); 3] = [
+    ("Annotated", include_bytes!("./annotations/Annotated.class")),
+    (
+        "Annotated$Everything",
+        include_bytes!("./annotations/Annotated$Everything.class"),
+    ),
+    (
+        "Annotated$Invisible",
+        include_bytes!("./annotations/Annotated$Invisible.class"),
+    ),
+];
+
+fn annotation(descriptor: &str, elements: Vec<(&str, ElementValue)>) -> Annotation {
+    Annotation {
+        descriptor: descriptor.to_string(),
+        elements: elements
+            .into_iter()
+            .map(|(name, value)| ElementValuePair {
+                name: name.to_string(),
+                value,
+            })
+            .collect(),
+    }
+}
+
+fn retention(policy: &str) -> Annotation {
+    annotation(
+        "Ljava/lang/annotation/Retention;",
+        vec![(
+            "value",
+            ElementValue::Enum {
+                descriptor: "Ljava/lang/annotation/RetentionPolicy;".to_string(),
+                name: policy.to_string(),
+            },
+        )],
+    )
+}
+
+fn invisible(value: &str) -> Annotation {
+    annotation(
+        "LAnnotated$Invisible;",
+        vec![("value", ElementValue::String(value.into()))],
+    )
+}
+
+#[test]
+fn annotations_are_resolved() {
+    let annotated = read(CLASSES[0].1);
+    let field = &annotated.fields[0];
+    let [Attribute::RuntimeVisibleAnnotations(visible), Attribute::RuntimeInvisibleAnnotations(invisible_annotations)] =
+        &field.attributes[..]
+    else {
+        panic!("Unexpected attributes {:?}", field.attributes)
+    };
+    assert_eq!(
+        *visible,
+        vec![annotation(
+            "LAnnotated$Everything;",
+            vec![
+                ("b", ElementValue::Byte(-1)),
+                ("c", ElementValue::Char('x' as u16)),
+                ("d", ElementValue::Double(-0.5)),
+                ("f", ElementValue::Float(0.25)),
+                ("i", ElementValue::Integer(i32::MIN)),
+                ("j", ElementValue::Long(i64::MAX)),
+                ("s", ElementValue::Short(-2)),
+                ("z", ElementValue::Boolean(false)),
+                ("string", ElementValue::String("text".into())),
+                (
+                    "e",
+                    ElementValue::Enum {
+                        descriptor: "Ljava/lang/annotation/ElementType;".to_string(),
+                        name: "FIELD".to_string(),
+                    },
+                ),
+                (
+                    "type",
+                    ElementValue::Class("[Ljava/lang/String;".to_string())
+                ),
+                ("annotation", ElementValue::Annotation(retention("RUNTIME"))),
+                (
+                    "array",
+                    ElementValue::Array(vec![ElementValue::Integer(1), ElementValue::Integer(2)]),
+                ),
+            ],
+        )]
+    );
+    assert_eq!(*invisible_annotations, vec![invisible("field")]);
+
+    let method = annotated
+        .methods
+        .iter()
+        .find(|method| method.name == "method")
+        .unwrap();
+    let parameter_annotations: Vec<_> = method
+        .attributes
+        .iter()
+        .filter_map(|attribute| match attribute {
+            Attribute::RuntimeVisibleParameterAnnotations(annotations)
+            | Attribute::RuntimeInvisibleParameterAnnotations(annotations) => Some(annotations),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        parameter_annotations,
+        vec![
+            &vec![vec![annotation("LAnnotated$Everything;", vec![])], vec![]],
+            &vec![vec![], vec![invisible("parameter")]],
+        ]
+    );
+
+    let everything = read(CLASSES[1].1);
+    let method = everything
+        .methods
+        .iter()
+        .find(|method| method.name == "annotation")
+        .unwrap();
+    assert!(matches!(
+        &method.attributes[..],
+        [Attribute::AnnotationDefault(ElementValue::Annotation(default))] if *default == retention("CLASS")
+    ));
+}
+
+#[test]
+fn rewritten_annotations_are_reflected() {
+    // Without lossless mode, the constant pool is built from scratch
+    let directory = write_classes(CLASSES.map(|(name, bytes)| (name, write(read(bytes)))));
+    assert_eq!(
+        run_java(&directory, "Annotated"),
+        "annotation [@java.lang.annotation.Retention(RUNTIME)] [@java.lang.annotation.Retention(CLASS)]
+array [[1, 2]] [[]]
+b [-1] [1]
+c [x] [c]
+d [-0.5] [1.5]
+e [FIELD] [TYPE]
+f [0.25] [2.5]
+i [-2147483648] [3]
+j [9223372036854775807] [4]
+s [-2] [5]
+string [text] [default]
+type [class [Ljava.lang.String;] [void]
+z [false] [true]
+Annotated$Everything 0
+"
+    );
+}
@@ -10,6 +10,8 @@ fn classfile2json() {
 
     let class_json_content = include_bytes!("./Example.class.json");
     let json_class_original: serde_json::Value = serde_json::from_slice(class_json_content).unwrap();
-    let json_class_written = serde_json::to_value(class).unwrap();
+    // Compare written text rather than `to_value`, which widens `f32`s to imprecise `f64`s
+    let json_class_written: serde_json::Value =
+        serde_json::from_str(&serde_json::to_string(&class).unwrap()).unwrap();
     assert_eq!(json_class_original, json_class_written)
 }
@@ -0,0 +1,78 @@
+// Each test crate uses only some of these
+#![allow(dead_code)]
+
+use rusty_javap::bytecode::reader::{ByteReader, Take};
+use rusty_javap::bytecode::writer::ByteWriter;
+use rusty_javap::model::class::Class;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+pub fn read(bytes: &[u8]) -> Class {
+    let mut reader = ByteReader::from(bytes.to_vec());
+    reader.take().unwrap()
+}
+
+pub fn write(class: Class) -> Vec<u8> {
+    let mut writer = ByteWriter::new();
+    writer.try_write(class).unwrap();
+    writer.into()
+}
+
+/// A new directory for one test's class files, named after the process and a counter,
+/// so neither the tests of one run nor concurrent runs share one
+pub fn temp_dir() -> PathBuf {
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+    let directory = std::env::temp_dir().join(format!(
+        "rusty_javap_{}_{}",
+        std::process::id(),
+        NEXT.fetch_add(1, Ordering::Relaxed)
+    ));
+    std::fs::create_dir_all(&directory).unwrap();
+    directory
+}
+
+/// Writes each class to a new [temp_dir] as `<name>.class`
+pub fn write_classes<'a>(classes: impl IntoIterator<Item = (&'a str, Vec<u8>)>) -> PathBuf {
+    let directory = temp_dir();
+    for (name, bytes) in classes {
+        let path = directory.join(format!("{}.class", name));
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, bytes).unwrap();
+    }
+    directory
+}
+
+/// Runs [main] from the classes in [directory], verifying all of them, and returns its output
+pub fn run_java(directory: &Path, main: &str) -> String {
+    run_java_with_args(directory, main, &[])
+}
+
+pub fn run_java_with_args(directory: &Path, main: &str, args: &[&str]) -> String {
+    stdout(java().arg("-cp").arg(directory).arg(main).args(args))
+}
+
+/// Runs the main class of [module] from the modules in [directory]
+pub fn run_module(directory: &Path, module: &str) -> String {
+    stdout(java().arg("-p").arg(directory).arg("-m").arg(module))
+}
+
+/// `java`, verifying every class it loads
+fn java() -> Command {
+    let mut command = Command::new("java");
+    command.arg("-Xverify:all");
+    command
+}
+
+/// Runs [command], which must succeed, and returns its output
+fn stdout(command: &mut Command) -> String {
+    let output = command.output().unwrap();
+    if !output.stderr.is_empty() {
+        eprintln!(
+            "Got stderr: {}",
+            String::from_utf8_lossy(output.stderr.as_slice())
+        );
+    }
+    assert!(output.status.success());
+    String::from_utf8_lossy(output.stdout.as_slice()).to_string()
+}
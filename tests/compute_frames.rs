@@ -0,0 +1,260 @@
+mod common;
+
+use common::{read, run_java_with_args, write_classes};
+use rusty_javap::analysis::frames::compute_frames;
+use rusty_javap::analysis::hierarchy::Classes;
+use rusty_javap::analysis::TypeError;
+use rusty_javap::bytecode::writer::ByteWriter;
+use rusty_javap::error::ClassFileError;
+use rusty_javap::model::attrs::code::exception_table::ExceptionTableElement;
+use rusty_javap::model::attrs::code::OpcodeInfo::{
+    aaload, aconst_null, aload_0, arraylength, athrow, dup, getstatic, goto, iconst_0, if_icmpge,
+    ifeq, iinc, iload_3, invokespecial, invokevirtual, istore_3, ladd, lconst_0, lconst_1, ldc,
+    lload_1, lstore_1, new, nop, pop, r#return, swap,
+};
+use rusty_javap::model::attrs::code::{
+    ClassRef, Code, FieldRef, Instruction, Label, MethodRef, OpcodeInfo,
+};
+use rusty_javap::model::attrs::stack_map_table::StackMapFrame::*;
+use rusty_javap::model::attrs::stack_map_table::VerificationType::*;
+use rusty_javap::model::attrs::Attribute;
+use rusty_javap::model::class::{Class, ClassAccessModifier, Version};
+use rusty_javap::model::constant::Loadable;
+use rusty_javap::model::method::{Method, MethodAccessModifier};
+
+/// Prints the string on top of the stack
+fn println() -> Vec<Instruction> {
+    vec![
+        getstatic {
+            field: FieldRef {
+                class: ClassRef("java/lang/System".to_string()),
+                name: "out".to_string(),
+                descriptor: "Ljava/io/PrintStream;".parse().unwrap(),
+            },
+        }
+        .into(),
+        swap.into(),
+        invokevirtual {
+            method: MethodRef {
+                class: ClassRef("java/io/PrintStream".to_string()),
+                name: "println".to_string(),
+                descriptor: "(Ljava/lang/String;)V".parse().unwrap(),
+                interface: false,
+            },
+        }
+        .into(),
+    ]
+}
+
+const LOOP: Label = Label(1000);
+const END: Label = Label(1001);
+const SKIP: Label = Label(1002);
+const TRY: Label = Label(1003);
+const TRY_END: Label = Label(1004);
+const HANDLER: Label = Label(1005);
+
+/// ```java
+/// long count = 0;
+/// for (int i = 0; i < args.length; i++) { System.out.println(args[i]); count++; }
+/// new Object(); // with a branch between `new` and the constructor call
+/// try { throw null; } catch (NullPointerException e) { System.out.println("caught"); }
+/// ```
+/// with unreachable code after the loop's `goto` and inside the `try`
+fn main_code() -> Vec<Instruction> {
+    let mut code: Vec<Instruction> = vec![
+        lconst_0.into(),
+        lstore_1.into(),
+        iconst_0.into(),
+        istore_3.into(),
+        LOOP.into(),
+        iload_3.into(),
+        aload_0.into(),
+        arraylength.into(),
+        if_icmpge { branch: END }.into(),
+        aload_0.into(),
+        iload_3.into(),
+        aaload.into(),
+    ];
+    code.extend(println());
+    code.extend([
+        lload_1.into(),
+        lconst_1.into(),
+        ladd.into(),
+        lstore_1.into(),
+        iinc {
+            index: 3,
+            constant: 1,
+        }
+        .into(),
+        goto { branch: LOOP }.into(),
+        // Would underflow the stack if it was reachable
+        pop.into(),
+        pop.into(),
+        END.into(),
+        new {
+            class: ClassRef("java/lang/Object".to_string()),
+        }
+        .into(),
+        dup.into(),
+        iload_3.into(),
+        ifeq { branch: SKIP }.into(),
+        nop.into(),
+        SKIP.into(),
+        invokespecial {
+            method: MethodRef {
+                class: ClassRef("java/lang/Object".to_string()),
+                name: "<init>".to_string(),
+                descriptor: "()V".parse().unwrap(),
+                interface: false,
+            },
+        }
+        .into(),
+        pop.into(),
+        TRY.into(),
+        aconst_null.into(),
+        athrow.into(),
+        iconst_0.into(),
+        pop.into(),
+        TRY_END.into(),
+        HANDLER.into(),
+        pop.into(),
+        ldc {
+            constant: Loadable::String("caught".into()),
+        }
+        .into(),
+    ]);
+    code.extend(println());
+    code.push(r#return.into());
+    code
+}
+
+fn class(code: Vec<Instruction>) -> Class {
+    let code = Code {
+        max_stack: 4,
+        max_locals: 4,
+        code,
+        exception_table: vec![ExceptionTableElement {
+            start: TRY,
+            end: TRY_END,
+            handler: HANDLER,
+            catch_type: Some("java/lang/NullPointerException".to_string()),
+        }],
+        attributes: vec![],
+    };
+    Class {
+        version: Version::new(0xCAFEBABE, 52, 0),
+        access_flags: vec![ClassAccessModifier::PUBLIC],
+        this_class: "ComputedFrames".to_string(),
+        super_class: Some("java/lang/Object".to_string()),
+        interfaces: vec![],
+        fields: vec![],
+        methods: vec![Method {
+            access_flags: vec![MethodAccessModifier::PUBLIC, MethodAccessModifier::STATIC],
+            name: "main".to_string(),
+            descriptor: "([Ljava/lang/String;)V".parse().unwrap(),
+            attributes: vec![Attribute::Code(code)],
+        }],
+        attributes: vec![],
+        constant_pool: None,
+    }
+}
+
+#[test]
+fn computed_frames_pass_verification() {
+    let mut writer = ByteWriter::new().compute_frames(Classes::new());
+    writer.try_write(class(main_code())).unwrap();
+    let bytes: Vec<u8> = writer.into();
+    let directory = write_classes([("ComputedFrames", bytes.clone())]);
+    assert_eq!(
+        run_java_with_args(&directory, "ComputedFrames", &["a", "b"]),
+        "a\nb\ncaught\n"
+    );
+
+    let class = read(&bytes);
+    let Attribute::Code(code) = &class.methods[0].attributes[0] else {
+        panic!("Expected Code, found {:?}", class.methods[0].attributes)
+    };
+    let ops: Vec<&OpcodeInfo> = code
+        .code
+        .iter()
+        .filter_map(|instruction| match instruction {
+            Instruction::Op(op) => Some(op),
+            _ => None,
+        })
+        .collect();
+    // Unreachable code is replaced with `nop`s and an `athrow`
+    assert_eq!(ops[20..22], [&nop, &athrow]);
+    assert_eq!(ops[31..33], [&nop, &athrow]);
+    // ... and taken out of the exception range
+    assert_eq!(code.exception_table.len(), 1);
+    assert_eq!(code.exception_table[0].start.0, 45);
+    assert_eq!(code.exception_table[0].end.0, 47);
+
+    let [Attribute::StackMapTable(frames)] = &code.attributes[..] else {
+        panic!("Expected StackMapTable, found {:?}", code.attributes)
+    };
+    let labels: Vec<Label> = frames.iter().map(|frame| frame.label()).collect();
+    let new_label = Label(32);
+    assert_eq!(
+        *frames,
+        vec![
+            append_frame {
+                label: labels[0],
+                locals: vec![Long, Integer],
+            },
+            full_frame {
+                label: labels[1],
+                locals: vec![],
+                stack: vec![Object("java/lang/Throwable".to_string())],
+            },
+            // Relative to the unreachable code's frame
+            append_frame {
+                label: labels[2],
+                locals: vec![Object("[Ljava/lang/String;".to_string()), Long, Integer],
+            },
+            full_frame {
+                label: labels[3],
+                locals: vec![Object("[Ljava/lang/String;".to_string()), Long, Integer],
+                stack: vec![Uninitialized(new_label), Uninitialized(new_label)],
+            },
+            full_frame {
+                label: labels[4],
+                locals: vec![],
+                stack: vec![Object("java/lang/Throwable".to_string())],
+            },
+            full_frame {
+                label: labels[5],
+                locals: vec![Object("[Ljava/lang/String;".to_string()), Long, Integer],
+                stack: vec![Object("java/lang/NullPointerException".to_string())],
+            },
+        ]
+    );
+}
+
+#[test]
+fn broken_code_is_reported() {
+    let mut class = class(vec![pop.into(), r#return.into()]);
+    let Attribute::Code(code) = &mut class.methods[0].attributes[0] else {
+        unreachable!()
+    };
+    code.exception_table.clear();
+    let error = compute_frames(&mut class, &Classes::new()).unwrap_err();
+    assert_eq!(error.method, "main([Ljava/lang/String;)V");
+    assert_eq!(error.instruction, Some(0));
+    assert_eq!(error.error, TypeError::StackUnderflow);
+}
+
+#[test]
+fn writer_reports_broken_code() {
+    let mut class = class(vec![pop.into(), r#return.into()]);
+    let Attribute::Code(code) = &mut class.methods[0].attributes[0] else {
+        unreachable!()
+    };
+    code.exception_table.clear();
+    let mut writer = ByteWriter::new().compute_frames(Classes::new());
+    let Err(ClassFileError::AnalysisFailed { error }) = writer.try_write(class) else {
+        panic!("Expected the analysis to fail")
+    };
+    assert_eq!(error.instruction, Some(0));
+    assert_eq!(error.error, TypeError::StackUnderflow);
+}
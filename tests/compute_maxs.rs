@@ -0,0 +1,119 @@
+use rusty_javap::analysis::maxs::compute_maxs;
+use rusty_javap::analysis::TypeError;
+use rusty_javap::bytecode::reader::{ByteReader, Take};
+use rusty_javap::bytecode::writer::ByteWriter;
+use rusty_javap::error::ClassFileError;
+use rusty_javap::model::attrs::code::exception_table::ExceptionTableElement;
+use rusty_javap::model::attrs::code::OpcodeInfo::{
+    dload_1, dstore, dup, goto, iconst_0, pop, pop2, r#return,
+};
+use rusty_javap::model::attrs::code::{Code, Label};
+use rusty_javap::model::attrs::Attribute;
+use rusty_javap::model::class::{Class, ClassAccessModifier, Version};
+use rusty_javap::model::method::{Method, MethodAccessModifier};
+
+const EXAMPLE: &[u8] = include_bytes!("./Example.class");
+
+fn maxs(class: &Class) -> Vec<(u16, u16)> {
+    class
+        .methods
+        .iter()
+        .flat_map(|method| &method.attributes)
+        .filter_map(|attribute| match attribute {
+            Attribute::Code(code) => Some((code.max_stack, code.max_locals)),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn maxs_match_javacs() {
+    let mut reader = ByteReader::from(EXAMPLE.to_vec());
+    let mut class: Class = reader.take().unwrap();
+    let javacs = maxs(&class);
+    for method in &mut class.methods {
+        for attribute in &mut method.attributes {
+            if let Attribute::Code(code) = attribute {
+                code.max_stack = 0;
+                code.max_locals = 0;
+            }
+        }
+    }
+    compute_maxs(&mut class).unwrap();
+    assert_eq!(maxs(&class), javacs);
+}
+
+const START: Label = Label(1000);
+const END: Label = Label(1001);
+const HANDLER: Label = Label(1002);
+/// Not placed in the code
+const NOWHERE: Label = Label(1003);
+
+/// `static void f(int, double)`, storing another double after its arguments,
+/// whose exception handler takes more of the stack than the rest of the code
+fn class() -> Class {
+    let code = Code {
+        max_stack: 0,
+        max_locals: 0,
+        code: vec![
+            START.into(),
+            dload_1.into(),
+            dstore { index: 3 }.into(),
+            END.into(),
+            r#return.into(),
+            HANDLER.into(),
+            iconst_0.into(),
+            dup.into(),
+            pop2.into(),
+            pop.into(),
+            r#return.into(),
+        ],
+        exception_table: vec![ExceptionTableElement {
+            start: START,
+            end: END,
+            handler: HANDLER,
+            catch_type: None,
+        }],
+        attributes: vec![],
+    };
+    Class {
+        version: Version::new(0xCAFEBABE, 49, 0),
+        access_flags: vec![ClassAccessModifier::PUBLIC],
+        this_class: "Maxs".to_string(),
+        super_class: Some("java/lang/Object".to_string()),
+        interfaces: vec![],
+        fields: vec![],
+        methods: vec![Method {
+            access_flags: vec![MethodAccessModifier::STATIC],
+            name: "f".to_string(),
+            descriptor: "(ID)V".parse().unwrap(),
+            attributes: vec![Attribute::Code(code)],
+        }],
+        attributes: vec![],
+        constant_pool: None,
+    }
+}
+
+#[test]
+fn writer_computes_maxs() {
+    let mut writer = ByteWriter::new().compute_maxs();
+    writer.try_write(class()).unwrap();
+    let mut reader = ByteReader::from(Vec::<u8>::from(writer));
+    let class: Class = reader.take().unwrap();
+    // The handler's exception, an int and its copy; the doubles take locals 1-2 and 3-4
+    assert_eq!(maxs(&class), vec![(3, 5)]);
+}
+
+#[test]
+fn writer_reports_code_it_cant_analyze() {
+    let mut class = class();
+    let Attribute::Code(code) = &mut class.methods[0].attributes[0] else {
+        unreachable!()
+    };
+    code.code.insert(0, goto { branch: NOWHERE }.into());
+    let mut writer = ByteWriter::new().compute_maxs();
+    let Err(ClassFileError::AnalysisFailed { error }) = writer.try_write(class) else {
+        panic!("Expected the analysis to fail")
+    };
+    assert_eq!(error.error, TypeError::UnknownLabel(NOWHERE));
+}
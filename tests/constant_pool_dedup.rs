@@ -0,0 +1,79 @@
+use rusty_javap::bytecode::reader::{ByteReader, Take};
+use rusty_javap::bytecode::writer::ByteWriter;
+use rusty_javap::error::ClassFileError;
+use rusty_javap::model::attrs::constant_value::ConstantValue;
+use rusty_javap::model::attrs::Attribute;
+use rusty_javap::model::class::{Class, ClassAccessModifier, Version};
+use rusty_javap::model::field::{Field, FieldAccessModifier};
+
+fn constant_pool_count(bytes: &[u8]) -> u16 {
+    u16::from_be_bytes([bytes[8], bytes[9]])
+}
+
+#[test]
+fn rewritten_pool_is_no_larger_than_javacs() {
+    let bytes = include_bytes!("./Example.class");
+    let mut reader: ByteReader = bytes.to_vec().into();
+    let class: Class = reader.take().unwrap();
+
+    let mut writer = ByteWriter::new();
+    writer.try_write(class).unwrap();
+    let rewritten: Vec<u8> = writer.into();
+    assert!(constant_pool_count(&rewritten) <= constant_pool_count(bytes));
+}
+
+fn field(name: &str) -> Field {
+    Field::new(
+        vec![FieldAccessModifier::PUBLIC],
+        name.to_string(),
+        "Ljava/lang/Object;".parse().unwrap(),
+        vec![],
+    )
+}
+
+fn class_with_fields(fields: Vec<Field>) -> Class {
+    Class {
+        version: Version::new(0xCAFEBABE, 52, 0),
+        access_flags: vec![ClassAccessModifier::PUBLIC],
+        this_class: "java/lang/Object".to_string(),
+        super_class: Some("java/lang/Object".to_string()),
+        interfaces: vec![],
+        fields,
+        methods: vec![],
+        attributes: vec![],
+        constant_pool: None,
+    }
+}
+
+#[test]
+fn equal_constants_share_an_entry() {
+    let class = class_with_fields((0..10).map(|i| field(&format!("field{}", i))).collect());
+
+    let mut writer = ByteWriter::new();
+    writer.try_write(class).unwrap();
+    let bytes: Vec<u8> = writer.into();
+    // The class name and its Class constant, the descriptor, and the 10 field names
+    assert_eq!(constant_pool_count(&bytes), 1 + 2 + 1 + 10);
+}
+
+#[test]
+fn full_constant_pool_is_an_error() {
+    // Slot 0, the class name and its Class constant, and the descriptor take 4 entries
+    let fields = |count: usize| (0..count).map(|i| field(&format!("field{}", i))).collect();
+
+    let mut writer = ByteWriter::new();
+    writer.try_write(class_with_fields(fields(65531))).unwrap();
+    let bytes: Vec<u8> = writer.into();
+    assert_eq!(constant_pool_count(&bytes), u16::MAX);
+
+    let mut writer = ByteWriter::new();
+    let result = writer.try_write(class_with_fields(fields(65532)));
+    assert_eq!(result, Err(ClassFileError::ConstantPoolFull));
+
+    // A long needs two entries, but only one is left
+    let mut fields: Vec<Field> = fields(65529);
+    fields[65528].attributes = vec![Attribute::ConstantValue(ConstantValue::Long(0))];
+    let mut writer = ByteWriter::new();
+    let result = writer.try_write(class_with_fields(fields));
+    assert_eq!(result, Err(ClassFileError::ConstantPoolFull));
+}
@@ -0,0 +1,98 @@
+mod common;
+
+use common::{read, run_java, write, write_classes};
+use rusty_javap::bytecode::reader::{ByteReader, Take};
+use rusty_javap::constant_pool::CpTag;
+use rusty_javap::error::ClassFileError;
+use rusty_javap::model::attrs::constant_value::ConstantValue;
+use rusty_javap::model::attrs::Attribute;
+use rusty_javap::model::class::Class;
+use rusty_javap::model::field::{Field, FieldAccessModifier};
+
+// Compiled with `javac --release 17` from
+// ```java
+// public class Constants {
+//     static final int INT = -5;
+//     static final long LONG = 1L << 40;
+//     static final float FLOAT = 1.5f;
+//     static final double DOUBLE = -0.25;
+//     static final String STRING = "five";
+//     static final boolean BOOLEAN = true;
+//     static final char CHAR = 'x';
+//
+//     public static void main(String[] args) throws Exception {
+//         for (String name : new String[] {"INT", "LONG", "FLOAT", "DOUBLE", "STRING", "BOOLEAN", "CHAR"}) {
+//             System.out.println(Constants.class.getDeclaredField(name).get(null));
+//         }
+//     }
+// }
+// ```
+const CONSTANTS: &[u8] = include_bytes!("./constants/Constants.class");
+
+fn constant_value(field: &Field) -> &ConstantValue {
+    field
+        .attributes
+        .iter()
+        .find_map(|attribute| match attribute {
+            Attribute::ConstantValue(value) => Some(value),
+            _ => None,
+        })
+        .unwrap()
+}
+
+#[test]
+fn constant_values_are_typed() {
+    let class: Class = read(CONSTANTS);
+    let values: Vec<(&str, &ConstantValue)> = class
+        .fields
+        .iter()
+        .map(|field| (field.name.as_str(), constant_value(field)))
+        .collect();
+    assert_eq!(
+        values,
+        vec![
+            ("INT", &ConstantValue::Integer(-5)),
+            ("LONG", &ConstantValue::Long(1 << 40)),
+            ("FLOAT", &ConstantValue::Float(1.5)),
+            ("DOUBLE", &ConstantValue::Double(-0.25)),
+            ("STRING", &ConstantValue::String("five".into())),
+            ("BOOLEAN", &ConstantValue::Integer(1)),
+            ("CHAR", &ConstantValue::Integer('x' as i32)),
+        ]
+    );
+}
+
+#[test]
+fn constant_values_are_rewritten() {
+    let mut class: Class = read(CONSTANTS);
+    class.fields[0].attributes = vec![Attribute::ConstantValue(ConstantValue::Integer(7))];
+
+    let directory = write_classes([("Constants", write(class))]);
+    assert_eq!(
+        run_java(&directory, "Constants"),
+        "7\n1099511627776\n1.5\n-0.25\nfive\ntrue\nx\n"
+    );
+}
+
+#[test]
+fn constant_values_must_fit_static_fields() {
+    let mut class: Class = read(CONSTANTS);
+    class.fields[0].attributes = vec![Attribute::ConstantValue(ConstantValue::String("-5".into()))];
+    let result: Result<Class, _> = ByteReader::from(write(class)).take();
+    let error = result.unwrap_err();
+    assert_eq!(
+        error.error,
+        ClassFileError::ConstantValueMismatch {
+            descriptor: "I".to_string(),
+            found: CpTag::String,
+        }
+    );
+    assert_eq!(error.location.path, vec!["fields[0]"]);
+
+    // Instance fields ignore their constant value
+    let mut class: Class = read(CONSTANTS);
+    class.fields[0].access_flags = vec![FieldAccessModifier::FINAL];
+    class.fields[0].attributes = vec![Attribute::ConstantValue(ConstantValue::Long(5))];
+    let class = read(&write(class));
+    assert_eq!(constant_value(&class.fields[0]), &ConstantValue::Long(5));
+}
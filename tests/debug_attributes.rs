@@ -0,0 +1,317 @@
+mod common;
+
+use common::{read, temp_dir, write};
+use rusty_javap::bytecode::reader::{ByteReader, Take};
+use rusty_javap::model::attrs::code::{Code, Label, OpcodeInfo::nop};
+use rusty_javap::model::attrs::local_variable_type_table::{
+    LocalVariableTypeTable, LocalVariableTypeTableElement,
+};
+use rusty_javap::model::attrs::source_debug_extension::{
+    FileInfo, LineInfo, SourceDebugExtension, SourceMap, Stratum,
+};
+use rusty_javap::model::attrs::Attribute;
+use rusty_javap::model::class::Class;
+use std::process::Command;
+
+// Compiled with `javac --release 17 -g` from
+// ```java
+// public class Generic {
+//     static <T> List<T> wrap(T item) {
+//         List<T> list = new ArrayList<>();
+//         list.add(item);
+//         return list;
+//     }
+// }
+// ```
+const GENERIC: &[u8] = include_bytes!("./debug/Generic.class");
+
+fn wrap_code(class: &mut Class) -> &mut Code {
+    let method = class
+        .methods
+        .iter_mut()
+        .find(|method| method.name == "wrap")
+        .unwrap();
+    method
+        .attributes
+        .iter_mut()
+        .find_map(|attribute| match attribute {
+            Attribute::Code(code) => Some(code),
+            _ => None,
+        })
+        .unwrap()
+}
+
+fn local_variable_types(code: &Code) -> &LocalVariableTypeTable {
+    code.attributes
+        .iter()
+        .find_map(|attribute| match attribute {
+            Attribute::LocalVariableTypeTable(table) => Some(table),
+            _ => None,
+        })
+        .unwrap()
+}
+
+/// The table of `wrap`, if its code starts at [pc]
+fn wrap_local_variable_types(pc: u32) -> LocalVariableTypeTable {
+    vec![
+        LocalVariableTypeTableElement {
+            start: Label(pc),
+            end: Label(pc + 18),
+            name: "item".to_string(),
+            signature: "TT;".to_string(),
+            index: 0,
+        },
+        LocalVariableTypeTableElement {
+            start: Label(pc + 8),
+            end: Label(pc + 18),
+            name: "list".to_string(),
+            signature: "Ljava/util/List<TT;>;".to_string(),
+            index: 1,
+        },
+    ]
+}
+
+#[test]
+fn local_variable_types_follow_edits() {
+    let mut class = read(GENERIC);
+    assert_eq!(
+        *local_variable_types(wrap_code(&mut class)),
+        wrap_local_variable_types(0)
+    );
+
+    wrap_code(&mut class).code.insert(0, nop.into());
+    let mut class = read(&write(class));
+    assert_eq!(
+        *local_variable_types(wrap_code(&mut class)),
+        wrap_local_variable_types(1)
+    );
+}
+
+/// As Kotlin lays it out, with an `*E` after each stratum
+const KOTLIN_SMAP: &str = "SMAP
+Generic.java
+Kotlin
+*S Kotlin
+*F
++ 1 Generic.kt
+Generic
++ 2 Inline.kt
+InlineKt
+*L
+1#1,6:1
+3#2,2:7,2
+*E
+*S KotlinDebug
+*F
++ 1 Generic.kt
+Generic
+*L
+4#1:7
+*E
+";
+
+/// As JSR-45 lays it out
+const SMAP: &str = "SMAP
+Generic.java
+Kotlin
+*S Kotlin
+*F
++ 1 Generic.kt
+Generic
++ 2 Inline.kt
+InlineKt
+*L
+1#1,6:1
+3#2,2:7,2
+*S KotlinDebug
+*F
++ 1 Generic.kt
+Generic
+*L
+4#1:7
+*E
+";
+
+fn file(id: u32, name: &str, path: &str) -> FileInfo {
+    FileInfo {
+        id,
+        name: name.to_string(),
+        path: Some(path.to_string()),
+    }
+}
+
+fn line(
+    input_start_line: u32,
+    file_id: u32,
+    repeat_count: u32,
+    output_start_line: u32,
+    output_line_increment: u32,
+) -> LineInfo {
+    LineInfo {
+        input_start_line,
+        file_id,
+        repeat_count,
+        output_start_line,
+        output_line_increment,
+    }
+}
+
+#[test]
+fn source_maps_are_parsed() {
+    let source_map = SourceMap::parse(KOTLIN_SMAP).unwrap();
+    assert_eq!(
+        source_map,
+        SourceMap::new(
+            "Generic.java".to_string(),
+            "Kotlin".to_string(),
+            vec![
+                Stratum {
+                    name: "Kotlin".to_string(),
+                    files: vec![
+                        file(1, "Generic.kt", "Generic"),
+                        file(2, "Inline.kt", "InlineKt")
+                    ],
+                    lines: vec![line(1, 1, 6, 1, 1), line(3, 2, 2, 7, 2)],
+                },
+                Stratum {
+                    name: "KotlinDebug".to_string(),
+                    files: vec![file(1, "Generic.kt", "Generic")],
+                    lines: vec![line(4, 1, 1, 7, 1)],
+                },
+            ],
+        )
+    );
+    assert_eq!(source_map.to_string(), SMAP);
+    assert_eq!(SourceMap::parse(SMAP), Some(source_map.clone()));
+
+    let mapped = |output_line| {
+        source_map
+            .map_line("Kotlin", output_line)
+            .map(|(file, line)| (file.name.as_str(), line))
+    };
+    assert_eq!(mapped(3), Some(("Generic.kt", 3)));
+    // Inlined, two output lines per input line
+    assert_eq!(mapped(7), Some(("Inline.kt", 3)));
+    assert_eq!(mapped(10), Some(("Inline.kt", 4)));
+    assert_eq!(mapped(11), None);
+
+    assert_eq!(
+        SourceMap::parse("SMAP\nGeneric.java\nKotlin\n*S Kotlin\n"),
+        None
+    );
+    assert_eq!(
+        SourceMap::parse("SMAP\nA.java\nJSP\n*V\nvendor\n*E\n"),
+        None
+    );
+}
+
+#[test]
+fn source_debug_extensions_are_written() {
+    let directory = temp_dir();
+    let source_map = SourceMap::parse(KOTLIN_SMAP).unwrap();
+    // A map that wasn't parsed from text is laid out as JSR-45 lays it out
+    let built = SourceMap::new(
+        source_map.output_file.clone(),
+        source_map.default_stratum.clone(),
+        source_map.strata.clone(),
+    );
+    for (extension, expected) in [
+        (SourceDebugExtension::SourceMap(source_map), KOTLIN_SMAP),
+        (SourceDebugExtension::SourceMap(built), SMAP),
+        (
+            SourceDebugExtension::Other(b"Not a source map".to_vec()),
+            "Not a source map",
+        ),
+    ] {
+        let mut class = read(GENERIC);
+        class
+            .attributes
+            .push(Attribute::SourceDebugExtension(extension.clone()));
+        let written = write(class);
+
+        let class = read(&written);
+        assert!(matches!(
+            class.attributes.last(),
+            Some(Attribute::SourceDebugExtension(read)) if *read == extension
+        ));
+
+        let path = directory.join("Generic.class");
+        std::fs::write(&path, written).unwrap();
+        let output = Command::new("javap").arg("-v").arg(&path).output().unwrap();
+        assert!(output.status.success());
+        let javap = String::from_utf8_lossy(output.stdout.as_slice()).to_string();
+        let shown: String = javap
+            .lines()
+            .skip_while(|line| *line != "SourceDebugExtension:")
+            .skip(1)
+            .take_while(|line| line.starts_with("  "))
+            .map(|line| format!("{}\n", line.trim_start()))
+            .collect();
+        assert_eq!(shown.trim_end(), expected.trim_end());
+    }
+}
+
+/// As kotlinc writes it for a function with two inlined calls: every line info names its
+/// file, even when it's the previous line's, and each stratum ends with `*E`
+const KOTLINC_SMAP: &str = "SMAP
+Generic.kt
+Kotlin
+*S Kotlin
+*F
++ 1 Generic.kt
+GenericKt
++ 2 _Collections.kt
+kotlin/collections/CollectionsKt___CollectionsKt
+*L
+1#1,12:1
+1863#2,2:13
+*E
+*S KotlinDebug
+*F
++ 1 Generic.kt
+GenericKt
+*L
+7#1:13
+8#1:14
+*E
+";
+
+#[test]
+fn unedited_source_maps_are_written_unchanged() {
+    let mut class = read(GENERIC);
+    class.attributes.push(Attribute::SourceDebugExtension(
+        SourceDebugExtension::Other(KOTLINC_SMAP.as_bytes().to_vec()),
+    ));
+    let written = write(class);
+
+    // Lossless mode writes the whole class back as it was
+    let mut reader = ByteReader::from(written.clone()).lossless();
+    let class: Class = reader.take().unwrap();
+    assert!(matches!(
+        class.attributes.last(),
+        Some(Attribute::SourceDebugExtension(
+            SourceDebugExtension::SourceMap(_)
+        ))
+    ));
+    assert!(write(class) == written);
+
+    // Without it the constant pool is rebuilt, but the map is still written as it was
+    let contains = |bytes: &[u8], text: &str| {
+        bytes
+            .windows(text.len())
+            .any(|window| window == text.as_bytes())
+    };
+    assert!(contains(&write(read(&written)), KOTLINC_SMAP));
+
+    // Once edited, the map is laid out anew
+    let mut class = read(&written);
+    let Some(Attribute::SourceDebugExtension(SourceDebugExtension::SourceMap(source_map))) =
+        class.attributes.last_mut()
+    else {
+        panic!("Expected a source map, found {:?}", class.attributes)
+    };
+    source_map.strata[1].lines[1].output_start_line = 15;
+    let edited = source_map.to_string();
+    assert!(edited.contains("7#1:13\n8:15\n"));
+    assert!(contains(&write(class), &edited));
+}
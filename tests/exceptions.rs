@@ -0,0 +1,64 @@
+mod common;
+
+use common::{read, run_java, write, write_classes};
+use rusty_javap::model::attrs::Attribute;
+
+// Compiled with `javac --release 17 -g:none` from
+// ```java
+// public class Throwing {
+//     static void io() throws IOException, InterruptedException {}
+//
+//     public static void main(String[] args) throws ReflectiveOperationException {
+//         System.out.println(Arrays.toString(Throwing.class.getDeclaredMethod("io").getExceptionTypes()));
+//         System.out.println(Arrays.toString(Throwing.class.getDeclaredMethod("main", String[].class).getExceptionTypes()));
+//     }
+// }
+// ```
+const THROWING: &[u8] = include_bytes!("./exceptions/Throwing.class");
+
+#[test]
+fn declared_exceptions_are_resolved() {
+    let class = read(THROWING);
+    let exceptions: Vec<(&str, Option<&Vec<String>>)> = class
+        .methods
+        .iter()
+        .map(|method| {
+            let exceptions = method
+                .attributes
+                .iter()
+                .find_map(|attribute| match attribute {
+                    Attribute::Exceptions(exceptions) => Some(exceptions),
+                    _ => None,
+                });
+            (method.name.as_str(), exceptions)
+        })
+        .collect();
+    assert_eq!(
+        exceptions,
+        [
+            ("<init>", None),
+            (
+                "io",
+                Some(&vec![
+                    "java/io/IOException".to_string(),
+                    "java/lang/InterruptedException".to_string()
+                ])
+            ),
+            (
+                "main",
+                Some(&vec!["java/lang/ReflectiveOperationException".to_string()])
+            ),
+        ]
+    );
+}
+
+#[test]
+fn rewritten_exceptions_are_reflected() {
+    // Without lossless mode, the constant pool is built from scratch
+    let directory = write_classes([("Throwing", write(read(THROWING)))]);
+    assert_eq!(
+        run_java(&directory, "Throwing"),
+        "[class java.io.IOException, class java.lang.InterruptedException]\n\
+         [class java.lang.ReflectiveOperationException]\n"
+    );
+}
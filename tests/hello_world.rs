@@ -1,12 +1,13 @@
-use rusty_javap::bytecode::writer::{ByteWriter, Writeable};
+mod common;
+
+use common::{run_java, write_classes};
+use rusty_javap::bytecode::writer::{ByteWriter, TryWriteable};
 use rusty_javap::model::attrs::code::OpcodeInfo::{getstatic, invokevirtual, ldc, r#return, swap};
 use rusty_javap::model::attrs::code::{ClassRef, Code, FieldRef, MethodRef};
 use rusty_javap::model::attrs::Attribute;
 use rusty_javap::model::class::{Class, ClassAccessModifier, Version};
+use rusty_javap::model::constant::Loadable;
 use rusty_javap::model::method::{Method, MethodAccessModifier};
-use std::fs::File;
-use std::io::Write;
-use std::process::Command;
 
 #[test]
 fn hello_world() {
@@ -14,21 +15,23 @@ fn hello_world() {
         max_stack: 3,
         max_locals: 1,
         code: vec![
-            // TODO: fix ldc to work properly with constants rather than guessing indexes
-            ldc { index: 2 }, // Assume that the current class is at index 2
-            invokevirtual { method: MethodRef { class: ClassRef("java/lang/Class".to_string()), name: "getName".to_string(), descriptor: "()Ljava/lang/String;".to_string() }},
-            getstatic { field: FieldRef { class: ClassRef("java/lang/System".to_string()), name: "out".to_string(), descriptor: "Ljava/io/PrintStream;".to_string()} },
+            ldc { constant: Loadable::Class("HelloWorld".to_string()) },
+            invokevirtual { method: MethodRef { class: ClassRef("java/lang/Class".to_string()), name: "getName".to_string(), descriptor: "()Ljava/lang/String;".parse().unwrap(), interface: false }},
+            getstatic { field: FieldRef { class: ClassRef("java/lang/System".to_string()), name: "out".to_string(), descriptor: "Ljava/io/PrintStream;".parse().unwrap()} },
             swap,
-            invokevirtual { method: MethodRef { class: ClassRef("java/io/PrintStream".to_string()), name: "println".to_string(), descriptor: "(Ljava/lang/String;)V".to_string()}},
+            invokevirtual { method: MethodRef { class: ClassRef("java/io/PrintStream".to_string()), name: "println".to_string(), descriptor: "(Ljava/lang/String;)V".parse().unwrap(), interface: false }},
             r#return,
-        ],
+        ]
+        .into_iter()
+        .map(Into::into)
+        .collect(),
         exception_table: vec![],
         attributes: vec![],
     };
     let main = Method {
         access_flags: vec![MethodAccessModifier::PUBLIC,  MethodAccessModifier::STATIC],
         name: "main".to_string(),
-        descriptor: "([Ljava/lang/String;)V".to_string(),
+        descriptor: "([Ljava/lang/String;)V".parse().unwrap(),
         attributes: vec![Attribute::Code(code)],
     };
     let class = Class {
@@ -40,22 +43,16 @@ fn hello_world() {
         fields: vec![],
         methods: vec![main],
         attributes: vec![],
+        constant_pool: None,
     };
 
     let mut writer = ByteWriter::new();
-    class.write(&mut writer);
+    class.try_write(&mut writer).unwrap();
 
     let class_bytes: Vec<u8> = writer.into();
-    let path = "HelloWorld.class";
-    let mut file = File::create(path).expect("Couldn't create class file");
-    file.write_all(&class_bytes).expect("Couldn't write class file");
+    let directory = write_classes([("HelloWorld", class_bytes)]);
 
-    let output = Command::new("java").args(["HelloWorld"]).output().unwrap();
-    let stdout = String::from_utf8_lossy(output.stdout.as_slice());
+    let stdout = run_java(&directory, "HelloWorld");
     println!("Got output: {}", stdout);
-    if output.stderr.len() > 0 {
-        eprintln!("Got stderr: {}", String::from_utf8_lossy(output.stderr.as_slice()));
-    }
-    assert!(output.status.success());
     assert_eq!(stdout, "HelloWorld\n");
 }
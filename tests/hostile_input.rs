@@ -0,0 +1,56 @@
+use rusty_javap::bytecode::reader::{ByteReader, Take};
+use rusty_javap::error::ParseError;
+use rusty_javap::model::class::Class;
+
+fn parse(bytes: Vec<u8>) -> Result<Class, ParseError> {
+    let mut reader: ByteReader = bytes.into();
+    reader.take()
+}
+
+#[test]
+fn every_truncation_fails_cleanly() {
+    let bytes = include_bytes!("./Example.class");
+    for length in 0..bytes.len() {
+        assert!(parse(bytes[..length].to_vec()).is_err());
+    }
+}
+
+#[test]
+fn single_byte_corruptions_never_panic() {
+    let bytes = include_bytes!("./Example.class");
+    for offset in 0..bytes.len() {
+        for replacement in [0x00, 0x01, 0x7F, 0x80, 0xFF, bytes[offset] ^ 0x40] {
+            let mut corrupted = bytes.to_vec();
+            corrupted[offset] = replacement;
+            let _ = parse(corrupted);
+        }
+    }
+}
+
+#[test]
+fn deeply_nested_attributes_fail_cleanly() {
+    // A `Code` attribute whose only nested attribute is another `Code` attribute, and so on
+    let mut code: Vec<u8> = vec![0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+    for _ in 0..1000 {
+        let mut outer: Vec<u8> = vec![0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 1];
+        outer.extend_from_slice(&(code.len() as u32).to_be_bytes());
+        outer.extend_from_slice(&code);
+        code = outer;
+    }
+
+    let mut bytes: Vec<u8> = vec![0xCA, 0xFE, 0xBA, 0xBE, 0, 0, 0, 52];
+    bytes.extend_from_slice(&[0, 6]); // constant pool count
+    bytes.extend_from_slice(&[1, 0, 4, b'C', b'o', b'd', b'e']); // #1
+    bytes.extend_from_slice(&[1, 0, 1, b'A']); // #2
+    bytes.extend_from_slice(&[7, 0, 2]); // #3
+    bytes.extend_from_slice(&[1, 0, 1, b'm']); // #4
+    bytes.extend_from_slice(&[1, 0, 3, b'(', b')', b'V']); // #5
+    bytes.extend_from_slice(&[0, 0, 0, 3, 0, 0]); // access flags, this class, super class
+    bytes.extend_from_slice(&[0, 0, 0, 0]); // interfaces, fields
+    bytes.extend_from_slice(&[0, 1, 0, 0, 0, 4, 0, 5, 0, 1, 0, 1]); // one method with one attribute
+    bytes.extend_from_slice(&(code.len() as u32).to_be_bytes());
+    bytes.extend_from_slice(&code);
+    bytes.extend_from_slice(&[0, 0]); // class attributes
+
+    assert!(parse(bytes).is_err());
+}
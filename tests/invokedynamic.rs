@@ -0,0 +1,184 @@
+mod common;
+
+use common::{read, run_java, write, write_classes};
+use rusty_javap::model::attrs::code::OpcodeInfo::{
+    getstatic, invokedynamic, invokevirtual, ldc, r#return, swap,
+};
+use rusty_javap::model::attrs::code::{ClassRef, Code, FieldRef, Instruction, MethodRef};
+use rusty_javap::model::attrs::Attribute;
+use rusty_javap::model::class::{Class, ClassAccessModifier, Version};
+use rusty_javap::model::constant::{
+    BootstrapMethod, CallSite, Dynamic, Loadable, MethodHandle, ReferenceKind,
+};
+use rusty_javap::model::method::{Method, MethodAccessModifier};
+
+fn concat(recipe: &str) -> BootstrapMethod {
+    BootstrapMethod {
+        handle: MethodHandle {
+            kind: ReferenceKind::REF_invokeStatic,
+            class: "java/lang/invoke/StringConcatFactory".to_string(),
+            name: "makeConcatWithConstants".to_string(),
+            descriptor: "(Ljava/lang/invoke/MethodHandles$Lookup;Ljava/lang/String;Ljava/lang/invoke/MethodType;Ljava/lang/String;[Ljava/lang/Object;)Ljava/lang/invoke/CallSite;".to_string(),
+            interface: false,
+        },
+        arguments: vec![Loadable::String(recipe.into())],
+    }
+}
+
+fn call_site(recipe: &str) -> Instruction {
+    invokedynamic {
+        call_site: CallSite {
+            name: "makeConcatWithConstants".to_string(),
+            descriptor: "(Ljava/lang/String;)Ljava/lang/String;".parse().unwrap(),
+            bootstrap: concat(recipe),
+        },
+    }
+    .into()
+}
+
+fn null_string() -> Instruction {
+    ldc {
+        constant: Loadable::Dynamic(Dynamic {
+            name: "_".to_string(),
+            descriptor: "Ljava/lang/String;".parse().unwrap(),
+            bootstrap: BootstrapMethod {
+                handle: MethodHandle {
+                    kind: ReferenceKind::REF_invokeStatic,
+                    class: "java/lang/invoke/ConstantBootstraps".to_string(),
+                    name: "nullConstant".to_string(),
+                    descriptor: "(Ljava/lang/invoke/MethodHandles$Lookup;Ljava/lang/String;Ljava/lang/Class;)Ljava/lang/Object;".to_string(),
+                    interface: false,
+                },
+                arguments: vec![],
+            },
+        }),
+    }
+    .into()
+}
+
+fn println() -> Vec<Instruction> {
+    vec![
+        getstatic {
+            field: FieldRef {
+                class: ClassRef("java/lang/System".to_string()),
+                name: "out".to_string(),
+                descriptor: "Ljava/io/PrintStream;".parse().unwrap(),
+            },
+        }
+        .into(),
+        swap.into(),
+        invokevirtual {
+            method: MethodRef {
+                class: ClassRef("java/io/PrintStream".to_string()),
+                name: "println".to_string(),
+                descriptor: "(Ljava/lang/String;)V".parse().unwrap(),
+                interface: false,
+            },
+        }
+        .into(),
+    ]
+}
+
+fn class(code: Vec<Instruction>) -> Class {
+    let code = Code {
+        max_stack: 3,
+        max_locals: 1,
+        code,
+        exception_table: vec![],
+        attributes: vec![],
+    };
+    Class {
+        // Dynamic constants need Java 11
+        version: Version::new(0xCAFEBABE, 55, 0),
+        access_flags: vec![ClassAccessModifier::PUBLIC],
+        this_class: "Indy".to_string(),
+        super_class: Some("java/lang/Object".to_string()),
+        interfaces: vec![],
+        fields: vec![],
+        methods: vec![Method {
+            access_flags: vec![MethodAccessModifier::PUBLIC, MethodAccessModifier::STATIC],
+            name: "main".to_string(),
+            descriptor: "([Ljava/lang/String;)V".parse().unwrap(),
+            attributes: vec![Attribute::Code(code)],
+        }],
+        attributes: vec![Attribute::SourceFile("Indy.java".to_string())],
+        constant_pool: None,
+    }
+}
+
+fn main_code(class: Class) -> Vec<Instruction> {
+    match class
+        .methods
+        .into_iter()
+        .next()
+        .unwrap()
+        .attributes
+        .remove(0)
+    {
+        Attribute::Code(code) => code.code,
+        attribute => panic!("Expected Code, found {:?}", attribute),
+    }
+}
+
+fn code() -> Vec<Instruction> {
+    let mut code = vec![ldc {
+        constant: Loadable::String("world".into()),
+    }
+    .into()];
+    code.push(call_site("Hello, \u{1}!"));
+    code.extend(println());
+    code.push(null_string());
+    code.push(call_site("Hello, \u{1}!"));
+    code.extend(println());
+    code.push(r#return.into());
+    code
+}
+
+#[test]
+fn call_sites_round_trip() {
+    let read = read(&write(class(code())));
+    assert!(matches!(
+        read.attributes.as_slice(),
+        [Attribute::SourceFile(_), Attribute::BootstrapMethods]
+    ));
+    assert_eq!(main_code(read), code());
+}
+
+#[test]
+fn equal_bootstrap_methods_are_shared() {
+    let size = |code: Vec<Instruction>| write(class(code)).len();
+    let one = size(vec![call_site("\u{1}"), r#return.into()]);
+    let same = size(vec![
+        call_site("\u{1}"),
+        call_site("\u{1}"),
+        r#return.into(),
+    ]);
+    let different = size(vec![
+        call_site("\u{1}"),
+        call_site("<\u{1}>"),
+        r#return.into(),
+    ]);
+    // Only the 5-byte instruction is added
+    assert_eq!(same, one + 5);
+    assert!(different > same);
+}
+
+#[test]
+fn bootstrap_methods_are_only_written_when_used() {
+    let mut class = class(vec![r#return.into()]);
+    class.attributes.push(Attribute::BootstrapMethods);
+    let read = read(&write(class));
+    assert!(matches!(
+        read.attributes.as_slice(),
+        [Attribute::SourceFile(_)]
+    ));
+}
+
+#[test]
+fn call_sites_are_linked() {
+    let directory = write_classes([("Indy", write(class(code())))]);
+    assert_eq!(
+        run_java(&directory, "Indy"),
+        "Hello, world!\nHello, null!\n"
+    );
+}
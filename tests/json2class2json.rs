@@ -7,7 +7,9 @@ fn json2class2json() {
 
     let class: Class = serde_json::from_value(json_class.clone()).unwrap();
 
-    let written_json: serde_json::Value = serde_json::to_value(class).unwrap();
+    // Compare written text rather than `to_value`, which widens `f32`s to imprecise `f64`s
+    let written_json: serde_json::Value =
+        serde_json::from_str(&serde_json::to_string(&class).unwrap()).unwrap();
     assert_eq!(json_class, written_json)
 }
 
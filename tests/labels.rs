@@ -0,0 +1,201 @@
+mod common;
+
+use common::write;
+use rusty_javap::bytecode::reader::{ByteReader, Take};
+use rusty_javap::bytecode::writer::ByteWriter;
+use rusty_javap::error::ClassFileError;
+use rusty_javap::model::attrs::code::exception_table::ExceptionTableElement;
+use rusty_javap::model::attrs::code::OpcodeInfo::{
+    goto, goto_w, iconst_0, ifeq, ifne, nop, r#return,
+};
+use rusty_javap::model::attrs::code::{Code, Instruction, Label};
+use rusty_javap::model::attrs::line_number_table::LineNumberTableElement;
+use rusty_javap::model::attrs::Attribute;
+use rusty_javap::model::class::{Class, ClassAccessModifier, Version};
+use rusty_javap::model::method::{Method, MethodAccessModifier};
+
+fn class_with_code(code: Vec<Instruction>) -> Class {
+    let code = Code {
+        max_stack: 1,
+        max_locals: 0,
+        code,
+        exception_table: vec![],
+        attributes: vec![],
+    };
+    Class {
+        version: Version::new(0xCAFEBABE, 49, 0),
+        access_flags: vec![ClassAccessModifier::PUBLIC],
+        this_class: "Labels".to_string(),
+        super_class: Some("java/lang/Object".to_string()),
+        interfaces: vec![],
+        fields: vec![],
+        methods: vec![Method {
+            access_flags: vec![MethodAccessModifier::STATIC],
+            name: "run".to_string(),
+            descriptor: "()V".parse().unwrap(),
+            attributes: vec![Attribute::Code(code)],
+        }],
+        attributes: vec![],
+        constant_pool: None,
+    }
+}
+
+fn code_mut(class: &mut Class) -> &mut Code {
+    match &mut class.methods[0].attributes[0] {
+        Attribute::Code(code) => code,
+        attribute => panic!("Expected Code, found {:?}", attribute),
+    }
+}
+
+fn write_error(class: Class) -> ClassFileError {
+    ByteWriter::new().try_write(class).unwrap_err()
+}
+
+fn read(bytes: Vec<u8>) -> Vec<Instruction> {
+    let mut reader: ByteReader = bytes.into();
+    let class: Class = reader.take().unwrap();
+    match class
+        .methods
+        .into_iter()
+        .next()
+        .unwrap()
+        .attributes
+        .remove(0)
+    {
+        Attribute::Code(code) => code.code,
+        attribute => panic!("Expected Code, found {:?}", attribute),
+    }
+}
+
+#[test]
+fn branches_follow_inserted_instructions() {
+    let skip = Label(100);
+    let mut code: Vec<Instruction> = vec![goto { branch: skip }.into(), nop.into()];
+    code.push(skip.into());
+    code.push(r#return.into());
+
+    // Inserting an instruction before the target moves it
+    code.insert(1, nop.into());
+    let bytes = write(class_with_code(code));
+
+    // Labels read back are named after their pc: goto (3 bytes) + 2 nops
+    assert_eq!(
+        read(bytes),
+        vec![
+            goto { branch: Label(5) }.into(),
+            nop.into(),
+            nop.into(),
+            Label(5).into(),
+            r#return.into(),
+        ]
+    );
+}
+
+#[test]
+fn far_branches_are_widened() {
+    let far = Label(0);
+    let mut code: Vec<Instruction> = vec![iconst_0.into(), ifeq { branch: far }.into()];
+    code.extend(std::iter::repeat_n(Instruction::from(nop), 40_000));
+    code.push(goto { branch: far }.into());
+    code.push(far.into());
+    code.push(r#return.into());
+
+    let code = read(write(class_with_code(code)));
+    // ifeq far => ifne +8; goto_w far
+    assert_eq!(code[1], ifne { branch: Label(9) }.into());
+    assert_eq!(
+        code[2],
+        goto_w {
+            branch: Label(40_012)
+        }
+        .into()
+    );
+    assert_eq!(code[3], Label(9).into());
+    // goto far fits once it's past the nops
+    assert_eq!(
+        code[40_004],
+        goto {
+            branch: Label(40_012)
+        }
+        .into()
+    );
+    assert_eq!(code[40_005], Label(40_012).into());
+}
+
+#[test]
+fn branch_into_an_instruction_is_rejected() {
+    let mut bytes = write(class_with_code(vec![
+        goto { branch: Label(3) }.into(),
+        Label(3).into(),
+        r#return.into(),
+    ]));
+    // Point the goto at its own operand
+    let goto_offset = bytes
+        .windows(3)
+        .position(|window| window == [0xa7, 0x00, 0x03])
+        .unwrap();
+    bytes[goto_offset + 2] = 0x01;
+
+    let mut reader: ByteReader = bytes.into();
+    let error = reader.take().map(|_: Class| ()).unwrap_err();
+    assert_eq!(error.error, ClassFileError::InvalidCodeOffset { pc: 1 });
+}
+
+#[test]
+fn unplaced_labels_are_rejected() {
+    let class = class_with_code(vec![goto { branch: Label(3) }.into(), r#return.into()]);
+    assert_eq!(
+        write_error(class),
+        ClassFileError::UnplacedLabel { label: 3 }
+    );
+
+    let mut class = class_with_code(vec![Label(0).into(), r#return.into()]);
+    code_mut(&mut class).exception_table = vec![ExceptionTableElement {
+        start: Label(0),
+        end: Label(1),
+        handler: Label(0),
+        catch_type: None,
+    }];
+    assert_eq!(
+        write_error(class),
+        ClassFileError::UnplacedLabel { label: 1 }
+    );
+}
+
+#[test]
+fn labels_placed_twice_are_rejected() {
+    let class = class_with_code(vec![
+        Label(0).into(),
+        nop.into(),
+        Label(0).into(),
+        goto { branch: Label(0) }.into(),
+    ]);
+    assert_eq!(
+        write_error(class),
+        ClassFileError::DuplicateLabel { label: 0 }
+    );
+}
+
+#[test]
+fn pcs_past_a_u2_are_rejected() {
+    let far = Label(70_000);
+    let mut code: Vec<Instruction> = vec![Label(0).into()];
+    code.extend(std::iter::repeat_n(Instruction::from(nop), 70_000));
+    code.push(far.into());
+    code.push(r#return.into());
+    let mut class = class_with_code(code);
+    code_mut(&mut class).attributes = vec![Attribute::LineNumberTable(vec![
+        LineNumberTableElement {
+            start: Label(0),
+            line_number: 1,
+        },
+        LineNumberTableElement {
+            start: far,
+            line_number: 2,
+        },
+    ])];
+    assert_eq!(
+        write_error(class),
+        ClassFileError::PcOutOfRange { pc: 70_000 }
+    );
+}
@@ -0,0 +1,87 @@
+use rusty_javap::bytecode::reader::{ByteReader, Take};
+use rusty_javap::bytecode::writer::ByteWriter;
+use rusty_javap::constant_pool::ConstantPool;
+use rusty_javap::model::class::{Class, Version};
+use rusty_javap::model::field::{Field, FieldAccessModifier};
+
+const EXAMPLE: &[u8] = include_bytes!("./Example.class");
+
+/// Every class file the tests have, javac's output for all sorts of attributes
+const FIXTURES: &[&[u8]] = &[
+    include_bytes!("./Example.class"),
+    include_bytes!("./annotations/Annotated$Everything.class"),
+    include_bytes!("./annotations/Annotated$Invisible.class"),
+    include_bytes!("./annotations/Annotated.class"),
+    include_bytes!("./constants/Constants.class"),
+    include_bytes!("./debug/Generic.class"),
+    include_bytes!("./exceptions/Throwing.class"),
+    include_bytes!("./module/demo/api/Service.class"),
+    include_bytes!("./module/demo/impl/Main.class"),
+    include_bytes!("./module/demo/impl/ServiceImpl.class"),
+    include_bytes!("./module/demo/internal/Internal.class"),
+    include_bytes!("./module/module-info.class"),
+    include_bytes!("./nested/Nested$1.class"),
+    include_bytes!("./nested/Nested$Inner.class"),
+    include_bytes!("./nested/Nested$Leaf.class"),
+    include_bytes!("./nested/Nested.class"),
+    include_bytes!("./records/Point$Named.class"),
+    include_bytes!("./records/Point.class"),
+    include_bytes!("./signatures/Generics.class"),
+    include_bytes!("./type_annotations/TypeAnnotated$Nullable.class"),
+    include_bytes!("./type_annotations/TypeAnnotated.class"),
+];
+
+/// Offset of the first byte after the constant pool
+fn constant_pool_end(bytes: &[u8]) -> usize {
+    let mut reader = ByteReader::from(bytes.to_vec());
+    let _: Version = reader.take().unwrap();
+    let _: ConstantPool = reader.take().unwrap();
+    reader.position()
+}
+
+fn read_and_write(bytes: &[u8], edit: impl FnOnce(&mut Class)) -> Vec<u8> {
+    let mut reader = ByteReader::from(bytes.to_vec()).lossless();
+    let mut class: Class = reader.take().unwrap();
+    assert!(class.constant_pool.is_some());
+    edit(&mut class);
+
+    let mut writer = ByteWriter::new();
+    writer.try_write(class).unwrap();
+    writer.into()
+}
+
+#[test]
+fn original_constant_pool_is_kept() {
+    for (i, bytes) in FIXTURES.iter().enumerate() {
+        let written = read_and_write(bytes, |_| {});
+        assert!(written == *bytes, "fixture {} changed on write", i);
+    }
+}
+
+#[test]
+fn new_constants_are_appended() {
+    let written = read_and_write(EXAMPLE, |class| {
+        class.fields.push(Field::new(
+            vec![FieldAccessModifier::PRIVATE],
+            "added".to_string(),
+            "I".parse().unwrap(),
+            vec![],
+        ))
+    });
+    let end = constant_pool_end(EXAMPLE);
+    let count = |bytes: &[u8]| u16::from_be_bytes([bytes[8], bytes[9]]);
+    // "added" is new, while the descriptor "I" is already in the pool
+    assert!(count(&written) > count(EXAMPLE));
+    assert_eq!(written[10..end], EXAMPLE[10..end]);
+    let appended = &written[end..constant_pool_end(&written)];
+    assert!(appended
+        .windows(8)
+        .any(|window| window == [1, 0, 5, b'a', b'd', b'd', b'e', b'd']));
+}
+
+#[test]
+fn pool_is_rebuilt_by_default() {
+    let mut reader = ByteReader::from(EXAMPLE.to_vec());
+    let class: Class = reader.take().unwrap();
+    assert!(class.constant_pool.is_none());
+}
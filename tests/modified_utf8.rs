@@ -0,0 +1,52 @@
+use rusty_javap::bytecode::reader::{ByteReader, Take};
+use rusty_javap::bytecode::writer::ByteWriter;
+use rusty_javap::error::ClassFileError;
+use rusty_javap::model::class::{Class, ClassAccessModifier, Version};
+use rusty_javap::model::field::{Field, FieldAccessModifier};
+
+fn class_with_field(name: String) -> Class {
+    Class {
+        version: Version::new(0xCAFEBABE, 52, 0),
+        access_flags: vec![ClassAccessModifier::PUBLIC],
+        this_class: "Names".to_string(),
+        super_class: Some("java/lang/Object".to_string()),
+        interfaces: vec![],
+        fields: vec![Field::new(
+            vec![FieldAccessModifier::PUBLIC],
+            name,
+            "I".parse().unwrap(),
+            vec![],
+        )],
+        methods: vec![],
+        attributes: vec![],
+        constant_pool: None,
+    }
+}
+
+#[test]
+fn names_round_trip_through_modified_utf8() {
+    let name = "nul\0emoji😀é".to_string();
+    let mut writer = ByteWriter::new();
+    writer.try_write(class_with_field(name.clone())).unwrap();
+    let bytes: Vec<u8> = writer.into();
+    // Standard UTF-8 would contain a raw NUL and a 4-byte sequence
+    assert!(bytes.windows(2).any(|window| window == [0xC0, 0x80]));
+    assert!(!bytes.windows(4).any(|window| window == "😀".as_bytes()));
+
+    let mut reader: ByteReader = bytes.into();
+    let class: Class = reader.take().unwrap();
+    assert_eq!(class.fields[0].name, name);
+}
+
+#[test]
+fn strings_longer_than_a_u2_are_an_error() {
+    let mut writer = ByteWriter::new();
+    writer
+        .try_write(class_with_field("a".repeat(65535)))
+        .unwrap();
+
+    // Each NUL takes two bytes
+    let mut writer = ByteWriter::new();
+    let result = writer.try_write(class_with_field("\0".repeat(32768)));
+    assert_eq!(result, Err(ClassFileError::StringTooLong { length: 65536 }));
+}
@@ -0,0 +1,147 @@
+mod common;
+
+use common::{read, run_module, write, write_classes};
+use rusty_javap::model::attrs::module::{
+    Exports, Module, Opens, Provides, Requires, RequiresFlags,
+};
+use rusty_javap::model::attrs::Attribute;
+use rusty_javap::model::class::{Class, ClassAccessModifier, Version};
+
+// Compiled with `javac --release 17 -g:none` and packaged with
+// `jar --create --main-class demo.impl.Main --module-version 1.0`, which adds the
+// `ModulePackages` and `ModuleMainClass` attributes, from
+// ```java
+// module demo {
+//     requires transitive java.logging;
+//     requires static java.sql;
+//     exports demo.api;
+//     exports demo.internal to java.logging;
+//     opens demo.impl;
+//     uses demo.api.Service;
+//     provides demo.api.Service with demo.impl.ServiceImpl;
+// }
+// ```
+const MODULE_INFO: &[u8] = include_bytes!("./module/module-info.class");
+
+// `demo.impl.Main` prints its module's descriptor, sorted, and the services it finds
+const CLASSES: [(&str, &[u8]); 4] = [
+    (
+        "demo/api/Service",
+        include_bytes!("./module/demo/api/Service.class"),
+    ),
+    (
+        "demo/impl/Main",
+        include_bytes!("./module/demo/impl/Main.class"),
+    ),
+    (
+        "demo/impl/ServiceImpl",
+        include_bytes!("./module/demo/impl/ServiceImpl.class"),
+    ),
+    (
+        "demo/internal/Internal",
+        include_bytes!("./module/demo/internal/Internal.class"),
+    ),
+];
+
+fn requires(module: &str, requires_flags: Vec<RequiresFlags>) -> Requires {
+    Requires {
+        module: module.to_string(),
+        requires_flags,
+        version: Some("17.0.15".to_string()),
+    }
+}
+
+fn strings(strings: &[&str]) -> Vec<String> {
+    strings.iter().map(|string| string.to_string()).collect()
+}
+
+fn module() -> Module {
+    Module {
+        name: "demo".to_string(),
+        module_flags: vec![],
+        version: Some("1.0".to_string()),
+        requires: vec![
+            requires("java.base", vec![RequiresFlags::MANDATED]),
+            requires("java.logging", vec![RequiresFlags::TRANSITIVE]),
+            requires("java.sql", vec![RequiresFlags::STATIC_PHASE]),
+        ],
+        exports: vec![
+            Exports {
+                package: "demo/api".to_string(),
+                exports_flags: vec![],
+                exports_to: vec![],
+            },
+            Exports {
+                package: "demo/internal".to_string(),
+                exports_flags: vec![],
+                exports_to: strings(&["java.logging"]),
+            },
+        ],
+        opens: vec![Opens {
+            package: "demo/impl".to_string(),
+            opens_flags: vec![],
+            opens_to: vec![],
+        }],
+        uses: strings(&["demo/api/Service"]),
+        provides: vec![Provides {
+            service: "demo/api/Service".to_string(),
+            provides_with: strings(&["demo/impl/ServiceImpl"]),
+        }],
+    }
+}
+
+fn packages() -> Vec<String> {
+    strings(&["demo/api", "demo/impl", "demo/internal"])
+}
+
+#[test]
+fn module_attributes_are_resolved() {
+    let module_info: Class = read(MODULE_INFO);
+    assert_eq!(module_info.this_class, "module-info");
+    assert_eq!(module_info.super_class, None);
+    let [Attribute::Module(module_attribute), Attribute::ModulePackages(module_packages), Attribute::ModuleMainClass(main_class)] =
+        &module_info.attributes[..]
+    else {
+        panic!("Unexpected attributes {:?}", module_info.attributes)
+    };
+    assert_eq!(*module_attribute, module());
+    assert_eq!(*module_packages, packages());
+    assert_eq!(main_class, "demo/impl/Main");
+}
+
+#[test]
+fn generated_module_runs() {
+    let module_info = Class {
+        version: Version::new(0xCAFEBABE, 61, 0),
+        access_flags: vec![ClassAccessModifier::MODULE],
+        this_class: "module-info".to_string(),
+        super_class: None,
+        interfaces: vec![],
+        fields: vec![],
+        methods: vec![],
+        attributes: vec![
+            Attribute::Module(module()),
+            Attribute::ModulePackages(packages()),
+            Attribute::ModuleMainClass("demo/impl/Main".to_string()),
+        ],
+        constant_pool: None,
+    };
+    let classes = CLASSES.map(|(name, bytes)| (name, bytes.to_vec()));
+    let directory = write_classes(
+        classes
+            .into_iter()
+            .chain([("module-info", write(module_info))]),
+    );
+    assert_eq!(
+        run_module(&directory, "demo"),
+        "demo@1.0 demo.impl.Main
+[mandated java.base (@17.0.15), static java.sql (@17.0.15), transitive java.logging (@17.0.15)]
+[demo.api, demo.internal to [java.logging]]
+[demo.impl]
+[demo.api.Service]
+[demo.api.Service with [demo.impl.ServiceImpl]]
+[demo.api, demo.impl, demo.internal]
+implementation
+"
+    );
+}
@@ -0,0 +1,111 @@
+mod common;
+
+use common::{read, run_java, write, write_classes};
+use rusty_javap::model::attrs::enclosing_method::{EnclosingMethod, NameAndType};
+use rusty_javap::model::attrs::inner_classes::{InnerClass, InnerClassAccessFlags};
+use rusty_javap::model::attrs::Attribute;
+
+// Compiled with `javac --release 17 -g:none` from
+// ```java
+// public sealed class Nested permits Nested.Inner, Nested.Leaf {
+//     final class Inner extends Nested {}
+//
+//     static final class Leaf extends Nested {}
+//
+//     public static void main(String[] args) {
+//         Runnable anonymous = new Runnable() {
+//             public void run() {
+//                 System.out.println(getClass().getEnclosingMethod().getName());
+//             }
+//         };
+//         anonymous.run();
+//         System.out.println(Nested.class.getPermittedSubclasses().length);
+//         System.out.println(Leaf.class.getNestHost().getSimpleName());
+//         System.out.println(Inner.class.getModifiers());
+//     }
+// }
+// ```
+const CLASSES: [(&str, &[u8]); 4] = [
+    ("Nested", include_bytes!("./nested/Nested.class")),
+    ("Nested$1", include_bytes!("./nested/Nested$1.class")),
+    (
+        "Nested$Inner",
+        include_bytes!("./nested/Nested$Inner.class"),
+    ),
+    ("Nested$Leaf", include_bytes!("./nested/Nested$Leaf.class")),
+];
+
+fn strings(classes: &[&str]) -> Vec<String> {
+    classes.iter().map(|class| class.to_string()).collect()
+}
+
+#[test]
+fn nested_class_attributes_are_resolved() {
+    let nested = read(CLASSES[0].1);
+    let [Attribute::NestMembers(members), Attribute::PermittedSubclasses(permitted), Attribute::InnerClasses(inner_classes)] =
+        &nested.attributes[..]
+    else {
+        panic!("Unexpected attributes {:?}", nested.attributes)
+    };
+    assert_eq!(
+        *members,
+        strings(&["Nested$Leaf", "Nested$Inner", "Nested$1"])
+    );
+    assert_eq!(*permitted, strings(&["Nested$Inner", "Nested$Leaf"]));
+    assert_eq!(
+        *inner_classes,
+        vec![
+            InnerClass {
+                inner_class: "Nested$1".to_string(),
+                outer_class: None,
+                inner_name: None,
+                inner_class_access_flags: vec![],
+            },
+            InnerClass {
+                inner_class: "Nested$Leaf".to_string(),
+                outer_class: Some("Nested".to_string()),
+                inner_name: Some("Leaf".to_string()),
+                inner_class_access_flags: vec![
+                    InnerClassAccessFlags::STATIC,
+                    InnerClassAccessFlags::FINAL
+                ],
+            },
+            InnerClass {
+                inner_class: "Nested$Inner".to_string(),
+                outer_class: Some("Nested".to_string()),
+                inner_name: Some("Inner".to_string()),
+                inner_class_access_flags: vec![InnerClassAccessFlags::FINAL],
+            },
+        ]
+    );
+
+    let anonymous = read(CLASSES[1].1);
+    let enclosing_method = anonymous
+        .attributes
+        .iter()
+        .find_map(|attribute| match attribute {
+            Attribute::EnclosingMethod(enclosing_method) => Some(enclosing_method),
+            _ => None,
+        });
+    assert_eq!(
+        enclosing_method,
+        Some(&EnclosingMethod {
+            class: "Nested".to_string(),
+            method: Some(NameAndType {
+                name: "main".to_string(),
+                descriptor: "([Ljava/lang/String;)V".parse().unwrap(),
+            }),
+        })
+    );
+    assert!(anonymous
+        .attributes
+        .iter()
+        .any(|attribute| matches!(attribute, Attribute::NestHost(host) if host == "Nested")));
+}
+
+#[test]
+fn rewritten_nested_classes_run() {
+    // Without lossless mode, the constant pool is built from scratch
+    let directory = write_classes(CLASSES.map(|(name, bytes)| (name, write(read(bytes)))));
+    assert_eq!(run_java(&directory, "Nested"), "main\n2\nNested\n16\n");
+}
@@ -0,0 +1,258 @@
+use rusty_javap::bytecode::reader::{ByteReader, Take};
+use rusty_javap::bytecode::writer::ByteWriter;
+use rusty_javap::error::ClassFileError;
+use rusty_javap::model::attrs::code::OpcodeInfo::{
+    bipush, iinc, invokeinterface, invokestatic, ldc, ldc2_w, ldc_w, newarray, pop, pop2, r#return,
+    ret, sipush,
+};
+use rusty_javap::model::attrs::code::{
+    ArrayType, ClassRef, Code, Instruction, InterfaceMethodRef, MethodRef,
+};
+use rusty_javap::model::attrs::Attribute;
+use rusty_javap::model::class::{Class, ClassAccessModifier, Version};
+use rusty_javap::model::constant::{Loadable, MethodHandle, ReferenceKind};
+use rusty_javap::model::method::{Method, MethodAccessModifier};
+
+fn write(code: Vec<Instruction>) -> Vec<u8> {
+    let code = Code {
+        max_stack: 10,
+        max_locals: 10,
+        code,
+        exception_table: vec![],
+        attributes: vec![],
+    };
+    let class = Class {
+        version: Version::new(0xCAFEBABE, 49, 0),
+        access_flags: vec![ClassAccessModifier::PUBLIC],
+        this_class: "Operands".to_string(),
+        super_class: Some("java/lang/Object".to_string()),
+        interfaces: vec![],
+        fields: vec![],
+        methods: vec![Method {
+            access_flags: vec![MethodAccessModifier::STATIC],
+            name: "run".to_string(),
+            descriptor: "()V".parse().unwrap(),
+            attributes: vec![Attribute::Code(code)],
+        }],
+        attributes: vec![],
+        constant_pool: None,
+    };
+    let mut writer = ByteWriter::new();
+    writer.try_write(class).unwrap();
+    writer.into()
+}
+
+fn read(bytes: Vec<u8>) -> Vec<Instruction> {
+    let mut reader: ByteReader = bytes.into();
+    let class: Class = reader.take().unwrap();
+    match class
+        .methods
+        .into_iter()
+        .next()
+        .unwrap()
+        .attributes
+        .remove(0)
+    {
+        Attribute::Code(code) => code.code,
+        attribute => panic!("Expected Code, found {:?}", attribute),
+    }
+}
+
+fn contains(bytes: &[u8], expected: &[u8]) -> bool {
+    bytes
+        .windows(expected.len())
+        .any(|window| window == expected)
+}
+
+#[test]
+fn signed_operands_round_trip() {
+    let code: Vec<Instruction> = vec![
+        bipush { byte: -5 }.into(),
+        sipush { short: -300 }.into(),
+        iinc {
+            index: 1,
+            constant: -1,
+        }
+        .into(),
+        ret { index: 2 }.into(),
+        r#return.into(),
+    ];
+    let bytes = write(code.clone());
+    assert!(contains(
+        &bytes,
+        &[0x10, 0xfb, 0x11, 0xfe, 0xd4, 0x84, 0x01, 0xff, 0xa9, 0x02, 0xb1]
+    ));
+    assert_eq!(read(bytes), code);
+}
+
+#[test]
+fn invokeinterface_count_comes_from_the_descriptor() {
+    let code: Vec<Instruction> = vec![invokeinterface {
+        method: InterfaceMethodRef {
+            class: ClassRef("java/util/Map".to_string()),
+            name: "put".to_string(),
+            descriptor: "(Ljava/lang/Object;Ljava/lang/Object;)Ljava/lang/Object;"
+                .parse()
+                .unwrap(),
+            count: None,
+        },
+    }
+    .into()];
+    let bytes = write(code.clone());
+    // Index, then the receiver and 2 arguments, then zero
+    let opcode = bytes.iter().rposition(|&byte| byte == 0xb9).unwrap();
+    assert_eq!(bytes[opcode + 3..opcode + 5], [3, 0]);
+    assert_eq!(read(bytes), code);
+
+    let code: Vec<Instruction> = vec![invokeinterface {
+        method: InterfaceMethodRef {
+            class: ClassRef("Numbers".to_string()),
+            name: "apply".to_string(),
+            descriptor: "(J[DI[[JLjava/lang/String;D)V".parse().unwrap(),
+            count: None,
+        },
+    }
+    .into()];
+    let bytes = write(code);
+    let opcode = bytes.iter().rposition(|&byte| byte == 0xb9).unwrap();
+    assert_eq!(bytes[opcode + 3], 1 + 2 + 1 + 1 + 1 + 1 + 2);
+}
+
+#[test]
+fn invokeinterface_count_that_was_read_is_kept() {
+    let code: Vec<Instruction> = vec![invokeinterface {
+        method: InterfaceMethodRef {
+            class: ClassRef("java/lang/Runnable".to_string()),
+            name: "run".to_string(),
+            descriptor: "()V".parse().unwrap(),
+            count: Some(4),
+        },
+    }
+    .into()];
+    let bytes = write(code.clone());
+    let opcode = bytes.iter().rposition(|&byte| byte == 0xb9).unwrap();
+    assert_eq!(bytes[opcode + 3..opcode + 5], [4, 0]);
+    assert_eq!(read(bytes), code);
+}
+
+#[test]
+fn invokestatic_can_call_interface_methods() {
+    let code: Vec<Instruction> = vec![
+        invokestatic {
+            method: MethodRef {
+                class: ClassRef("java/util/List".to_string()),
+                name: "of".to_string(),
+                descriptor: "()Ljava/util/List;".parse().unwrap(),
+                interface: true,
+            },
+        }
+        .into(),
+        r#return.into(),
+    ];
+    assert_eq!(read(write(code.clone())), code);
+}
+
+#[test]
+fn newarray_type_is_symbolic() {
+    let code: Vec<Instruction> = vec![
+        newarray {
+            atype: ArrayType::T_INT,
+        }
+        .into(),
+        r#return.into(),
+    ];
+    assert_eq!(
+        serde_json::to_string(&code[0]).unwrap(),
+        r#"{"newarray":{"atype":"T_INT"}}"#
+    );
+    let mut bytes = write(code.clone());
+    assert_eq!(read(bytes.clone()), code);
+
+    let opcode = bytes.iter().rposition(|&byte| byte == 0xbc).unwrap();
+    bytes[opcode + 1] = 12;
+    let mut reader: ByteReader = bytes.into();
+    let result: Result<Class, _> = reader.take();
+    assert_eq!(
+        result.unwrap_err().error,
+        ClassFileError::InvalidArrayType { atype: 12 }
+    );
+}
+
+#[test]
+fn loadable_constants_round_trip() {
+    let constants = vec![
+        Loadable::Integer(-1),
+        Loadable::Float(0.5),
+        Loadable::String("text".into()),
+        Loadable::Class("[I".to_string()),
+        Loadable::MethodType("(I)V".parse().unwrap()),
+        Loadable::MethodHandle(MethodHandle {
+            kind: ReferenceKind::REF_invokeStatic,
+            class: "java/util/List".to_string(),
+            name: "of".to_string(),
+            descriptor: "()Ljava/util/List;".to_string(),
+            interface: true,
+        }),
+        Loadable::MethodHandle(MethodHandle {
+            kind: ReferenceKind::REF_getField,
+            class: "Operands".to_string(),
+            name: "field".to_string(),
+            descriptor: "J".to_string(),
+            interface: false,
+        }),
+    ];
+    let mut code: Vec<Instruction> = vec![];
+    for constant in constants {
+        code.push(ldc { constant }.into());
+        code.push(pop.into());
+    }
+    for constant in [Loadable::Long(-1), Loadable::Double(0.25)] {
+        code.push(ldc2_w { constant }.into());
+        code.push(pop2.into());
+    }
+    code.push(r#return.into());
+    assert_eq!(read(write(code.clone())), code);
+}
+
+#[test]
+fn ldc_is_widened_when_the_index_does_not_fit() {
+    let mut code: Vec<Instruction> = vec![];
+    for int in 0..300 {
+        code.push(
+            ldc {
+                constant: Loadable::Integer(int),
+            }
+            .into(),
+        );
+        code.push(pop.into());
+    }
+    code.push(
+        ldc {
+            constant: Loadable::Long(1),
+        }
+        .into(),
+    );
+    code.push(pop2.into());
+    code.push(r#return.into());
+
+    let read = read(write(code));
+    let opcodes: Vec<&str> = read
+        .iter()
+        .filter_map(|instruction| match instruction {
+            Instruction::Op(ldc { .. }) => Some("ldc"),
+            Instruction::Op(ldc_w { .. }) => Some("ldc_w"),
+            Instruction::Op(ldc2_w { .. }) => Some("ldc2_w"),
+            _ => None,
+        })
+        .collect();
+    let narrow = opcodes.iter().filter(|&&opcode| opcode == "ldc").count();
+    assert!(narrow > 200, "only {} narrow ldc", narrow);
+    assert_eq!(opcodes[narrow..299], vec!["ldc_w"; 299 - narrow]);
+    assert_eq!(opcodes[300], "ldc2_w");
+    assert_eq!(
+        read[2 * 299],
+        Instruction::Op(ldc_w {
+            constant: Loadable::Integer(299)
+        })
+    );
+}
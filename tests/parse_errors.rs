@@ -0,0 +1,84 @@
+use rusty_javap::bytecode::reader::{ByteReader, Take};
+use rusty_javap::error::{ClassFileError, ParseError};
+use rusty_javap::model::class::Class;
+
+fn parse(bytes: Vec<u8>) -> Result<Class, ParseError> {
+    let mut reader: ByteReader = bytes.into();
+    reader.take()
+}
+
+/// Offset of the `<init>` code, which starts with `aload_0; invokespecial #1`
+fn init_code_offset(bytes: &[u8]) -> usize {
+    bytes
+        .windows(4)
+        .position(|window| window == [0x2a, 0xb7, 0x00, 0x01])
+        .expect("Couldn't find <init> code")
+}
+
+#[test]
+fn bad_magic() {
+    let mut bytes = include_bytes!("./Example.class").to_vec();
+    bytes[0] = 0;
+    let error = parse(bytes).unwrap_err();
+    assert_eq!(error.error, ClassFileError::BadMagic { found: 0x00FEBABE });
+    assert_eq!(error.location.offset, 0);
+    assert_eq!(error.location.path, vec!["version"]);
+}
+
+#[test]
+fn truncated() {
+    let bytes = include_bytes!("./Example.class");
+    let error = parse(bytes[..bytes.len() - 1].to_vec()).unwrap_err();
+    assert!(matches!(error.error, ClassFileError::UnexpectedEof { .. }));
+}
+
+#[test]
+fn bad_constant_index() {
+    let mut bytes = include_bytes!("./Example.class").to_vec();
+    let code_offset = init_code_offset(&bytes);
+    bytes[code_offset + 2] = 0xFF;
+    bytes[code_offset + 3] = 0xFF;
+    let error = parse(bytes).unwrap_err();
+    assert_eq!(
+        error.error,
+        ClassFileError::InvalidConstantIndex { index: 0xFFFF }
+    );
+    assert_eq!(error.location.offset, code_offset + 2);
+    assert_eq!(
+        error.location.path.join("."),
+        "methods[0].attributes[Code].code[pc=1]"
+    );
+}
+
+#[test]
+fn invalid_opcode() {
+    let mut bytes = include_bytes!("./Example.class").to_vec();
+    let code_offset = init_code_offset(&bytes);
+    bytes[code_offset] = 0xFE;
+    let error = parse(bytes).unwrap_err();
+    assert_eq!(
+        error.error,
+        ClassFileError::InvalidOpcode { pc: 0, byte: 0xFE }
+    );
+    assert_eq!(error.location.offset, code_offset);
+}
+
+#[test]
+fn invalid_descriptor() {
+    let mut bytes = include_bytes!("./Example.class").to_vec();
+    // The `Utf8` constant `()V`, the descriptor of `<init>`
+    let descriptor_offset = bytes
+        .windows(6)
+        .position(|window| window == [0x01, 0x00, 0x03, b'(', b')', b'V'])
+        .expect("Couldn't find ()V");
+    bytes[descriptor_offset + 5] = b'X';
+    let error = parse(bytes).unwrap_err();
+    assert_eq!(
+        error.error,
+        ClassFileError::InvalidDescriptor {
+            descriptor: "()X".to_string(),
+            position: 2
+        }
+    );
+    assert_eq!(error.location.path, vec!["methods[0]"]);
+}
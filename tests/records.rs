@@ -0,0 +1,78 @@
+mod common;
+
+use common::{read, run_java, write, write_classes};
+use rusty_javap::model::attrs::annotations::{Annotation, ElementValue, ElementValuePair};
+use rusty_javap::model::attrs::Attribute;
+use rusty_javap::model::class::Class;
+
+// Compiled with `javac --release 17 -g:none` from
+// ```java
+// public record Point(int x, @Point.Named("labels") List<String> labels) {
+//     @Retention(RetentionPolicy.RUNTIME)
+//     @interface Named {
+//         String value();
+//     }
+//
+//     public static void main(String[] args) {
+//         System.out.println(Point.class.isRecord());
+//         for (RecordComponent component : Point.class.getRecordComponents()) {
+//             System.out.println(component.getGenericType() + " " + component.getName() + " "
+//                     + component.getAnnotation(Named.class));
+//         }
+//         System.out.println(new Point(1, List.of("a")));
+//     }
+// }
+// ```
+const CLASSES: [(&str, &[u8]); 2] = [
+    ("Point", include_bytes!("./records/Point.class")),
+    ("Point$Named", include_bytes!("./records/Point$Named.class")),
+];
+
+#[test]
+fn record_components_are_resolved() {
+    let point: Class = read(CLASSES[0].1);
+    assert!(point.is_record());
+    let components = point.record_components().unwrap();
+    let names: Vec<(&str, String)> = components
+        .iter()
+        .map(|component| (component.name.as_str(), component.descriptor.encode()))
+        .collect();
+    assert_eq!(
+        names,
+        [
+            ("x", "I".to_string()),
+            ("labels", "Ljava/util/List;".to_string())
+        ]
+    );
+    assert!(components[0].attributes.is_empty());
+    let [Attribute::Signature(signature), Attribute::RuntimeVisibleAnnotations(annotations)] =
+        &components[1].attributes[..]
+    else {
+        panic!("Unexpected attributes {:?}", components[1].attributes)
+    };
+    assert_eq!(signature, "Ljava/util/List<Ljava/lang/String;>;");
+    assert_eq!(
+        *annotations,
+        vec![Annotation {
+            descriptor: "LPoint$Named;".to_string(),
+            elements: vec![ElementValuePair {
+                name: "value".to_string(),
+                value: ElementValue::String("labels".into()),
+            }],
+        }]
+    );
+
+    let named: Class = read(CLASSES[1].1);
+    assert!(!named.is_record());
+    assert!(named.record_components().is_none());
+}
+
+#[test]
+fn rewritten_record_runs() {
+    // Without lossless mode, the constant pool is built from scratch
+    let directory = write_classes(CLASSES.map(|(name, bytes)| (name, write(read(bytes)))));
+    assert_eq!(
+        run_java(&directory, "Point"),
+        "true\nint x null\njava.util.List<java.lang.String> labels @Point$Named(\"labels\")\nPoint[x=1, labels=[a]]\n"
+    );
+}
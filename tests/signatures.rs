@@ -0,0 +1,182 @@
+mod common;
+
+use common::{read, run_java, write, write_classes};
+use rusty_javap::model::attrs::Attribute;
+use rusty_javap::model::descriptor::BaseType;
+use rusty_javap::model::signature::{
+    ClassSignature, FieldSignature, JavaTypeSignature, MethodSignature, ReferenceTypeSignature,
+    TypeArgument, TypeParameter,
+};
+
+// Compiled with `javac --release 17 -g:none` from
+// ```java
+// public class Generics<T extends Comparable<? super T>> extends AbstractList<T>
+//         implements Supplier<Map<String, List<? extends T>>> {
+//     Map<String, List<? extends T>> field;
+//
+//     public T get(int index) { return null; }
+//
+//     public int size() { return 0; }
+//
+//     public Map<String, List<? extends T>> get() { return field; }
+//
+//     static <E extends Exception> void fail(E[] exceptions) throws E {}
+//
+//     public static void main(String[] args) throws ReflectiveOperationException {
+//         System.out.println(Arrays.toString(Generics.class.getTypeParameters()[0].getBounds()));
+//         System.out.println(Generics.class.getGenericSuperclass());
+//         System.out.println(Arrays.toString(Generics.class.getGenericInterfaces()));
+//         System.out.println(Generics.class.getDeclaredField("field").getGenericType());
+//         System.out.println(Generics.class.getDeclaredMethod("fail", Exception[].class).toGenericString());
+//     }
+// }
+// ```
+const GENERICS: &[u8] = include_bytes!("./signatures/Generics.class");
+
+fn signature(attributes: &mut [Attribute]) -> &mut String {
+    attributes
+        .iter_mut()
+        .find_map(|attribute| match attribute {
+            Attribute::Signature(signature) => Some(signature),
+            _ => None,
+        })
+        .unwrap()
+}
+
+fn type_variable(name: &str) -> ReferenceTypeSignature {
+    ReferenceTypeSignature::TypeVariable(name.to_string())
+}
+
+#[test]
+fn signatures_are_resolved() {
+    let mut class = read(GENERICS);
+
+    let class_signature = ClassSignature::parse(signature(&mut class.attributes)).unwrap();
+    assert_eq!(
+        class_signature.to_string(),
+        "<T extends java.lang.Comparable<? super T>> extends java.util.AbstractList<T> \
+         implements java.util.function.Supplier<java.util.Map<java.lang.String, java.util.List<? extends T>>>"
+    );
+    let [TypeParameter {
+        name,
+        class_bound: None,
+        interface_bounds,
+    }] = &class_signature.type_parameters[..]
+    else {
+        panic!(
+            "Unexpected type parameters {:?}",
+            class_signature.type_parameters
+        )
+    };
+    assert_eq!(name, "T");
+    let [ReferenceTypeSignature::Class(comparable)] = &interface_bounds[..] else {
+        panic!("Unexpected bounds {:?}", interface_bounds)
+    };
+    assert_eq!(comparable.package, "java/lang");
+    assert_eq!(comparable.class.name, "Comparable");
+    assert_eq!(
+        comparable.class.type_arguments,
+        [TypeArgument::Super(type_variable("T"))]
+    );
+
+    let field = FieldSignature::parse(signature(&mut class.fields[0].attributes)).unwrap();
+    assert_eq!(format!("{:#}", field), "Map<String, List<? extends T>>");
+
+    let fail = class
+        .methods
+        .iter_mut()
+        .find(|method| method.name == "fail")
+        .unwrap();
+    let fail = MethodSignature::parse(signature(&mut fail.attributes)).unwrap();
+    assert_eq!(
+        fail.parameters,
+        [JavaTypeSignature::Reference(ReferenceTypeSignature::Array(
+            Box::new(JavaTypeSignature::Reference(type_variable("E")))
+        ))]
+    );
+    assert_eq!(fail.result, None);
+    assert_eq!(fail.throws, [type_variable("E")]);
+    assert_eq!(
+        fail.to_string(),
+        "<E extends java.lang.Exception> void (E[]) throws E"
+    );
+
+    let get = class
+        .methods
+        .iter_mut()
+        .find(|method| {
+            method.name == "get" && method.descriptor.encode() == "(I)Ljava/lang/Comparable;"
+        })
+        .unwrap();
+    let get = MethodSignature::parse(signature(&mut get.attributes)).unwrap();
+    assert_eq!(get.parameters, [JavaTypeSignature::Base(BaseType::Int)]);
+    assert_eq!(
+        get.result,
+        Some(JavaTypeSignature::Reference(type_variable("T")))
+    );
+}
+
+#[test]
+fn rewritten_signatures_are_reflected() {
+    let mut class = read(GENERICS);
+    // `Map<String, List<? super T>> field`
+    let field_signature = signature(&mut class.fields[0].attributes);
+    let mut field = FieldSignature::parse(field_signature).unwrap();
+    let ReferenceTypeSignature::Class(map) = &mut field else {
+        panic!("Unexpected field signature {:?}", field)
+    };
+    let TypeArgument::Exact(ReferenceTypeSignature::Class(list)) = &mut map.class.type_arguments[1]
+    else {
+        panic!("Unexpected type arguments {:?}", map.class.type_arguments)
+    };
+    list.class.type_arguments = vec![TypeArgument::Super(type_variable("T"))];
+    *field_signature = field.encode();
+
+    // Without lossless mode, the constant pool is built from scratch
+    let directory = write_classes([("Generics", write(class))]);
+    assert_eq!(
+        run_java(&directory, "Generics"),
+        "[java.lang.Comparable<? super T>]
+java.util.AbstractList<T>
+[java.util.function.Supplier<java.util.Map<java.lang.String, java.util.List<? extends T>>>]
+java.util.Map<java.lang.String, java.util.List<? super T>>
+static <E extends java.lang.Exception> void Generics.fail(E[]) throws E
+"
+    );
+}
+
+#[test]
+fn deep_signatures_are_rejected() {
+    let dimensions = |count: usize| "[".repeat(count);
+    assert_eq!(
+        FieldSignature::parse(&format!("{}I", dimensions(60_000))),
+        Err(255)
+    );
+    // Dimensions add up through type arguments
+    let list = "Ljava/util/List<";
+    assert_eq!(
+        FieldSignature::parse(&format!("[{}{}I>;", list, dimensions(255))),
+        Err(1 + list.len() + 254)
+    );
+
+    // `List<List<...>>`, nested deeper than the limit
+    let nested = |depth: usize| format!("{}TT;{}", list.repeat(depth), ">;".repeat(depth));
+    assert_eq!(
+        FieldSignature::parse(&nested(60_000)),
+        Err(32 * list.len() + list.len() - 1)
+    );
+
+    // As deep as allowed
+    let deepest = format!(
+        "{}{}TT;{}",
+        dimensions(23),
+        format!("{}{}", list, dimensions(7)).repeat(32),
+        ">;".repeat(32)
+    );
+    let parsed = FieldSignature::parse(&deepest).unwrap();
+    assert_eq!(parsed.encode(), deepest);
+    let displayed = parsed.to_string();
+    assert!(displayed.contains("<T[][][][][][][]>[][][][][][][]>"));
+    assert!(displayed.ends_with(&format!(">{}", "[]".repeat(23))));
+    assert_eq!(parsed.clone(), parsed);
+}
@@ -0,0 +1,241 @@
+mod common;
+
+use common::{read, run_java, write, write_classes};
+use rusty_javap::bytecode::writer::ByteWriter;
+use rusty_javap::error::ClassFileError;
+use rusty_javap::model::attrs::code::OpcodeInfo::{
+    aload_0, arraylength, dup, getstatic, goto, ifeq, invokespecial, invokevirtual, ldc, new, nop,
+    pop, r#return, swap,
+};
+use rusty_javap::model::attrs::code::{ClassRef, Code, FieldRef, Instruction, Label, MethodRef};
+use rusty_javap::model::attrs::stack_map_table::StackMapFrame::*;
+use rusty_javap::model::attrs::stack_map_table::{StackMapFrame, StackMapTable, VerificationType};
+use rusty_javap::model::attrs::Attribute;
+use rusty_javap::model::class::{Class, ClassAccessModifier, Version};
+use rusty_javap::model::constant::Loadable;
+use rusty_javap::model::method::{Method, MethodAccessModifier};
+use std::iter::repeat_n;
+
+fn print(text: &str) -> Vec<Instruction> {
+    vec![
+        ldc {
+            constant: Loadable::String(text.into()),
+        }
+        .into(),
+        getstatic {
+            field: FieldRef {
+                class: ClassRef("java/lang/System".to_string()),
+                name: "out".to_string(),
+                descriptor: "Ljava/io/PrintStream;".parse().unwrap(),
+            },
+        }
+        .into(),
+        swap.into(),
+        invokevirtual {
+            method: MethodRef {
+                class: ClassRef("java/io/PrintStream".to_string()),
+                name: "println".to_string(),
+                descriptor: "(Ljava/lang/String;)V".parse().unwrap(),
+                interface: false,
+            },
+        }
+        .into(),
+    ]
+}
+
+const NONE: Label = Label(1000);
+const END: Label = Label(1001);
+const OBJECT: Label = Label(1002);
+
+/// `if (args.length == 0) print("none") else print("some"); new Object();`
+fn main_code(padding: usize) -> Vec<Instruction> {
+    let mut code: Vec<Instruction> = repeat_n(nop.into(), padding).collect();
+    code.extend([
+        aload_0.into(),
+        arraylength.into(),
+        ifeq { branch: NONE }.into(),
+    ]);
+    code.extend(print("some"));
+    code.push(goto { branch: END }.into());
+    code.push(NONE.into());
+    code.extend(print("none"));
+    code.extend([END.into(), nop.into(), OBJECT.into()]);
+    code.extend([
+        new {
+            class: ClassRef("java/lang/Object".to_string()),
+        }
+        .into(),
+        dup.into(),
+        invokespecial {
+            method: MethodRef {
+                class: ClassRef("java/lang/Object".to_string()),
+                name: "<init>".to_string(),
+                descriptor: "()V".parse().unwrap(),
+                interface: false,
+            },
+        }
+        .into(),
+        pop.into(),
+        r#return.into(),
+    ]);
+    code
+}
+
+fn class(code: Vec<Instruction>, frames: StackMapTable) -> Class {
+    let code = Code {
+        max_stack: 3,
+        max_locals: 1,
+        code,
+        exception_table: vec![],
+        attributes: vec![Attribute::StackMapTable(frames)],
+    };
+    Class {
+        version: Version::new(0xCAFEBABE, 52, 0),
+        access_flags: vec![ClassAccessModifier::PUBLIC],
+        this_class: "Frames".to_string(),
+        super_class: Some("java/lang/Object".to_string()),
+        interfaces: vec![],
+        fields: vec![],
+        methods: vec![Method {
+            access_flags: vec![MethodAccessModifier::PUBLIC, MethodAccessModifier::STATIC],
+            name: "main".to_string(),
+            descriptor: "([Ljava/lang/String;)V".parse().unwrap(),
+            attributes: vec![Attribute::Code(code)],
+        }],
+        attributes: vec![],
+        constant_pool: None,
+    }
+}
+
+fn read_code(bytes: Vec<u8>) -> Code {
+    match read(&bytes)
+        .methods
+        .into_iter()
+        .next()
+        .unwrap()
+        .attributes
+        .remove(0)
+    {
+        Attribute::Code(code) => code,
+        attribute => panic!("Expected Code, found {:?}", attribute),
+    }
+}
+
+fn run(bytes: Vec<u8>) -> String {
+    run_java(&write_classes([("Frames", bytes)]), "Frames")
+}
+
+#[test]
+fn frames_follow_inserted_instructions() {
+    let frames = || vec![same_frame { label: NONE }, same_frame { label: END }];
+    assert_eq!(run(write(class(main_code(0), frames()))), "none\n");
+
+    // Pushes the first frame's offset out of reach of same_frame
+    let bytes = write(class(main_code(100), frames()));
+    assert_eq!(run(bytes.clone()), "none\n");
+    assert!(matches!(
+        read_code(bytes).attributes.as_slice(),
+        [Attribute::StackMapTable(frames)]
+            if matches!(frames.as_slice(), [same_frame_extended { .. }, same_frame { .. }])
+    ));
+}
+
+/// Frames of every kind, which don't have to make sense for the verifier
+fn all_frames(none: Label, end: Label, object: Label) -> Vec<StackMapTable> {
+    use VerificationType::*;
+    vec![
+        vec![
+            same_frame_extended { label: none },
+            append_frame {
+                label: end,
+                locals: vec![Long, Object("[I".to_string())],
+            },
+            full_frame {
+                label: object,
+                locals: vec![Top, UninitializedThis],
+                stack: vec![Uninitialized(object), Null, Double],
+            },
+        ],
+        vec![
+            same_locals_1_stack_item_frame {
+                label: none,
+                stack: Integer,
+            },
+            same_locals_1_stack_item_frame_extended {
+                label: end,
+                stack: Float,
+            },
+            chop_frame {
+                label: object,
+                chopped: 2,
+            },
+        ],
+    ]
+}
+
+#[test]
+fn every_frame_kind_round_trips() {
+    for (i, frames) in all_frames(NONE, END, OBJECT).into_iter().enumerate() {
+        let read = read_code(write(class(main_code(0), frames)));
+        // Labels read from the class file are named after their pc
+        let labels: Vec<Label> = read
+            .code
+            .iter()
+            .filter_map(|instruction| match instruction {
+                Instruction::Label { label } => Some(*label),
+                _ => None,
+            })
+            .collect();
+        let [none, end, object] = labels[..] else {
+            panic!("Expected 3 labels, found {:?}", labels)
+        };
+        match &read.attributes[..] {
+            [Attribute::StackMapTable(frames)] => {
+                assert_eq!(*frames, all_frames(none, end, object).remove(i))
+            }
+            attributes => panic!("Expected StackMapTable, found {:?}", attributes),
+        }
+    }
+}
+
+#[test]
+fn chop_and_append_frames_need_1_to_3_locals() {
+    for (json, count) in [
+        (r#"{"chop_frame": {"label": 1000, "chopped": 0}}"#, 0),
+        (r#"{"chop_frame": {"label": 1000, "chopped": 4}}"#, 4),
+        (r#"{"chop_frame": {"label": 1000, "chopped": 252}}"#, 252),
+        (r#"{"append_frame": {"label": 1000, "locals": []}}"#, 0),
+        (
+            r#"{"append_frame": {"label": 1000, "locals": ["Top", "Top", "Top", "Top"]}}"#,
+            4,
+        ),
+        (
+            r#"{"append_frame": {"label": 1000, "locals": ["Top", "Top", "Top", "Top", "Top"]}}"#,
+            5,
+        ),
+    ] {
+        let frame: StackMapFrame = serde_json::from_str(json).unwrap();
+        let mut writer = ByteWriter::new();
+        assert_eq!(
+            writer.try_write(class(main_code(0), vec![frame])),
+            Err(ClassFileError::InvalidFrameLocals { count }),
+            "{}",
+            json
+        );
+    }
+}
+
+#[test]
+fn frames_have_to_be_in_order() {
+    // `END` is at pc 26, `NONE` at pc 17
+    for frames in [
+        vec![same_frame { label: END }, same_frame { label: NONE }],
+        vec![same_frame { label: NONE }, same_frame { label: NONE }],
+    ] {
+        let mut writer = ByteWriter::new();
+        assert_eq!(
+            writer.try_write(class(main_code(0), frames)),
+            Err(ClassFileError::FramesOutOfOrder { pc: 17 })
+        );
+    }
+}
@@ -0,0 +1,175 @@
+use rusty_javap::bytecode::reader::{ByteReader, Take};
+use rusty_javap::bytecode::writer::ByteWriter;
+use rusty_javap::error::ClassFileError;
+use rusty_javap::model::attrs::code::OpcodeInfo::{
+    iconst_0, iconst_1, ireturn, lookupswitch, nop, tableswitch, wide,
+};
+use rusty_javap::model::attrs::code::{
+    Code, Instruction, Label, LookupSwitch, TableSwitch, WideInstruction,
+};
+use rusty_javap::model::attrs::Attribute;
+use rusty_javap::model::class::{Class, ClassAccessModifier, Version};
+use rusty_javap::model::method::{Method, MethodAccessModifier};
+
+fn try_write(code: Vec<Instruction>) -> Result<Vec<u8>, ClassFileError> {
+    let code = Code {
+        max_stack: 1,
+        max_locals: 400,
+        code,
+        exception_table: vec![],
+        attributes: vec![],
+    };
+    let class = Class {
+        version: Version::new(0xCAFEBABE, 49, 0),
+        access_flags: vec![ClassAccessModifier::PUBLIC],
+        this_class: "Switches".to_string(),
+        super_class: Some("java/lang/Object".to_string()),
+        interfaces: vec![],
+        fields: vec![],
+        methods: vec![Method {
+            access_flags: vec![MethodAccessModifier::STATIC],
+            name: "run".to_string(),
+            descriptor: "()I".parse().unwrap(),
+            attributes: vec![Attribute::Code(code)],
+        }],
+        attributes: vec![],
+        constant_pool: None,
+    };
+    let mut writer = ByteWriter::new();
+    writer.try_write(class)?;
+    Ok(writer.into())
+}
+
+fn write(code: Vec<Instruction>) -> Vec<u8> {
+    try_write(code).unwrap()
+}
+
+fn read(bytes: Vec<u8>) -> Vec<Instruction> {
+    let mut reader: ByteReader = bytes.into();
+    let class: Class = reader.take().unwrap();
+    match class
+        .methods
+        .into_iter()
+        .next()
+        .unwrap()
+        .attributes
+        .remove(0)
+    {
+        Attribute::Code(code) => code.code,
+        attribute => panic!("Expected Code, found {:?}", attribute),
+    }
+}
+
+/// `padding` nops, then a switch on 0 whose cases all return 1 and whose default returns 0
+fn switch_code(padding: usize, switch: impl Fn(Label, Label) -> Instruction) -> Vec<Instruction> {
+    let (case, default) = (Label(1000), Label(2000));
+    let mut code: Vec<Instruction> = vec![nop.into(); padding];
+    code.push(iconst_0.into());
+    code.push(switch(case, default));
+    code.extend([case.into(), iconst_1.into(), ireturn.into()]);
+    code.extend([default.into(), iconst_0.into(), ireturn.into()]);
+    code
+}
+
+#[test]
+fn switches_are_aligned_whatever_their_pc() {
+    let switches: [fn(Label, Label) -> Instruction; 2] = [
+        |case, default| {
+            tableswitch {
+                table: TableSwitch {
+                    default,
+                    low: -1,
+                    targets: vec![case, default, case],
+                },
+            }
+            .into()
+        },
+        |case, default| {
+            lookupswitch {
+                table: LookupSwitch {
+                    default,
+                    pairs: vec![(70_000, case), (-5, case)],
+                },
+            }
+            .into()
+        },
+    ];
+    for switch in switches {
+        for padding in 0..4 {
+            let bytes = write(switch_code(padding, switch));
+            let code = read(bytes.clone());
+            // Operands start at a multiple of 4, after the opcode at `padding + 1`
+            let switch_size = match &code[padding + 1] {
+                Instruction::Op(tableswitch { .. }) => 12 + 3 * 4,
+                Instruction::Op(lookupswitch { .. }) => 8 + 2 * 8,
+                instruction => panic!("Expected a switch, found {:?}", instruction),
+            };
+            let case_pc = padding + 2 + (3 - (padding + 1) % 4) + switch_size;
+            assert_eq!(code[padding + 2], Label(case_pc as u32).into());
+            assert_eq!(write(code), bytes);
+        }
+    }
+}
+
+#[test]
+fn tableswitch_bounds_have_to_fit_an_int() {
+    for (low, targets, high) in [(0, 0, -1), (i32::MAX, 2, i32::MAX as i64 + 1)] {
+        let code = switch_code(0, |case, default| {
+            tableswitch {
+                table: TableSwitch {
+                    default,
+                    low,
+                    targets: vec![case; targets],
+                },
+            }
+            .into()
+        });
+        assert_eq!(
+            try_write(code),
+            Err(ClassFileError::InvalidSwitchBounds { low, high })
+        );
+    }
+}
+
+#[test]
+fn lookupswitch_pairs_are_sorted() {
+    let code = read(write(switch_code(0, |case, default| {
+        lookupswitch {
+            table: LookupSwitch {
+                default,
+                pairs: vec![(3, default), (1, case), (2, default)],
+            },
+        }
+        .into()
+    })));
+    match &code[1] {
+        Instruction::Op(lookupswitch { table }) => {
+            let keys: Vec<i32> = table.pairs.iter().map(|(key, _)| *key).collect();
+            assert_eq!(keys, vec![1, 2, 3]);
+        }
+        instruction => panic!("Expected lookupswitch, found {:?}", instruction),
+    }
+}
+
+#[test]
+fn wide_instructions_round_trip() {
+    let code: Vec<Instruction> = vec![
+        wide {
+            instruction: WideInstruction::iinc {
+                index: 300,
+                constant: -1000,
+            },
+        }
+        .into(),
+        wide {
+            instruction: WideInstruction::iload { index: 300 },
+        }
+        .into(),
+        ireturn.into(),
+    ];
+    let bytes = write(code.clone());
+    assert!(bytes
+        .windows(6)
+        .any(|window| window == [0xc4, 0x84, 0x01, 0x2c, 0xfc, 0x18]));
+    assert_eq!(read(bytes), code);
+}
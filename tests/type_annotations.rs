@@ -0,0 +1,184 @@
+mod common;
+
+use common::{read, run_java, write, write_classes};
+use rusty_javap::model::attrs::annotations::Annotation;
+use rusty_javap::model::attrs::code::{Code, Label, OpcodeInfo::nop};
+use rusty_javap::model::attrs::type_annotations::TypeAnnotationTarget::*;
+use rusty_javap::model::attrs::type_annotations::{
+    LocalVariableTarget, TypeAnnotation, TypeAnnotationTarget, TypePath, TypePathElement,
+};
+use rusty_javap::model::attrs::Attribute;
+use rusty_javap::model::class::Class;
+
+// Compiled with `javac --release 17 -g:none` from
+// ```java
+// public class TypeAnnotated<@TypeAnnotated.Nullable T> {
+//     @Retention(RetentionPolicy.RUNTIME)
+//     @Target(ElementType.TYPE_USE)
+//     @interface Nullable {}
+//
+//     public List<@Nullable String> @Nullable [] field;
+//
+//     static @Nullable Object code(Object object) {
+//         @Nullable List<String> list = new @Nullable ArrayList<>();
+//         if (object instanceof @Nullable String) {
+//             list.add((@Nullable String) object);
+//         }
+//         try {
+//             return list.get(0);
+//         } catch (@Nullable IndexOutOfBoundsException e) {
+//             return null;
+//         }
+//     }
+//
+//     public static void main(String[] args) {
+//         try {
+//             System.out.println(TypeAnnotated.class.getField("field").getAnnotatedType());
+//         } catch (NoSuchFieldException e) {
+//             throw new RuntimeException(e);
+//         }
+//         System.out.println(code("text"));
+//     }
+// }
+// ```
+const CLASSES: [(&str, &[u8]); 2] = [
+    (
+        "TypeAnnotated",
+        include_bytes!("./type_annotations/TypeAnnotated.class"),
+    ),
+    (
+        "TypeAnnotated$Nullable",
+        include_bytes!("./type_annotations/TypeAnnotated$Nullable.class"),
+    ),
+];
+
+fn nullable(target: TypeAnnotationTarget, target_path: TypePath) -> TypeAnnotation {
+    TypeAnnotation {
+        target,
+        target_path,
+        annotation: Annotation {
+            descriptor: "LTypeAnnotated$Nullable;".to_string(),
+            elements: vec![],
+        },
+    }
+}
+
+fn type_annotations(attributes: &[Attribute]) -> Vec<TypeAnnotation> {
+    attributes
+        .iter()
+        .filter_map(|attribute| match attribute {
+            Attribute::RuntimeVisibleTypeAnnotations(annotations) => Some(annotations.clone()),
+            _ => None,
+        })
+        .flatten()
+        .collect()
+}
+
+fn code_method(class: &mut Class) -> &mut Vec<Attribute> {
+    let method = class
+        .methods
+        .iter_mut()
+        .find(|method| method.name == "code")
+        .unwrap();
+    &mut method.attributes
+}
+
+fn code(attributes: &mut [Attribute]) -> &mut Code {
+    attributes
+        .iter_mut()
+        .find_map(|attribute| match attribute {
+            Attribute::Code(code) => Some(code),
+            _ => None,
+        })
+        .unwrap()
+}
+
+/// The type annotations of `code`'s instructions, if the code starts at [pc]
+fn code_annotations(pc: u32) -> Vec<TypeAnnotation> {
+    vec![
+        nullable(New { label: Label(pc) }, vec![]),
+        nullable(
+            InstanceOf {
+                label: Label(pc + 9),
+            },
+            vec![],
+        ),
+        nullable(
+            Cast {
+                label: Label(pc + 17),
+                type_argument_index: 0,
+            },
+            vec![],
+        ),
+        nullable(
+            LocalVariable {
+                table: vec![LocalVariableTarget {
+                    start: Label(pc + 8),
+                    end: Label(pc + 37),
+                    index: 1,
+                }],
+            },
+            vec![],
+        ),
+        nullable(
+            Catch {
+                exception_table_index: 0,
+            },
+            vec![],
+        ),
+    ]
+}
+
+#[test]
+fn type_annotations_are_resolved() {
+    let mut class = read(CLASSES[0].1);
+    assert_eq!(
+        type_annotations(&class.attributes),
+        vec![nullable(
+            ClassTypeParameter {
+                type_parameter_index: 0
+            },
+            vec![]
+        )]
+    );
+    assert_eq!(
+        type_annotations(&class.fields[0].attributes),
+        vec![
+            nullable(Field, vec![]),
+            nullable(
+                Field,
+                vec![TypePathElement::Array, TypePathElement::TypeArgument(0)]
+            ),
+        ]
+    );
+    let attributes = code_method(&mut class);
+    assert_eq!(type_annotations(attributes), vec![nullable(Return, vec![])]);
+    assert_eq!(
+        type_annotations(&code(attributes).attributes),
+        code_annotations(0)
+    );
+}
+
+#[test]
+fn code_targets_follow_edits() {
+    let directory = write_classes(CLASSES.map(|(name, bytes)| {
+        // Without lossless mode, the constant pool is built from scratch
+        let mut class = read(bytes);
+        if name == "TypeAnnotated" {
+            code(code_method(&mut class)).code.insert(0, nop.into());
+        }
+        let written = write(class);
+        if name == "TypeAnnotated" {
+            let mut class = read(&written);
+            assert_eq!(
+                type_annotations(&code(code_method(&mut class)).attributes),
+                code_annotations(1)
+            );
+        }
+        (name, written)
+    }));
+    assert_eq!(
+        run_java(&directory, "TypeAnnotated"),
+        "java.util.List<@TypeAnnotated$Nullable() java.lang.String> @TypeAnnotated$Nullable()[]\ntext\n"
+    );
+}
@@ -0,0 +1,176 @@
+use rusty_javap::analysis::hierarchy::Classes;
+use rusty_javap::analysis::verifier::verify;
+use rusty_javap::analysis::{AnalysisError, TypeError};
+use rusty_javap::bytecode::reader::{ByteReader, Take};
+use rusty_javap::bytecode::writer::ByteWriter;
+use rusty_javap::model::attrs::code::OpcodeInfo::{
+    aload_0, areturn, fconst_0, fstore_0, ifeq, iload_0, ldc, pop, r#return,
+};
+use rusty_javap::model::attrs::code::{Code, Instruction, Label};
+use rusty_javap::model::attrs::stack_map_table::StackMapFrame::*;
+use rusty_javap::model::attrs::stack_map_table::{StackMapTable, VerificationType::*};
+use rusty_javap::model::attrs::Attribute;
+use rusty_javap::model::class::{Class, ClassAccessModifier, Version};
+use rusty_javap::model::constant::Loadable;
+use rusty_javap::model::field::{Field, FieldAccessModifier};
+use rusty_javap::model::method::{Method, MethodAccessModifier};
+
+const EXAMPLE: &[u8] = include_bytes!("./Example.class");
+
+#[test]
+fn javacs_code_verifies() {
+    let mut reader = ByteReader::from(EXAMPLE.to_vec());
+    let class: Class = reader.take().unwrap();
+    let mut hierarchy = Classes::new();
+    hierarchy.add(&class);
+    assert_eq!(verify(&class, &hierarchy), Ok(()));
+}
+
+const SKIP: Label = Label(1000);
+
+/// `static <descriptor> f` with [code] and [frames]
+fn class(descriptor: &str, code: Vec<Instruction>, frames: StackMapTable) -> Class {
+    let code = Code {
+        max_stack: 1,
+        max_locals: 1,
+        code,
+        exception_table: vec![],
+        attributes: vec![Attribute::StackMapTable(frames)],
+    };
+    Class {
+        version: Version::new(0xCAFEBABE, 52, 0),
+        access_flags: vec![ClassAccessModifier::PUBLIC],
+        this_class: "Verified".to_string(),
+        super_class: Some("java/lang/Object".to_string()),
+        interfaces: vec![],
+        fields: vec![],
+        methods: vec![Method {
+            access_flags: vec![MethodAccessModifier::STATIC],
+            name: "f".to_string(),
+            descriptor: descriptor.parse().unwrap(),
+            attributes: vec![Attribute::Code(code)],
+        }],
+        attributes: vec![],
+        constant_pool: None,
+    }
+}
+
+/// `if (i != 0) { float f = 0; }`
+fn branch_code() -> Vec<Instruction> {
+    vec![
+        iload_0.into(),
+        ifeq { branch: SKIP }.into(),
+        fconst_0.into(),
+        fstore_0.into(),
+        SKIP.into(),
+        r#return.into(),
+    ]
+}
+
+fn verify_error(class: &Class) -> AnalysisError {
+    let mut errors = verify(class, &Classes::new()).unwrap_err();
+    assert_eq!(errors.len(), 1);
+    errors.remove(0)
+}
+
+#[test]
+fn frames_are_checked() {
+    // Local 0 holds an int or a float, depending on the path
+    let frames = vec![chop_frame {
+        label: SKIP,
+        chopped: 1,
+    }];
+    let valid = class("(I)V", branch_code(), frames);
+    assert_eq!(verify(&valid, &Classes::new()), Ok(()));
+
+    // The branch's int can't be used as the frame's float
+    let frames = vec![full_frame {
+        label: SKIP,
+        locals: vec![Float],
+        stack: vec![],
+    }];
+    let error = verify_error(&class("(I)V", branch_code(), frames));
+    assert_eq!(error.method, "f(I)V");
+    assert_eq!(error.instruction, Some(1));
+    assert_eq!(error.pc, Some(1));
+    assert_eq!(
+        error.error,
+        TypeError::WrongLocalType {
+            index: 0,
+            expected: "float".to_string(),
+            found: Integer,
+        }
+    );
+    assert_eq!(
+        error.to_string(),
+        "f(I)V, pc 1 (instruction 1): Expected float in local 0, found Integer"
+    );
+
+    let error = verify_error(&class("(I)V", branch_code(), vec![]));
+    assert_eq!(error.pc, Some(1));
+    assert_eq!(error.error, TypeError::MissingFrame);
+}
+
+#[test]
+fn returns_are_checked() {
+    let code = vec![aload_0.into(), areturn.into()];
+    let error = verify_error(&class(
+        "(Ljava/lang/Object;)Ljava/lang/String;",
+        code,
+        vec![],
+    ));
+    assert_eq!(error.pc, Some(1));
+    assert_eq!(
+        error.error,
+        TypeError::WrongStackType {
+            expected: "java/lang/String".to_string(),
+            found: Object("java/lang/Object".to_string()),
+        }
+    );
+
+    let error = verify_error(&class("(I)I", vec![r#return.into()], vec![]));
+    assert_eq!(
+        error.error,
+        TypeError::WrongReturn {
+            expected: "int".to_string(),
+            found: "void".to_string(),
+        }
+    );
+}
+
+#[test]
+fn pcs_are_located_with_the_class_constant_pool() {
+    // The string comes after the fields' names in the pool, too far for `ldc`
+    let code = vec![
+        ldc {
+            constant: Loadable::String("x".into()),
+        }
+        .into(),
+        pop.into(),
+        pop.into(),
+        r#return.into(),
+    ];
+    let mut class = class("()V", code, vec![]);
+    class.fields = (0..300)
+        .map(|i| {
+            Field::new(
+                vec![FieldAccessModifier::STATIC],
+                format!("field{}", i),
+                "I".parse().unwrap(),
+                vec![],
+            )
+        })
+        .collect();
+    // Without a pool of the class' own, the `ldc` is laid out with a pool holding only the string
+    let error = verify_error(&class);
+    assert_eq!(error.error, TypeError::StackUnderflow);
+    assert_eq!(error.pc, Some(3));
+
+    let mut writer = ByteWriter::new();
+    writer.try_write(class).unwrap();
+    let mut reader = ByteReader::from(Vec::<u8>::from(writer)).lossless();
+    let class: Class = reader.take().unwrap();
+    let error = verify_error(&class);
+    assert_eq!(error.instruction, Some(2));
+    assert_eq!(error.pc, Some(4));
+}